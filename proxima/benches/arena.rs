@@ -0,0 +1,50 @@
+//! Traversal and evaluation of a large expression tree, boxed and stored
+//! in an arena:
+//!
+//! ```sh
+//! cargo bench --bench arena
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use proxima::{arena::ExpressionArena, interner::PathId, interpreter::Interpreter, parser::Parser};
+
+const TERMS: usize = 10_000;
+
+fn expression_source() -> String {
+    (0..TERMS)
+        .map(|idx| format!("{idx} * -{idx}"))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn traversal(c: &mut Criterion) {
+    let source = expression_source();
+    let expression = Parser::new(PathId::from("bench.lzr"), &source)
+        .parse_expression()
+        .unwrap();
+    let mut arena = ExpressionArena::new();
+    let id = arena.alloc_expression(&expression).unwrap();
+
+    c.bench_function("boxed node count", |b| {
+        b.iter(|| black_box(expression.node_count()));
+    });
+    c.bench_function("arena node count", |b| {
+        b.iter(|| black_box(arena.node_count(id)));
+    });
+
+    c.bench_function("boxed eval", |b| {
+        b.iter(|| black_box(Interpreter::new().eval_expression(&expression).unwrap()));
+    });
+    c.bench_function("arena eval", |b| {
+        b.iter(|| {
+            black_box(
+                Interpreter::new()
+                    .eval_arena_expression(&arena, id)
+                    .unwrap(),
+            )
+        });
+    });
+}
+
+criterion_group!(benches, traversal);
+criterion_main!(benches);
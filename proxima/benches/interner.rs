@@ -0,0 +1,28 @@
+//! Compares the global `Mutex` interner with the thread-local one:
+//!
+//! ```sh
+//! cargo bench --bench interner
+//! cargo bench --bench interner --features single-threaded
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use proxima::interner::IdentifierId;
+
+const IDENTIFIERS: usize = 100_000;
+
+fn intern_identifiers(c: &mut Criterion) {
+    let names = (0..IDENTIFIERS)
+        .map(|idx| format!("identifier_{}", idx % (IDENTIFIERS / 10)))
+        .collect::<Vec<_>>();
+
+    c.bench_function("intern 100k identifiers", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(IdentifierId::from(name));
+            }
+        });
+    });
+}
+
+criterion_group!(benches, intern_identifiers);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+//! Evaluation of a long straight-line block and of a deep tail-recursive
+//! function:
+//!
+//! ```sh
+//! cargo bench --bench interpreter
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use proxima::{interpreter::Interpreter, parser::parse};
+
+const STATEMENTS: usize = 10_000;
+
+fn eval_long_block(c: &mut Criterion) {
+    let source = "i = 0;\n".to_owned() + &"i += 1;\n".repeat(STATEMENTS - 1);
+    let program = parse("bench.lzr", &source).unwrap();
+
+    c.bench_function("eval 10k statements", |b| {
+        b.iter(|| black_box(Interpreter::new().eval_block(&program).unwrap()));
+    });
+}
+
+fn eval_tail_recursion(c: &mut Criterion) {
+    let program = parse(
+        "bench.lzr",
+        "func count(n) { if n == 0 { return 0; } return count(n - 1); } count(10000);",
+    )
+    .unwrap();
+
+    c.bench_function("eval 10k tail calls", |b| {
+        b.iter(|| black_box(Interpreter::new().eval_block(&program).unwrap()));
+    });
+}
+
+criterion_group!(benches, eval_long_block, eval_tail_recursion);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+use std::{env, process::Command};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(has_cold_path)");
+
+    // `core::hint::cold_path` is stable since Rust 1.95.
+    if rustc_minor_version().is_some_and(|minor| minor >= 95) {
+        println!("cargo:rustc-cfg=has_cold_path");
+    }
+}
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+
+    // rustc 1.95.0 (59807616e 2026-04-14)
+    version
+        .split_whitespace()
+        .nth(1)?
+        .split('.')
+        .nth(1)?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,282 @@
+//! Expression trees stored contiguously in an arena, with children
+//! referenced by [`ExpressionId`] instead of being boxed. Nodes are
+//! allocated in post-order, so the whole subtree of a node directly
+//! precedes it in memory.
+//!
+//! The parser still builds boxed trees, which are then copied into the
+//! arena with [`ExpressionArena::alloc_expression`]. Only operator
+//! expressions are supported for now, which are the ones long chains and
+//! deep nesting come from, e.g. `a + b * -c[0]`.
+
+use crate::{
+    ast::Expression,
+    interner::IdentifierId,
+    interpreter::{
+        eval_binary_operation, eval_unary_operation, short_circuits, Interpreter, RuntimeError,
+    },
+    location::{HasLocation, Location},
+    token::Punctuator,
+    value::Value,
+};
+
+/// Index of a node in an [`ExpressionArena`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExpressionId(u32);
+
+/// Node of an expression tree stored in an [`ExpressionArena`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArenaExpression {
+    Literal(Value),
+    Identifier(IdentifierId),
+    Unary {
+        operator: Punctuator,
+        operand: ExpressionId,
+    },
+    Binary {
+        left: ExpressionId,
+        operator: Punctuator,
+        right: ExpressionId,
+    },
+    Index {
+        object: ExpressionId,
+        index: ExpressionId,
+    },
+}
+
+/// Storage of expression trees, see the [module documentation](self).
+/// Nodes of several trees can share one arena, each tree is identified by
+/// the [`ExpressionId`] of its root.
+#[derive(Clone, Debug, Default)]
+pub struct ExpressionArena {
+    nodes: Vec<ArenaExpression>,
+    locations: Vec<Location>,
+}
+
+impl ExpressionArena {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of nodes in the arena, of all the trees in it.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// # Panics
+    /// Panics if the node is not from this arena.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, id: ExpressionId) -> &ArenaExpression {
+        &self.nodes[id.0 as usize]
+    }
+
+    /// # Panics
+    /// Panics if the node is not from this arena.
+    #[inline]
+    #[must_use]
+    pub fn location(&self, id: ExpressionId) -> Location {
+        self.locations[id.0 as usize]
+    }
+
+    /// Adds node, whose children must already be in the arena.
+    ///
+    /// # Panics
+    /// Panics if the arena already holds `u32::MAX` nodes.
+    pub fn alloc(&mut self, node: ArenaExpression, location: Location) -> ExpressionId {
+        let id = ExpressionId(u32::try_from(self.nodes.len()).expect("arena is full"));
+
+        self.nodes.push(node);
+        self.locations.push(location);
+
+        id
+    }
+
+    /// Copies the boxed tree into the arena. Returns `None` if the tree
+    /// contains an expression other than a literal, an identifier,
+    /// a unary, a binary or an index expression, leaving the arena as it
+    /// was before the call.
+    pub fn alloc_expression(&mut self, expression: &Expression) -> Option<ExpressionId> {
+        let len = self.nodes.len();
+        let id = self.alloc_subtree(expression);
+
+        if id.is_none() {
+            self.nodes.truncate(len);
+            self.locations.truncate(len);
+        }
+
+        id
+    }
+
+    fn alloc_subtree(&mut self, expression: &Expression) -> Option<ExpressionId> {
+        let node = match expression {
+            Expression::Literal(literal) => ArenaExpression::Literal(literal.value().clone()),
+            Expression::Identifier(identifier) => ArenaExpression::Identifier(identifier.name()),
+            Expression::Unary(unary) => ArenaExpression::Unary {
+                operator: unary.operator(),
+                operand: self.alloc_subtree(unary.operand())?,
+            },
+            Expression::Binary(binary) => ArenaExpression::Binary {
+                left: self.alloc_subtree(binary.left())?,
+                operator: binary.operator(),
+                right: self.alloc_subtree(binary.right())?,
+            },
+            Expression::Index(index) => ArenaExpression::Index {
+                object: self.alloc_subtree(index.object())?,
+                index: self.alloc_subtree(index.index())?,
+            },
+            _ => return None,
+        };
+
+        Some(self.alloc(node, expression.location()))
+    }
+
+    /// Returns the number of nodes in the tree, like
+    /// [`Expression::node_count`] does for the boxed one.
+    #[must_use]
+    pub fn node_count(&self, id: ExpressionId) -> usize {
+        1 + match self.get(id) {
+            ArenaExpression::Literal(_) | ArenaExpression::Identifier(_) => 0,
+            ArenaExpression::Unary { operand, .. } => self.node_count(*operand),
+            ArenaExpression::Binary { left, right, .. } => {
+                self.node_count(*left) + self.node_count(*right)
+            }
+            ArenaExpression::Index { object, index } => {
+                self.node_count(*object) + self.node_count(*index)
+            }
+        }
+    }
+}
+
+impl Interpreter {
+    /// Evaluates expression stored in the arena, with the same semantics
+    /// as [`Interpreter::eval_expression`] has for the boxed tree.
+    ///
+    /// # Errors
+    /// Returns [`RuntimeError`] if evaluation of the expression fails.
+    pub fn eval_arena_expression(
+        &mut self,
+        arena: &ExpressionArena,
+        id: ExpressionId,
+    ) -> Result<Value, RuntimeError> {
+        match arena.get(id) {
+            ArenaExpression::Literal(value) => Ok(value.clone()),
+            ArenaExpression::Identifier(name) => self.lookup(*name).cloned(),
+            ArenaExpression::Unary { operator, operand } => {
+                let operand = self.eval_arena_expression(arena, *operand)?;
+                eval_unary_operation(*operator, &operand)
+            }
+            ArenaExpression::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.eval_arena_expression(arena, *left)?;
+
+                match short_circuits(*operator, &left) {
+                    Some(true) => return Ok(left),
+                    Some(false) => return self.eval_arena_expression(arena, *right),
+                    None => {}
+                }
+
+                let right = self.eval_arena_expression(arena, *right)?;
+
+                eval_binary_operation(&left, *operator, &right)
+            }
+            ArenaExpression::Index { object, index } => {
+                let object = self.eval_arena_expression(arena, *object)?;
+                let index = self.eval_arena_expression(arena, *index)?;
+
+                object.index(&index)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interner::PathId, parser::Parser};
+
+    fn parse_expression(source: &str) -> Expression {
+        Parser::new(PathId::from("test.lzr"), source)
+            .parse_expression()
+            .unwrap()
+    }
+
+    #[test]
+    fn same_result_as_boxed_tree() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_block(&crate::parser::parse("test.lzr", "a = 3; s = \"xyz\";").unwrap())
+            .unwrap();
+
+        for source in [
+            "1 + 2 * -3 ** 2",
+            "(a << 2) - a % 2 >= 10 && s[1] == \"y\"",
+            "false && undefined || null ?? a / 2",
+            "s[a]",
+            "-(a - 4.5) * 2",
+        ] {
+            let expression = parse_expression(source);
+            let mut arena = ExpressionArena::new();
+            let id = arena.alloc_expression(&expression).unwrap();
+
+            assert_eq!(arena.len(), expression.node_count(), "{source}");
+            assert_eq!(arena.node_count(id), expression.node_count(), "{source}");
+            assert_eq!(arena.location(id), expression.location(), "{source}");
+            assert_eq!(
+                interpreter.eval_arena_expression(&arena, id),
+                interpreter.eval_expression(&expression),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn children_precede_parents() {
+        let mut arena = ExpressionArena::new();
+        let id = arena.alloc_expression(&parse_expression("a + -b")).unwrap();
+
+        assert_eq!(id, ExpressionId(3));
+        assert!(matches!(
+            arena.get(ExpressionId(0)),
+            ArenaExpression::Identifier(_)
+        ));
+        assert!(matches!(
+            arena.get(ExpressionId(2)),
+            ArenaExpression::Unary {
+                operand: ExpressionId(1),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn unsupported_expressions() {
+        let mut arena = ExpressionArena::new();
+        let id = arena.alloc_expression(&parse_expression("a")).unwrap();
+
+        assert_eq!(arena.alloc_expression(&parse_expression("1 + f()")), None);
+        assert_eq!(arena.alloc_expression(&parse_expression("a = 1")), None);
+        assert_eq!(
+            arena.alloc_expression(&parse_expression("-b[0] * c()")),
+            None
+        );
+        assert_eq!(arena.len(), 1);
+        assert_eq!(
+            arena.alloc_expression(&parse_expression("b")),
+            Some(ExpressionId(1))
+        );
+        assert!(matches!(arena.get(id), ArenaExpression::Identifier(_)));
+    }
+}
@@ -1,5 +1,19 @@
+//! Syntax tree produced by the parser.
+//!
+//! Every node implements [`HasLocation`]. Nodes that start or end with
+//! a token of their own, e.g. a keyword or a closing delimiter, store their
+//! span explicitly. Infix nodes, [`BinaryExpression`] and
+//! [`AssignmentExpression`], compute it from their operands with
+//! [`Location::to`] instead, as the operands already cover both ends.
+//! Parentheses are not kept in the tree, so spans of parenthesized
+//! expressions exclude them.
+
+use std::{slice, vec};
+
 use crate::{
+    interner::{IdentifierId, PathId},
     location::{HasLocation, Location},
+    token::Punctuator,
     value::Value,
 };
 
@@ -30,6 +44,34 @@ impl HasLocation for Literal {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct IdentifierExpression {
+    name: IdentifierId,
+    location: Location,
+}
+
+impl IdentifierExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(name: IdentifierId, location: Location) -> Self {
+        Self { name, location }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> IdentifierId {
+        self.name
+    }
+}
+
+impl HasLocation for IdentifierExpression {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Array literal, e.g. `[1, a + 2]`.
 #[derive(Debug, Clone)]
 pub struct ArrayExpression {
     elements: Vec<Expression>,
@@ -48,6 +90,31 @@ impl ArrayExpression {
     pub fn elements(&self) -> &[Expression] {
         &self.elements
     }
+
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, Expression> {
+        self.elements.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ArrayExpression {
+    type Item = &'a Expression;
+    type IntoIter = slice::Iter<'a, Expression>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for ArrayExpression {
+    type Item = Expression;
+    type IntoIter = vec::IntoIter<Expression>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.into_iter()
+    }
 }
 
 impl HasLocation for ArrayExpression {
@@ -60,197 +127,1607 @@ impl HasLocation for ArrayExpression {
 #[derive(Debug, Clone)]
 pub struct BinaryExpression {
     left: Box<Expression>,
+    operator: Punctuator,
     right: Box<Expression>,
 }
 
-impl HasLocation for BinaryExpression {
-    fn location(&self) -> Location {
-        Location::new(self.left.location().start(), self.right.location().end())
+impl BinaryExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(left: Box<Expression>, operator: Punctuator, right: Box<Expression>) -> Self {
+        Self {
+            left,
+            operator,
+            right,
+        }
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct BreakExpression(Location);
+    #[inline]
+    #[must_use]
+    pub const fn left(&self) -> &Expression {
+        &self.left
+    }
 
-impl BreakExpression {
     #[inline]
     #[must_use]
-    pub const fn new(location: Location) -> Self {
-        Self(location)
+    pub const fn operator(&self) -> Punctuator {
+        self.operator
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn right(&self) -> &Expression {
+        &self.right
     }
 }
 
-impl HasLocation for BreakExpression {
+/// Spans from the start of the left operand to the end of the right one.
+impl HasLocation for BinaryExpression {
     fn location(&self) -> Location {
-        self.0
+        self.left.location().to(self.right.location())
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct WhileExpression {
-    condition: Box<Expression>,
-    body: Box<Expression>,
+pub struct CallExpression {
+    callee: Box<Expression>,
+    arguments: Vec<Expression>,
     location: Location,
 }
 
-impl WhileExpression {
+impl CallExpression {
     #[inline]
     #[must_use]
     pub const fn new(
-        condition: Box<Expression>,
-        body: Box<Expression>,
+        callee: Box<Expression>,
+        arguments: Vec<Expression>,
         location: Location,
     ) -> Self {
         Self {
-            condition,
-            body,
+            callee,
+            arguments,
             location,
         }
     }
 
     #[inline]
     #[must_use]
-    pub const fn condition(&self) -> &Expression {
-        &self.condition
+    pub const fn callee(&self) -> &Expression {
+        &self.callee
     }
 
     #[inline]
     #[must_use]
-    pub const fn body(&self) -> &Expression {
-        &self.body
+    pub fn arguments(&self) -> &[Expression] {
+        &self.arguments
     }
 }
 
-impl HasLocation for WhileExpression {
+impl HasLocation for CallExpression {
     #[inline]
     fn location(&self) -> Location {
         self.location
     }
 }
 
+/// Object construction expression, e.g. `new Foo(1, 2)`.
 #[derive(Debug, Clone)]
-pub enum Expression {
-    Literal(Literal),
-    Binary(BinaryExpression),
-    Break(BreakExpression),
-    Block(StatementsBlock),
-    While(WhileExpression),
+pub struct NewExpression {
+    class: IdentifierId,
+    arguments: Vec<Expression>,
+    location: Location,
 }
 
-impl HasLocation for Expression {
+impl NewExpression {
     #[inline]
-    fn location(&self) -> Location {
-        match self {
-            Self::Literal(literal) => literal.location(),
-            Self::Binary(binary) => binary.location(),
-            Self::Break(break_) => break_.location(),
-            Self::Block(block) => block.location(),
-            Self::While(while_) => while_.location(),
+    #[must_use]
+    pub const fn new(class: IdentifierId, arguments: Vec<Expression>, location: Location) -> Self {
+        Self {
+            class,
+            arguments,
+            location,
         }
     }
+
+    #[inline]
+    #[must_use]
+    pub const fn class(&self) -> IdentifierId {
+        self.class
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn arguments(&self) -> &[Expression] {
+        &self.arguments
+    }
+}
+
+impl HasLocation for NewExpression {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
 }
 
+/// Member access expression, e.g. `point.x`.
 #[derive(Debug, Clone)]
-pub struct ExpressionStatement {
-    expression: Expression,
+pub struct MemberExpression {
+    object: Box<Expression>,
+    member: IdentifierId,
     location: Location,
 }
 
-impl ExpressionStatement {
+impl MemberExpression {
     #[inline]
     #[must_use]
-    pub const fn new(expression: Expression, location: Location) -> Self {
+    pub const fn new(object: Box<Expression>, member: IdentifierId, location: Location) -> Self {
         Self {
-            expression,
+            object,
+            member,
             location,
         }
     }
 
     #[inline]
     #[must_use]
-    pub const fn expression(&self) -> &Expression {
-        &self.expression
+    pub const fn object(&self) -> &Expression {
+        &self.object
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn member(&self) -> IdentifierId {
+        self.member
     }
 }
 
-impl HasLocation for ExpressionStatement {
+impl HasLocation for MemberExpression {
     #[inline]
     fn location(&self) -> Location {
         self.location
     }
 }
 
+/// Index expression, e.g. `array[0]`.
 #[derive(Debug, Clone)]
-pub struct ReturnStatement {
-    expression: Expression,
+pub struct IndexExpression {
+    object: Box<Expression>,
+    index: Box<Expression>,
     location: Location,
 }
 
-impl ReturnStatement {
+impl IndexExpression {
     #[inline]
     #[must_use]
-    pub const fn new(expression: Expression, location: Location) -> Self {
+    pub const fn new(object: Box<Expression>, index: Box<Expression>, location: Location) -> Self {
         Self {
-            expression,
+            object,
+            index,
             location,
         }
     }
 
     #[inline]
     #[must_use]
-    pub const fn expression(&self) -> &Expression {
-        &self.expression
+    pub const fn object(&self) -> &Expression {
+        &self.object
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> &Expression {
+        &self.index
     }
 }
 
-impl HasLocation for ReturnStatement {
+impl HasLocation for IndexExpression {
     #[inline]
     fn location(&self) -> Location {
         self.location
     }
 }
 
+/// Prefix unary expression, e.g. `-a` or `!done`.
+///
+/// Number literals are never negative: the lexer scans `-5` as `-`
+/// followed by `5`, so the parser produces unary expression here. Use
+/// [`crate::const_eval::fold_expression`] to turn it into a literal.
 #[derive(Debug, Clone)]
-pub enum Statement {
-    Expression(Expression),
-    Return(ReturnStatement),
+pub struct UnaryExpression {
+    operator: Punctuator,
+    operand: Box<Expression>,
+    location: Location,
 }
 
-impl HasLocation for Statement {
+impl UnaryExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(operator: Punctuator, operand: Box<Expression>, location: Location) -> Self {
+        Self {
+            operator,
+            operand,
+            location,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn operator(&self) -> Punctuator {
+        self.operator
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn operand(&self) -> &Expression {
+        &self.operand
+    }
+}
+
+impl HasLocation for UnaryExpression {
+    #[inline]
     fn location(&self) -> Location {
-        match self {
-            Self::Expression(expression) => expression.location(),
-            Self::Return(return_) => return_.location(),
+        self.location
+    }
+}
+
+/// Assignment expression, e.g. `a = 1`.
+#[derive(Debug, Clone)]
+pub struct AssignmentExpression {
+    target: Box<Expression>,
+    operator: Punctuator,
+    value: Box<Expression>,
+}
+
+impl AssignmentExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        target: Box<Expression>,
+        operator: Punctuator,
+        value: Box<Expression>,
+    ) -> Self {
+        Self {
+            target,
+            operator,
+            value,
         }
     }
+
+    #[inline]
+    #[must_use]
+    pub const fn target(&self) -> &Expression {
+        &self.target
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn operator(&self) -> Punctuator {
+        self.operator
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn value(&self) -> &Expression {
+        &self.value
+    }
+}
+
+/// Spans from the start of the target to the end of the value.
+impl HasLocation for AssignmentExpression {
+    fn location(&self) -> Location {
+        self.target.location().to(self.value.location())
+    }
 }
 
+/// Exception raising expression, e.g. `throw error`.
 #[derive(Debug, Clone)]
-pub struct StatementsBlock {
-    statements: Vec<Statement>,
+pub struct ThrowExpression {
+    value: Box<Expression>,
     location: Location,
 }
 
-impl StatementsBlock {
+impl ThrowExpression {
     #[inline]
     #[must_use]
-    pub const fn new(statements: Vec<Statement>, location: Location) -> Self {
+    pub const fn new(value: Box<Expression>, location: Location) -> Self {
+        Self { value, location }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn value(&self) -> &Expression {
+        &self.value
+    }
+}
+
+impl HasLocation for ThrowExpression {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakExpression(Location);
+
+impl BreakExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(location: Location) -> Self {
+        Self(location)
+    }
+}
+
+impl HasLocation for BreakExpression {
+    fn location(&self) -> Location {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WhileExpression {
+    condition: Box<Expression>,
+    body: Box<Expression>,
+    location: Location,
+}
+
+impl WhileExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        condition: Box<Expression>,
+        body: Box<Expression>,
+        location: Location,
+    ) -> Self {
         Self {
-            statements,
+            condition,
+            body,
             location,
         }
     }
 
     #[inline]
     #[must_use]
-    pub fn statements(&self) -> &[Statement] {
-        &self.statements
+    pub const fn condition(&self) -> &Expression {
+        &self.condition
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn body(&self) -> &Expression {
+        &self.body
     }
 }
 
-impl HasLocation for StatementsBlock {
+impl HasLocation for WhileExpression {
     #[inline]
     fn location(&self) -> Location {
         self.location
     }
 }
+
+/// Conditional expression, e.g. `if a { b; } else { c; }`.
+#[derive(Debug, Clone)]
+pub struct IfExpression {
+    condition: Box<Expression>,
+    then_branch: Box<Expression>,
+    else_branch: Option<Box<Expression>>,
+    location: Location,
+}
+
+impl IfExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Option<Box<Expression>>,
+        location: Location,
+    ) -> Self {
+        Self {
+            condition,
+            then_branch,
+            else_branch,
+            location,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn condition(&self) -> &Expression {
+        &self.condition
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn then_branch(&self) -> &Expression {
+        &self.then_branch
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn else_branch(&self) -> Option<&Expression> {
+        self.else_branch.as_deref()
+    }
+}
+
+impl HasLocation for IfExpression {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Map literal, e.g. `{"a": 1, b: 2}`. Keys are arbitrary expressions.
+#[derive(Debug, Clone)]
+pub struct MapExpression {
+    entries: Vec<(Expression, Expression)>,
+    location: Location,
+}
+
+impl MapExpression {
+    #[inline]
+    #[must_use]
+    pub const fn new(entries: Vec<(Expression, Expression)>, location: Location) -> Self {
+        Self { entries, location }
+    }
+
+    /// Returns keys paired with values, in the source order.
+    #[inline]
+    #[must_use]
+    pub fn entries(&self) -> &[(Expression, Expression)] {
+        &self.entries
+    }
+}
+
+impl HasLocation for MapExpression {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Literal(Literal),
+    Identifier(IdentifierExpression),
+    Unary(UnaryExpression),
+    Binary(BinaryExpression),
+    Call(CallExpression),
+    New(NewExpression),
+    Member(MemberExpression),
+    Index(IndexExpression),
+    Assignment(AssignmentExpression),
+    Throw(ThrowExpression),
+    Break(BreakExpression),
+    Block(StatementsBlock),
+    While(WhileExpression),
+    If(IfExpression),
+    Map(MapExpression),
+    Array(ArrayExpression),
+}
+
+impl HasLocation for Expression {
+    #[inline]
+    fn location(&self) -> Location {
+        match self {
+            Self::Literal(literal) => literal.location(),
+            Self::Identifier(identifier) => identifier.location(),
+            Self::Unary(unary) => unary.location(),
+            Self::Binary(binary) => binary.location(),
+            Self::Call(call) => call.location(),
+            Self::New(new) => new.location(),
+            Self::Member(member) => member.location(),
+            Self::Index(index) => index.location(),
+            Self::Assignment(assignment) => assignment.location(),
+            Self::Throw(throw) => throw.location(),
+            Self::Break(break_) => break_.location(),
+            Self::Block(block) => block.location(),
+            Self::While(while_) => while_.location(),
+            Self::If(if_) => if_.location(),
+            Self::Map(map) => map.location(),
+            Self::Array(array) => array.location(),
+        }
+    }
+}
+
+impl Expression {
+    /// Returns `true` if a value can be assigned to the expression,
+    /// i.e. it is an identifier, member access or index expression.
+    #[inline]
+    #[must_use]
+    pub const fn is_lvalue(&self) -> bool {
+        matches!(self, Self::Identifier(_) | Self::Member(_) | Self::Index(_))
+    }
+
+    /// Returns `true` for expressions ending with a block, e.g. `while`,
+    /// which don't need a semicolon to be used as a statement.
+    #[inline]
+    #[must_use]
+    pub const fn is_block_like(&self) -> bool {
+        matches!(self, Self::Block(_) | Self::While(_) | Self::If(_))
+    }
+
+    /// Returns the number of nodes in the expression tree, including
+    /// statements of nested blocks.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        1 + match self {
+            Self::Literal(_) | Self::Identifier(_) | Self::Break(_) => 0,
+            Self::Unary(unary) => unary.operand().node_count(),
+            Self::Binary(binary) => binary.left().node_count() + binary.right().node_count(),
+            Self::Call(call) => {
+                call.callee().node_count()
+                    + call.arguments().iter().map(Self::node_count).sum::<usize>()
+            }
+            Self::New(new) => new.arguments().iter().map(Self::node_count).sum(),
+            Self::Member(member) => member.object().node_count(),
+            Self::Index(index) => index.object().node_count() + index.index().node_count(),
+            Self::Assignment(assignment) => {
+                assignment.target().node_count() + assignment.value().node_count()
+            }
+            Self::Throw(throw) => throw.value().node_count(),
+            Self::Block(block) => block.children_node_count(),
+            Self::While(while_) => while_.condition().node_count() + while_.body().node_count(),
+            Self::If(if_) => {
+                if_.condition().node_count()
+                    + if_.then_branch().node_count()
+                    + if_.else_branch().map_or(0, Self::node_count)
+            }
+            Self::Map(map) => map
+                .entries()
+                .iter()
+                .map(|(key, value)| key.node_count() + value.node_count())
+                .sum(),
+            Self::Array(array) => array.iter().map(Self::node_count).sum(),
+        }
+    }
+
+    /// Returns the length of the longest path from this node to a leaf,
+    /// e.g. a literal has depth 1.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        1 + match self {
+            Self::Literal(_) | Self::Identifier(_) | Self::Break(_) => 0,
+            Self::Unary(unary) => unary.operand().depth(),
+            Self::Binary(binary) => binary.left().depth().max(binary.right().depth()),
+            Self::Call(call) => call
+                .arguments()
+                .iter()
+                .map(Self::depth)
+                .fold(call.callee().depth(), usize::max),
+            Self::New(new) => new.arguments().iter().map(Self::depth).max().unwrap_or(0),
+            Self::Member(member) => member.object().depth(),
+            Self::Index(index) => index.object().depth().max(index.index().depth()),
+            Self::Assignment(assignment) => {
+                assignment.target().depth().max(assignment.value().depth())
+            }
+            Self::Throw(throw) => throw.value().depth(),
+            Self::Block(block) => block.children_depth(),
+            Self::While(while_) => while_.condition().depth().max(while_.body().depth()),
+            Self::If(if_) => if_
+                .condition()
+                .depth()
+                .max(if_.then_branch().depth())
+                .max(if_.else_branch().map_or(0, Self::depth)),
+            Self::Map(map) => map
+                .entries()
+                .iter()
+                .map(|(key, value)| key.depth().max(value.depth()))
+                .max()
+                .unwrap_or(0),
+            Self::Array(array) => array.iter().map(Self::depth).max().unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpressionStatement {
+    expression: Expression,
+    location: Location,
+}
+
+impl ExpressionStatement {
+    #[inline]
+    #[must_use]
+    pub const fn new(expression: Expression, location: Location) -> Self {
+        Self {
+            expression,
+            location,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn expression(&self) -> &Expression {
+        &self.expression
+    }
+}
+
+impl HasLocation for ExpressionStatement {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Return statement, e.g. `return a;`, or `return;` without a value,
+/// which returns `null`.
+#[derive(Debug, Clone)]
+pub struct ReturnStatement {
+    expression: Option<Expression>,
+    location: Location,
+}
+
+impl ReturnStatement {
+    #[inline]
+    #[must_use]
+    pub const fn new(expression: Option<Expression>, location: Location) -> Self {
+        Self {
+            expression,
+            location,
+        }
+    }
+
+    /// Returns the returned value, or `None` for `return;`.
+    #[inline]
+    #[must_use]
+    pub const fn expression(&self) -> Option<&Expression> {
+        self.expression.as_ref()
+    }
+}
+
+impl HasLocation for ReturnStatement {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Output statement, e.g. `println a, b;`. Values are printed separated
+/// by spaces, and `println` adds a line break after them.
+#[derive(Debug, Clone)]
+pub struct PrintStatement {
+    arguments: Vec<Expression>,
+    is_println: bool,
+    location: Location,
+}
+
+impl PrintStatement {
+    #[inline]
+    #[must_use]
+    pub const fn new(arguments: Vec<Expression>, is_println: bool, location: Location) -> Self {
+        Self {
+            arguments,
+            is_println,
+            location,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn arguments(&self) -> &[Expression] {
+        &self.arguments
+    }
+
+    /// Returns `true` for `println`, which adds a line break.
+    #[inline]
+    #[must_use]
+    pub const fn is_println(&self) -> bool {
+        self.is_println
+    }
+}
+
+impl HasLocation for PrintStatement {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Type written after a parameter or a function signature, e.g. `int`
+/// in `a: int`. Types are parsed, but not checked yet.
+#[derive(Copy, Clone, Debug)]
+pub struct TypeAnnotation {
+    name: IdentifierId,
+    location: Location,
+}
+
+impl TypeAnnotation {
+    #[inline]
+    #[must_use]
+    pub const fn new(name: IdentifierId, location: Location) -> Self {
+        Self { name, location }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> IdentifierId {
+        self.name
+    }
+}
+
+impl HasLocation for TypeAnnotation {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Function declaration, e.g. `func add(a, b) { return a + b; }`, or with
+/// type annotations, e.g. `func add(a: int, b: int) -> int { ... }`.
+#[derive(Debug, Clone)]
+pub struct FunctionDeclaration {
+    name: IdentifierId,
+    parameters: Vec<IdentifierId>,
+    /// Types of the parameters, by their index. Empty if none of
+    /// the parameters is annotated.
+    parameter_types: Vec<Option<TypeAnnotation>>,
+    return_type: Option<TypeAnnotation>,
+    body: StatementsBlock,
+    location: Location,
+}
+
+impl FunctionDeclaration {
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        name: IdentifierId,
+        parameters: Vec<IdentifierId>,
+        body: StatementsBlock,
+        location: Location,
+    ) -> Self {
+        Self {
+            name,
+            parameters,
+            parameter_types: Vec::new(),
+            return_type: None,
+            body,
+            location,
+        }
+    }
+
+    /// Sets types of the parameters, by their index, and the return type.
+    #[inline]
+    #[must_use]
+    pub fn with_types(
+        mut self,
+        parameter_types: Vec<Option<TypeAnnotation>>,
+        return_type: Option<TypeAnnotation>,
+    ) -> Self {
+        self.parameter_types = parameter_types;
+        self.return_type = return_type;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> IdentifierId {
+        self.name
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn parameters(&self) -> &[IdentifierId] {
+        &self.parameters
+    }
+
+    /// Returns type annotation of the parameter at `idx`, if it has one.
+    #[inline]
+    #[must_use]
+    pub fn parameter_type(&self, idx: usize) -> Option<TypeAnnotation> {
+        self.parameter_types.get(idx).copied().flatten()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn parameter_types(&self) -> &[Option<TypeAnnotation>] {
+        &self.parameter_types
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn return_type(&self) -> Option<TypeAnnotation> {
+        self.return_type
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn body(&self) -> &StatementsBlock {
+        &self.body
+    }
+}
+
+impl HasLocation for FunctionDeclaration {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Class declaration, e.g. `class Foo { func bar() {} x; }`.
+#[derive(Debug, Clone)]
+pub struct ClassDeclaration {
+    name: IdentifierId,
+    methods: Vec<FunctionDeclaration>,
+    fields: Vec<IdentifierId>,
+    location: Location,
+}
+
+impl ClassDeclaration {
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        name: IdentifierId,
+        methods: Vec<FunctionDeclaration>,
+        fields: Vec<IdentifierId>,
+        location: Location,
+    ) -> Self {
+        Self {
+            name,
+            methods,
+            fields,
+            location,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> IdentifierId {
+        self.name
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn methods(&self) -> &[FunctionDeclaration] {
+        &self.methods
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn fields(&self) -> &[IdentifierId] {
+        &self.fields
+    }
+}
+
+impl HasLocation for ClassDeclaration {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Comment attached to a statement, see [`Statement::leading_comments`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    location: Location,
+}
+
+impl Comment {
+    #[inline]
+    #[must_use]
+    pub const fn new(location: Location) -> Self {
+        Self { location }
+    }
+
+    /// Returns text of the comment including its delimiters, e.g. `// a`.
+    #[inline]
+    #[must_use]
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        source
+            .get(self.location.start().offset()..self.location.end().offset())
+            .unwrap_or_default()
+    }
+}
+
+impl HasLocation for Comment {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RawStatement {
+    Expression(Expression),
+    Return(ReturnStatement),
+    Print(PrintStatement),
+    Function(FunctionDeclaration),
+    Class(ClassDeclaration),
+}
+
+/// Statement together with comments around it. Comments are only attached
+/// when the parser is given trivia tokens, see
+/// [`crate::lexer::tokenize_with_trivia`].
+#[derive(Debug, Clone)]
+pub struct Statement {
+    raw: RawStatement,
+    leading_comments: Vec<Comment>,
+    trailing_comments: Vec<Comment>,
+}
+
+impl Statement {
+    #[inline]
+    #[must_use]
+    pub const fn new(raw: RawStatement) -> Self {
+        Self {
+            raw,
+            leading_comments: Vec::new(),
+            trailing_comments: Vec::new(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_comments(mut self, leading: Vec<Comment>, trailing: Vec<Comment>) -> Self {
+        self.leading_comments = leading;
+        self.trailing_comments = trailing;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn raw(&self) -> &RawStatement {
+        &self.raw
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_raw(self) -> RawStatement {
+        self.raw
+    }
+
+    /// Comments between the previous statement (or the opening brace) and
+    /// this one, e.g. a doc comment on the line above.
+    #[inline]
+    #[must_use]
+    pub fn leading_comments(&self) -> &[Comment] {
+        &self.leading_comments
+    }
+
+    /// Comments starting on the line the statement ends on, e.g. `a; // b`.
+    #[inline]
+    #[must_use]
+    pub fn trailing_comments(&self) -> &[Comment] {
+        &self.trailing_comments
+    }
+
+    /// Returns `true` if control never reaches the statement after this one
+    /// in the same block, i.e. for `return`, and `throw` and `break` used
+    /// as statements. Conditional jumps, e.g. `if a { return; }`, are not
+    /// terminators.
+    #[must_use]
+    pub const fn is_terminator(&self) -> bool {
+        matches!(
+            self.raw,
+            RawStatement::Return(_)
+                | RawStatement::Expression(Expression::Throw(_) | Expression::Break(_))
+        )
+    }
+
+    /// See [`Expression::node_count`].
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        1 + match &self.raw {
+            RawStatement::Expression(expression) => expression.node_count(),
+            RawStatement::Return(return_) => return_.expression().map_or(0, Expression::node_count),
+            RawStatement::Print(print) => {
+                print.arguments().iter().map(Expression::node_count).sum()
+            }
+            RawStatement::Function(function) => function.body().children_node_count(),
+            RawStatement::Class(class) => class
+                .methods()
+                .iter()
+                .map(|method| 1 + method.body().children_node_count())
+                .sum(),
+        }
+    }
+
+    /// See [`Expression::depth`].
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        1 + match &self.raw {
+            RawStatement::Expression(expression) => expression.depth(),
+            RawStatement::Return(return_) => return_.expression().map_or(0, Expression::depth),
+            RawStatement::Print(print) => print
+                .arguments()
+                .iter()
+                .map(Expression::depth)
+                .max()
+                .unwrap_or(0),
+            RawStatement::Function(function) => function.body().children_depth(),
+            RawStatement::Class(class) => class
+                .methods()
+                .iter()
+                .map(|method| 1 + method.body().children_depth())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl From<RawStatement> for Statement {
+    #[inline]
+    fn from(raw: RawStatement) -> Self {
+        Self::new(raw)
+    }
+}
+
+/// Location of the statement itself, without the attached comments.
+impl HasLocation for Statement {
+    fn location(&self) -> Location {
+        match &self.raw {
+            RawStatement::Expression(expression) => expression.location(),
+            RawStatement::Return(return_) => return_.location(),
+            RawStatement::Print(print) => print.location(),
+            RawStatement::Function(function) => function.location(),
+            RawStatement::Class(class) => class.location(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StatementsBlock {
+    statements: Vec<Statement>,
+    location: Location,
+}
+
+impl StatementsBlock {
+    #[inline]
+    #[must_use]
+    pub const fn new(statements: Vec<Statement>, location: Location) -> Self {
+        Self {
+            statements,
+            location,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, Statement> {
+        self.statements.iter()
+    }
+
+    fn children_node_count(&self) -> usize {
+        self.statements.iter().map(Statement::node_count).sum()
+    }
+
+    fn children_depth(&self) -> usize {
+        self.statements
+            .iter()
+            .map(Statement::depth)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl<'a> IntoIterator for &'a StatementsBlock {
+    type Item = &'a Statement;
+    type IntoIter = slice::Iter<'a, Statement>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for StatementsBlock {
+    type Item = Statement;
+    type IntoIter = vec::IntoIter<Statement>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.into_iter()
+    }
+}
+
+impl HasLocation for StatementsBlock {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+/// Moves spans of a node and all its children to another file, e.g. when
+/// the tree of an `include`d file is inlined into the including one.
+pub trait RemapPath {
+    fn remap_path(&mut self, path: PathId);
+}
+
+macro_rules! remap_location {
+    ($($node:ty),*) => {
+        $(
+            impl RemapPath for $node {
+                fn remap_path(&mut self, path: PathId) {
+                    self.location = self.location.with_path(path);
+                }
+            }
+        )*
+    };
+}
+
+remap_location!(Literal, IdentifierExpression, TypeAnnotation);
+
+impl RemapPath for ArrayExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.elements.remap_path(path);
+    }
+}
+
+impl RemapPath for BinaryExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.left.remap_path(path);
+        self.right.remap_path(path);
+    }
+}
+
+impl RemapPath for CallExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.callee.remap_path(path);
+        self.arguments.remap_path(path);
+    }
+}
+
+impl RemapPath for NewExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.arguments.remap_path(path);
+    }
+}
+
+impl RemapPath for UnaryExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.operand.remap_path(path);
+    }
+}
+
+impl RemapPath for MemberExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.object.remap_path(path);
+    }
+}
+
+impl RemapPath for IndexExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.object.remap_path(path);
+        self.index.remap_path(path);
+    }
+}
+
+impl RemapPath for AssignmentExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.target.remap_path(path);
+        self.value.remap_path(path);
+    }
+}
+
+impl RemapPath for ThrowExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.value.remap_path(path);
+    }
+}
+
+impl RemapPath for BreakExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.0 = self.0.with_path(path);
+    }
+}
+
+impl RemapPath for WhileExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.condition.remap_path(path);
+        self.body.remap_path(path);
+    }
+}
+
+impl RemapPath for IfExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.condition.remap_path(path);
+        self.then_branch.remap_path(path);
+        self.else_branch.remap_path(path);
+    }
+}
+
+impl RemapPath for Expression {
+    fn remap_path(&mut self, path: PathId) {
+        match self {
+            Self::Literal(literal) => literal.remap_path(path),
+            Self::Identifier(identifier) => identifier.remap_path(path),
+            Self::Binary(binary) => binary.remap_path(path),
+            Self::Call(call) => call.remap_path(path),
+            Self::New(new) => new.remap_path(path),
+            Self::Unary(unary) => unary.remap_path(path),
+            Self::Member(member) => member.remap_path(path),
+            Self::Index(index) => index.remap_path(path),
+            Self::Assignment(assignment) => assignment.remap_path(path),
+            Self::Throw(throw) => throw.remap_path(path),
+            Self::Break(break_) => break_.remap_path(path),
+            Self::Block(block) => block.remap_path(path),
+            Self::While(while_) => while_.remap_path(path),
+            Self::If(if_) => if_.remap_path(path),
+            Self::Map(map) => map.remap_path(path),
+            Self::Array(array) => array.remap_path(path),
+        }
+    }
+}
+
+impl RemapPath for MapExpression {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+
+        for (key, value) in &mut self.entries {
+            key.remap_path(path);
+            value.remap_path(path);
+        }
+    }
+}
+
+impl RemapPath for ExpressionStatement {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.expression.remap_path(path);
+    }
+}
+
+impl RemapPath for ReturnStatement {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+
+        if let Some(expression) = &mut self.expression {
+            expression.remap_path(path);
+        }
+    }
+}
+
+impl RemapPath for PrintStatement {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.arguments.remap_path(path);
+    }
+}
+
+impl RemapPath for FunctionDeclaration {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.parameter_types.remap_path(path);
+        self.return_type.remap_path(path);
+        self.body.remap_path(path);
+    }
+}
+
+impl RemapPath for ClassDeclaration {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.methods.remap_path(path);
+    }
+}
+
+impl RemapPath for Comment {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+    }
+}
+
+impl RemapPath for Statement {
+    fn remap_path(&mut self, path: PathId) {
+        match &mut self.raw {
+            RawStatement::Expression(expression) => expression.remap_path(path),
+            RawStatement::Return(return_) => return_.remap_path(path),
+            RawStatement::Print(print) => print.remap_path(path),
+            RawStatement::Function(function) => function.remap_path(path),
+            RawStatement::Class(class) => class.remap_path(path),
+        }
+
+        self.leading_comments.remap_path(path);
+        self.trailing_comments.remap_path(path);
+    }
+}
+
+impl RemapPath for StatementsBlock {
+    fn remap_path(&mut self, path: PathId) {
+        self.location = self.location.with_path(path);
+        self.statements.remap_path(path);
+    }
+}
+
+impl<T: RemapPath> RemapPath for Vec<T> {
+    fn remap_path(&mut self, path: PathId) {
+        for node in self {
+            node.remap_path(path);
+        }
+    }
+}
+
+impl<T: RemapPath> RemapPath for Box<T> {
+    fn remap_path(&mut self, path: PathId) {
+        (**self).remap_path(path);
+    }
+}
+
+impl<T: RemapPath> RemapPath for Option<T> {
+    fn remap_path(&mut self, path: PathId) {
+        if let Some(node) = self {
+            node.remap_path(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interner::PathId, parser::Parser};
+
+    fn parse_expression(source: &str) -> Expression {
+        Parser::new(PathId::from("test.lzr"), source)
+            .parse_expression()
+            .unwrap()
+    }
+
+    #[test]
+    fn spans_cover_whole_nodes() {
+        for source in [
+            "-a",
+            "a + b * c",
+            "f(1, 2)",
+            "new Foo(1)",
+            "a.b",
+            "a[0]",
+            "a.b(c)[d]",
+            "a = b += 1",
+            "throw a",
+            "while a { b; }",
+            "if a { b; } else if c { d; }",
+        ] {
+            let location = parse_expression(source).location();
+
+            assert_eq!(location.start().offset(), 0, "{source}");
+            assert_eq!(location.end().offset(), source.len(), "{source}");
+        }
+
+        for source in ["return a;", "func f(a) { return a; }", "class A { x; }"] {
+            let location = Parser::new(PathId::from("test.lzr"), source)
+                .parse_statement()
+                .unwrap()
+                .location();
+
+            assert_eq!(location.start().offset(), 0, "{source}");
+            assert_eq!(location.end().offset(), source.len(), "{source}");
+        }
+    }
+
+    #[test]
+    fn parentheses_are_not_part_of_spans() {
+        let location = parse_expression("(a) + b").location();
+
+        assert_eq!(location.start().offset(), 1);
+        assert_eq!(location.end().offset(), 7);
+    }
+
+    #[test]
+    fn terminators() {
+        let statement = |source| {
+            Parser::new(PathId::from("test.lzr"), source)
+                .parse_statement()
+                .unwrap()
+        };
+
+        assert!(statement("return 1;").is_terminator());
+        assert!(statement("return;").is_terminator());
+        assert!(statement("throw a;").is_terminator());
+        assert!(!statement("a = 1;").is_terminator());
+        assert!(!statement("if a { return 1; }").is_terminator());
+        assert!(!statement("f(throw a);").is_terminator());
+    }
+
+    #[test]
+    fn literal_depth_and_node_count() {
+        let literal = parse_expression("1");
+
+        assert_eq!(literal.depth(), 1);
+        assert_eq!(literal.node_count(), 1);
+    }
+
+    #[test]
+    fn binary_depth_and_node_count() {
+        let binary = parse_expression("1 + (2 * 3)");
+
+        assert_eq!(binary.depth(), 3);
+        assert_eq!(binary.node_count(), 5);
+    }
+
+    #[test]
+    fn block_depth_and_node_count() {
+        let block = Expression::Block(StatementsBlock::new(
+            vec![
+                RawStatement::Expression(parse_expression("1")).into(),
+                RawStatement::Expression(parse_expression("f(2 + 3)")).into(),
+            ],
+            Location::of_first_byte(PathId::from("test.lzr")),
+        ));
+
+        // block -> statement -> call -> binary -> literal
+        assert_eq!(block.depth(), 5);
+        // block, 2 statements, 1, call, f, binary, 2, 3
+        assert_eq!(block.node_count(), 9);
+    }
+
+    #[test]
+    fn remap_path_updates_every_node() {
+        let mut program = crate::parser::parse(
+            "included.lzr",
+            "func f(a) { return new A(a + 1, throw g(2)); }\nclass B { func c() { while x { if y { x.y[1] = -1; } else { z; } } } }",
+        )
+        .unwrap();
+        let path = PathId::from("main.lzr");
+
+        program.remap_path(path);
+
+        fn assert_expression(expression: &Expression, path: PathId) {
+            assert_eq!(expression.location().path(), path);
+
+            match expression {
+                Expression::Binary(binary) => {
+                    assert_expression(binary.left(), path);
+                    assert_expression(binary.right(), path);
+                }
+                Expression::Call(call) => {
+                    assert_expression(call.callee(), path);
+                    call.arguments()
+                        .iter()
+                        .for_each(|argument| assert_expression(argument, path));
+                }
+                Expression::New(new) => new
+                    .arguments()
+                    .iter()
+                    .for_each(|argument| assert_expression(argument, path)),
+                Expression::Unary(unary) => assert_expression(unary.operand(), path),
+                Expression::Member(member) => assert_expression(member.object(), path),
+                Expression::Index(index) => {
+                    assert_expression(index.object(), path);
+                    assert_expression(index.index(), path);
+                }
+                Expression::Assignment(assignment) => {
+                    assert_expression(assignment.target(), path);
+                    assert_expression(assignment.value(), path);
+                }
+                Expression::Throw(throw) => assert_expression(throw.value(), path),
+                Expression::Block(block) => assert_block(block, path),
+                Expression::While(while_) => {
+                    assert_expression(while_.condition(), path);
+                    assert_expression(while_.body(), path);
+                }
+                Expression::If(if_) => {
+                    assert_expression(if_.condition(), path);
+                    assert_expression(if_.then_branch(), path);
+                    if let Some(else_branch) = if_.else_branch() {
+                        assert_expression(else_branch, path);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn assert_block(block: &StatementsBlock, path: PathId) {
+            assert_eq!(block.location().path(), path);
+
+            for statement in block.statements() {
+                assert_eq!(statement.location().path(), path);
+
+                match statement.raw() {
+                    RawStatement::Expression(expression) => assert_expression(expression, path),
+                    RawStatement::Return(return_) => {
+                        if let Some(expression) = return_.expression() {
+                            assert_expression(expression, path);
+                        }
+                    }
+                    RawStatement::Print(print) => print
+                        .arguments()
+                        .iter()
+                        .for_each(|argument| assert_expression(argument, path)),
+                    RawStatement::Function(function) => assert_block(function.body(), path),
+                    RawStatement::Class(class) => class.methods().iter().for_each(|method| {
+                        assert_eq!(method.location().path(), path);
+                        assert_block(method.body(), path);
+                    }),
+                }
+            }
+        }
+
+        assert_block(&program, path);
+    }
+
+    #[test]
+    fn lvalues() {
+        assert!(parse_expression("a").is_lvalue());
+        assert!(parse_expression("a.b").is_lvalue());
+        assert!(parse_expression("a[0]").is_lvalue());
+        assert!(parse_expression("f().b[1]").is_lvalue());
+    }
+
+    #[test]
+    fn rvalues() {
+        assert!(!parse_expression("1").is_lvalue());
+        assert!(!parse_expression("\"a\"").is_lvalue());
+        assert!(!parse_expression("a + b").is_lvalue());
+        assert!(!parse_expression("f()").is_lvalue());
+        assert!(!parse_expression("new A()").is_lvalue());
+        assert!(!parse_expression("a = b").is_lvalue());
+    }
+
+    #[test]
+    fn block_length() {
+        let empty = StatementsBlock::new(
+            Vec::new(),
+            Location::of_first_byte(PathId::from("test.lzr")),
+        );
+
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+
+        let block = crate::parser::parse("test.lzr", "a; b;").unwrap();
+
+        assert!(!block.is_empty());
+        assert_eq!(block.len(), 2);
+    }
+
+    #[test]
+    fn iterate_block() {
+        let block = crate::parser::parse("test.lzr", "a; return b;").unwrap();
+        let mut kinds = Vec::new();
+
+        for statement in &block {
+            kinds.push(matches!(statement.raw(), RawStatement::Return(_)));
+        }
+
+        assert_eq!(kinds, vec![false, true]);
+        assert_eq!(block.iter().count(), 2);
+        assert_eq!(block.into_iter().count(), 2);
+    }
+
+    #[test]
+    fn iterate_array() {
+        let location = Location::of_first_byte(PathId::from("test.lzr"));
+        let array = ArrayExpression::new(
+            vec![
+                Expression::Literal(Literal::new(Value::Int(1), location)),
+                Expression::Identifier(IdentifierExpression::new(
+                    IdentifierId::from("a"),
+                    location,
+                )),
+            ],
+            location,
+        );
+
+        assert!(matches!(
+            (&array).into_iter().collect::<Vec<_>>().as_slice(),
+            [Expression::Literal(_), Expression::Identifier(_)]
+        ));
+        assert_eq!(array.iter().count(), 2);
+        assert_eq!(array.into_iter().count(), 2);
+    }
+}
@@ -0,0 +1,234 @@
+//! Constant folding: replaces binary expressions, whose operands are both
+//! literals, with a single literal, e.g. `1 + 2 * 3` becomes `7`.
+
+use crate::{
+    ast::{
+        ArrayExpression, AssignmentExpression, BinaryExpression, CallExpression, ClassDeclaration,
+        Expression, FunctionDeclaration, IfExpression, IndexExpression, Literal, MapExpression,
+        MemberExpression, NewExpression, PrintStatement, RawStatement, ReturnStatement, Statement,
+        StatementsBlock, ThrowExpression, UnaryExpression, WhileExpression,
+    },
+    location::HasLocation,
+};
+
+#[must_use]
+pub fn fold_statements_block(block: &StatementsBlock) -> StatementsBlock {
+    StatementsBlock::new(
+        block.statements().iter().map(fold_statement).collect(),
+        block.location(),
+    )
+}
+
+#[must_use]
+pub fn fold_statement(statement: &Statement) -> Statement {
+    let raw = match statement.raw() {
+        RawStatement::Expression(expression) => {
+            RawStatement::Expression(fold_expression(expression))
+        }
+        RawStatement::Return(return_) => RawStatement::Return(ReturnStatement::new(
+            return_.expression().map(fold_expression),
+            return_.location(),
+        )),
+        RawStatement::Print(print) => RawStatement::Print(PrintStatement::new(
+            print.arguments().iter().map(fold_expression).collect(),
+            print.is_println(),
+            print.location(),
+        )),
+        RawStatement::Function(function) => RawStatement::Function(fold_function(function)),
+        RawStatement::Class(class) => RawStatement::Class(ClassDeclaration::new(
+            class.name(),
+            class.methods().iter().map(fold_function).collect(),
+            class.fields().to_vec(),
+            class.location(),
+        )),
+    };
+
+    Statement::new(raw).with_comments(
+        statement.leading_comments().to_vec(),
+        statement.trailing_comments().to_vec(),
+    )
+}
+
+fn fold_function(function: &FunctionDeclaration) -> FunctionDeclaration {
+    FunctionDeclaration::new(
+        function.name(),
+        function.parameters().to_vec(),
+        fold_statements_block(function.body()),
+        function.location(),
+    )
+    .with_types(function.parameter_types().to_vec(), function.return_type())
+}
+
+#[must_use]
+pub fn fold_expression(expression: &Expression) -> Expression {
+    match expression {
+        Expression::Literal(_) | Expression::Identifier(_) | Expression::Break(_) => {
+            expression.clone()
+        }
+        Expression::Unary(unary) => fold_unary_expression(unary),
+        Expression::Binary(binary) => fold_binary_expression(binary),
+        Expression::Call(call) => Expression::Call(CallExpression::new(
+            Box::new(fold_expression(call.callee())),
+            call.arguments().iter().map(fold_expression).collect(),
+            call.location(),
+        )),
+        Expression::New(new) => Expression::New(NewExpression::new(
+            new.class(),
+            new.arguments().iter().map(fold_expression).collect(),
+            new.location(),
+        )),
+        Expression::Member(member) => Expression::Member(MemberExpression::new(
+            Box::new(fold_expression(member.object())),
+            member.member(),
+            member.location(),
+        )),
+        Expression::Index(index) => Expression::Index(IndexExpression::new(
+            Box::new(fold_expression(index.object())),
+            Box::new(fold_expression(index.index())),
+            index.location(),
+        )),
+        Expression::Assignment(assignment) => Expression::Assignment(AssignmentExpression::new(
+            Box::new(fold_expression(assignment.target())),
+            assignment.operator(),
+            Box::new(fold_expression(assignment.value())),
+        )),
+        Expression::Throw(throw) => Expression::Throw(ThrowExpression::new(
+            Box::new(fold_expression(throw.value())),
+            throw.location(),
+        )),
+        Expression::Block(block) => Expression::Block(fold_statements_block(block)),
+        Expression::While(while_) => Expression::While(WhileExpression::new(
+            Box::new(fold_expression(while_.condition())),
+            Box::new(fold_expression(while_.body())),
+            while_.location(),
+        )),
+        Expression::If(if_) => Expression::If(IfExpression::new(
+            Box::new(fold_expression(if_.condition())),
+            Box::new(fold_expression(if_.then_branch())),
+            if_.else_branch()
+                .map(|else_branch| Box::new(fold_expression(else_branch))),
+            if_.location(),
+        )),
+        Expression::Map(map) => Expression::Map(MapExpression::new(
+            map.entries()
+                .iter()
+                .map(|(key, value)| (fold_expression(key), fold_expression(value)))
+                .collect(),
+            map.location(),
+        )),
+        Expression::Array(array) => Expression::Array(ArrayExpression::new(
+            array.iter().map(fold_expression).collect(),
+            array.location(),
+        )),
+    }
+}
+
+/// Folds unary operator applied to a literal, e.g. `-5` becomes
+/// a literal with value `-5`.
+fn fold_unary_expression(unary: &UnaryExpression) -> Expression {
+    let operand = fold_expression(unary.operand());
+
+    if let Expression::Literal(literal) = &operand {
+        if let Some(value) = literal.value().unary_operation(unary.operator()) {
+            return Expression::Literal(Literal::new(value, unary.location()));
+        }
+    }
+
+    Expression::Unary(UnaryExpression::new(
+        unary.operator(),
+        Box::new(operand),
+        unary.location(),
+    ))
+}
+
+fn fold_binary_expression(binary: &BinaryExpression) -> Expression {
+    let left = fold_expression(binary.left());
+    let right = fold_expression(binary.right());
+
+    // Operations failing at runtime, e.g. `%` by zero, are left unfolded,
+    // so that they are still reported there.
+    if let (Expression::Literal(left), Expression::Literal(right)) = (&left, &right) {
+        if let Some(value) = left
+            .value()
+            .binary_operation(binary.operator(), right.value())
+        {
+            return Expression::Literal(Literal::new(value, binary.location()));
+        }
+    }
+
+    Expression::Binary(BinaryExpression::new(
+        Box::new(left),
+        binary.operator(),
+        Box::new(right),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interner::PathId, parser::Parser, value::Value};
+
+    fn fold(source: &str) -> Expression {
+        fold_expression(
+            &Parser::new(PathId::from("test.lzr"), source)
+                .parse_expression()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn fold_arithmetic() {
+        let Expression::Literal(literal) = fold("1 + 2 * 3") else {
+            panic!("expected literal");
+        };
+
+        assert_eq!(literal.value(), &Value::Int(7));
+        assert_eq!(literal.location().start().offset(), 0);
+        assert_eq!(literal.location().end().offset(), 9);
+    }
+
+    #[test]
+    fn fold_negative_number() {
+        let Expression::Literal(literal) = fold("-5") else {
+            panic!("expected literal");
+        };
+
+        assert_eq!(literal.value(), &Value::Int(-5));
+        assert_eq!(literal.location().end().offset(), 2);
+
+        let Expression::Literal(literal) = fold("1 - -5") else {
+            panic!("expected literal");
+        };
+
+        assert_eq!(literal.value(), &Value::Int(6));
+        assert!(matches!(fold("a - 5"), Expression::Binary(_)));
+        assert!(matches!(fold("-a"), Expression::Unary(_)));
+    }
+
+    #[test]
+    fn fold_nested_operands() {
+        let Expression::Call(call) = fold("f(2 ** 3, \"a\" + \"b\", x + 1)") else {
+            panic!("expected call");
+        };
+
+        assert!(matches!(
+            call.arguments(),
+            [
+                Expression::Literal(_),
+                Expression::Literal(_),
+                Expression::Binary(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let Expression::Literal(literal) = fold("1 / 0") else {
+            panic!("expected literal");
+        };
+
+        assert_eq!(literal.value(), &Value::Float(f64::INFINITY));
+        assert!(matches!(fold("1 % (2 - 2)"), Expression::Binary(_)));
+        assert!(matches!(fold("1.5 % 0.0"), Expression::Binary(_)));
+    }
+}
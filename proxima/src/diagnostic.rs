@@ -0,0 +1,83 @@
+use derive_more::Display;
+
+use crate::location::{HasLocation, Location};
+
+/// Represents how serious a [`Diagnostic`] is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Display)]
+pub enum Severity {
+    /// Additional information about another diagnostic, e.g. a suggestion
+    /// how to fix an error.
+    #[display(fmt = "note")]
+    Note,
+    /// Code is valid, but is most likely not what the author intended
+    /// or can be written in a cleaner way.
+    #[display(fmt = "warning")]
+    Warning,
+    /// Code is invalid and cannot be executed.
+    #[display(fmt = "error")]
+    Error,
+}
+
+/// Represents a message reported to the user about a particular span
+/// in the source text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    location: Location,
+}
+
+impl Diagnostic {
+    #[inline]
+    #[must_use]
+    pub fn new(severity: Severity, message: impl Into<String>, location: Location) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            location,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn error(message: impl Into<String>, location: Location) -> Self {
+        Self::new(Severity::Error, message, location)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn warning(message: impl Into<String>, location: Location) -> Self {
+        Self::new(Severity::Warning, message, location)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn note(message: impl Into<String>, location: Location) -> Self {
+        Self::new(Severity::Note, message, location)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl HasLocation for Diagnostic {
+    #[inline]
+    fn location(&self) -> Location {
+        self.location
+    }
+}
@@ -1,23 +1,90 @@
+#[cfg(any(test, feature = "single-threaded"))]
+use std::cell::RefCell;
+#[cfg(not(feature = "single-threaded"))]
+use std::sync::Mutex;
 use std::{
+    fmt::{self, Display},
+    marker::PhantomData,
     path::{Path, PathBuf},
-    sync::Mutex,
 };
 
+#[cfg(not(feature = "single-threaded"))]
 use lazy_static::lazy_static;
+use paste::paste;
 use string_interner::{backend::StringBackend, StringInterner, Symbol};
 
-lazy_static! {
-    static ref IDENTIFIER_INTERNER: Mutex<StringInterner<StringBackend<SymbolUsize>>> =
-        Mutex::new(StringInterner::new());
+type Interner = StringInterner<StringBackend<SymbolUsize>>;
+
+/// Defines function giving access to a separate interner. Interners are
+/// global and shared by all threads, except for unit tests, where every
+/// test thread gets its own interners, so that tests running in parallel
+/// don't observe each other's symbols. The shared interner is still
+/// available to unit tests as `shared_$name`.
+///
+/// With the `single-threaded` feature interners are thread-local, which
+/// saves locking a `Mutex` on every intern. Ids are then only comparable
+/// within the thread that interned them, so they don't implement [`Send`]
+/// and [`Sync`], see [`SymbolUsize`].
+macro_rules! interner {
+    ($name:ident) => {
+        paste! {
+            fn $name<R>(f: impl FnOnce(&mut Interner) -> R) -> R {
+                #[cfg(not(any(test, feature = "single-threaded")))]
+                return [<shared_ $name>](f);
+
+                #[cfg(any(test, feature = "single-threaded"))]
+                return [<local_ $name>](f);
+            }
+
+            #[cfg(not(feature = "single-threaded"))]
+            #[cfg_attr(test, allow(dead_code))]
+            fn [<shared_ $name>]<R>(f: impl FnOnce(&mut Interner) -> R) -> R {
+                lazy_static! {
+                    static ref INTERNER: Mutex<Interner> = Mutex::new(Interner::new());
+                }
+
+                f(&mut INTERNER.lock().unwrap())
+            }
+
+            #[cfg(any(test, feature = "single-threaded"))]
+            fn [<local_ $name>]<R>(f: impl FnOnce(&mut Interner) -> R) -> R {
+                thread_local! {
+                    static INTERNER: RefCell<Interner> = RefCell::new(Interner::new());
+                }
+
+                INTERNER.with(|interner| f(&mut interner.borrow_mut()))
+            }
+        }
+    };
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
-pub struct SymbolUsize(usize);
+interner!(identifier_interner);
+interner!(string_interner);
+interner!(path_interner);
+interner!(symbol_interner);
+
+/// Index of interned text. With the `single-threaded` feature symbols, and
+/// so ids, can't be sent to other threads, where the same index refers to
+/// different text.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct SymbolUsize(usize, PhantomData<ThreadBound>);
+
+#[cfg(not(feature = "single-threaded"))]
+type ThreadBound = ();
+
+#[cfg(feature = "single-threaded")]
+type ThreadBound = *const ();
+
+impl SymbolUsize {
+    const fn new(index: usize) -> Self {
+        Self(index, PhantomData)
+    }
+}
 
 impl From<usize> for SymbolUsize {
     #[inline]
     fn from(value: usize) -> Self {
-        Self(value)
+        Self::new(value)
     }
 }
 
@@ -31,7 +98,7 @@ impl From<SymbolUsize> for usize {
 impl Symbol for SymbolUsize {
     #[inline]
     fn try_from_usize(index: usize) -> Option<Self> {
-        Some(Self(index))
+        Some(Self::new(index))
     }
 
     #[inline]
@@ -40,62 +107,167 @@ impl Symbol for SymbolUsize {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Interned identifier. Ids are ordered as they were interned, not by
+/// the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct IdentifierId(SymbolUsize);
 
-pub const DUMMY_IDENTIFIER_ID: IdentifierId = IdentifierId(SymbolUsize(usize::MAX - 1));
+pub const DUMMY_IDENTIFIER_ID: IdentifierId = IdentifierId(SymbolUsize::new(usize::MAX - 1));
 
 impl<S> From<S> for IdentifierId
 where
     S: AsRef<str>,
 {
     fn from(str: S) -> Self {
-        Self(IDENTIFIER_INTERNER.lock().unwrap().get_or_intern(str))
+        Self(identifier_interner(|interner| interner.get_or_intern(str)))
     }
 }
 
 impl From<IdentifierId> for Option<String> {
     fn from(id: IdentifierId) -> Self {
-        IDENTIFIER_INTERNER
-            .lock()
-            .unwrap()
-            .resolve(id.0)
-            .map(ToOwned::to_owned)
+        identifier_interner(|interner| interner.resolve(id.0).map(ToOwned::to_owned))
+    }
+}
+
+impl IdentifierId {
+    /// Calls `f` with the identifier text, without allocating unless the
+    /// identifier is longer than 64 bytes. Returns `None` if the id was
+    /// never interned.
+    ///
+    /// The text is copied out of the interner before `f` runs, so `f` can
+    /// intern and resolve identifiers itself.
+    pub fn with_resolved<R>(self, f: impl FnOnce(&str) -> R) -> Option<R> {
+        let text = identifier_interner(|interner| interner.resolve(self.0).map(Text::new))?;
+
+        Some(f(text.as_str()))
+    }
+}
+
+/// Returns the identifier text, or `None` if the id was never interned.
+/// Unlike converting the id into [`String`], this doesn't panic.
+#[must_use]
+pub fn resolve_identifier(id: IdentifierId) -> Option<String> {
+    id.into()
+}
+
+/// Text copied out of an interner, so that the interner isn't locked while
+/// the text is used. Short texts are kept on the stack.
+enum Text {
+    Inline { bytes: [u8; 64], len: usize },
+    Heap(String),
+}
+
+impl Text {
+    fn new(text: &str) -> Self {
+        let mut bytes = [0; 64];
+
+        match bytes.get_mut(..text.len()) {
+            Some(prefix) => {
+                prefix.copy_from_slice(text.as_bytes());
+
+                Self::Inline {
+                    bytes,
+                    len: text.len(),
+                }
+            }
+            None => Self::Heap(text.to_owned()),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Inline { bytes, len } => {
+                std::str::from_utf8(&bytes[..*len]).expect("bytes are copied from `str`")
+            }
+            Self::Heap(text) => text,
+        }
+    }
+}
+
+/// Displays the identifier text, or `<dummy>` for [`DUMMY_IDENTIFIER_ID`]
+/// and `<unknown>` for ids that were never interned.
+impl Display for IdentifierId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == DUMMY_IDENTIFIER_ID {
+            return f.write_str("<dummy>");
+        }
+
+        self.with_resolved(|name| f.write_str(name))
+            .unwrap_or_else(|| f.write_str("<unknown>"))
     }
 }
 
 impl From<IdentifierId> for String {
+    /// # Panics
+    ///
+    /// Panics if the id was never interned, see [`IdentifierId::with_resolved`]
+    /// for a fallible alternative.
     fn from(id: IdentifierId) -> Self {
         Option::<String>::from(id).unwrap()
     }
 }
 
-lazy_static! {
-    static ref STRING_INTERNER: Mutex<StringInterner<StringBackend<SymbolUsize>>> =
-        Mutex::new(StringInterner::new());
+/// Returns all interned identifiers paired with their symbol indices,
+/// in the order they were interned. Intended for debugging.
+#[must_use]
+pub fn dump_identifiers() -> Vec<(usize, String)> {
+    identifier_interner(|interner| dump(interner))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Returns all interned string literals, see [`dump_identifiers`].
+#[must_use]
+pub fn dump_strings() -> Vec<(usize, String)> {
+    string_interner(|interner| dump(interner))
+}
+
+/// Returns all interned paths, see [`dump_identifiers`].
+#[must_use]
+pub fn dump_paths() -> Vec<(usize, String)> {
+    path_interner(|interner| dump(interner))
+}
+
+fn dump(interner: &Interner) -> Vec<(usize, String)> {
+    interner
+        .into_iter()
+        .map(|(symbol, string)| (symbol.to_usize(), string.to_owned()))
+        .collect()
+}
+
+/// Interned string literal, ordered like [`IdentifierId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct StringId(SymbolUsize);
 
-pub const DUMMY_STRING_ID: StringId = StringId(SymbolUsize(usize::MAX - 1));
+pub const DUMMY_STRING_ID: StringId = StringId(SymbolUsize::new(usize::MAX - 1));
 
 impl<S> From<S> for StringId
 where
     S: AsRef<str>,
 {
     fn from(value: S) -> Self {
-        Self(STRING_INTERNER.lock().unwrap().get_or_intern(value))
+        Self(string_interner(|interner| interner.get_or_intern(value)))
     }
 }
 
 impl From<StringId> for Option<String> {
     fn from(id: StringId) -> Self {
-        STRING_INTERNER
-            .lock()
-            .unwrap()
-            .resolve(id.0)
-            .map(ToOwned::to_owned)
+        string_interner(|interner| interner.resolve(id.0).map(ToOwned::to_owned))
+    }
+}
+
+/// Displays the string without quotes and escapes, see
+/// [`IdentifierId`]'s `Display` for unresolvable ids.
+impl Display for StringId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == DUMMY_STRING_ID {
+            return f.write_str("<dummy>");
+        }
+
+        // The text is copied, so that the interner isn't locked while
+        // the formatter runs.
+        match string_interner(|interner| interner.resolve(self.0).map(Text::new)) {
+            Some(text) => f.write_str(text.as_str()),
+            None => f.write_str("<unknown>"),
+        }
     }
 }
 
@@ -105,33 +277,25 @@ impl From<StringId> for String {
     }
 }
 
-lazy_static! {
-    static ref PATH_INTERNER: Mutex<StringInterner<StringBackend<SymbolUsize>>> =
-        Mutex::new(StringInterner::new());
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PathId(SymbolUsize);
 
-pub const DUMMY_PATH_ID: PathId = PathId(SymbolUsize(usize::MAX - 1));
+pub const DUMMY_PATH_ID: PathId = PathId(SymbolUsize::new(usize::MAX - 1));
 
 impl<P> From<P> for PathId
 where
     P: AsRef<Path>,
 {
     fn from(path: P) -> Self {
-        Self(
-            PATH_INTERNER
-                .lock()
-                .unwrap()
-                .get_or_intern(path.as_ref().to_str().unwrap()),
-        )
+        Self(path_interner(|interner| {
+            interner.get_or_intern(path.as_ref().to_str().unwrap())
+        }))
     }
 }
 
 impl From<PathId> for Option<PathBuf> {
     fn from(id: PathId) -> Self {
-        PATH_INTERNER.lock().unwrap().resolve(id.0).map(Into::into)
+        path_interner(|interner| interner.resolve(id.0).map(Into::into))
     }
 }
 
@@ -141,6 +305,19 @@ impl From<PathId> for PathBuf {
     }
 }
 
+/// Interns synthetic key, e.g. `+(number,number)` for operator dispatch
+/// tables. Symbols live in their own table, so they never collide with
+/// identifiers.
+pub fn intern_symbol(key: &str) -> SymbolUsize {
+    symbol_interner(|interner| interner.get_or_intern(key))
+}
+
+/// Returns key interned with [`intern_symbol`].
+#[must_use]
+pub fn resolve_symbol(symbol: SymbolUsize) -> Option<String> {
+    symbol_interner(|interner| interner.resolve(symbol).map(ToOwned::to_owned))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,4 +354,163 @@ mod tests {
         assert_ne!(a, b);
         assert_ne!(a2, b);
     }
+
+    #[test]
+    fn resolve_identifier_without_allocating() {
+        let id = IdentifierId::from("resolved");
+
+        assert_eq!(id.with_resolved(str::len), Some(8));
+        assert_eq!(id.with_resolved(|name| name == "resolved"), Some(true));
+        assert_eq!(DUMMY_IDENTIFIER_ID.with_resolved(str::len), None);
+
+        let long = "long".repeat(20);
+        let long_id = IdentifierId::from(&long);
+
+        assert_eq!(long_id.with_resolved(|name| name == long), Some(true));
+    }
+
+    #[test]
+    fn nested_resolve() {
+        let outer = IdentifierId::from("outer");
+        let inner = IdentifierId::from("inner");
+
+        assert_eq!(
+            outer.with_resolved(|name| format!("{name} {inner} {}", IdentifierId::from("new"))),
+            Some("outer inner new".to_owned())
+        );
+    }
+
+    #[test]
+    fn resolve_known_and_unknown_identifiers() {
+        let id = IdentifierId::from("known");
+
+        assert_eq!(resolve_identifier(id).as_deref(), Some("known"));
+        assert_eq!(resolve_identifier(DUMMY_IDENTIFIER_ID), None);
+    }
+
+    #[test]
+    fn dump_symbols_in_order() {
+        let mut interner = Interner::new();
+        interner.get_or_intern("a");
+        interner.get_or_intern("b");
+        interner.get_or_intern("a");
+        interner.get_or_intern("c");
+
+        assert_eq!(
+            dump(&interner),
+            vec![
+                (0, "a".to_owned()),
+                (1, "b".to_owned()),
+                (2, "c".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn dump_identifiers_contains_interned() {
+        let id = IdentifierId::from("dump_identifiers_contains_interned");
+
+        assert!(dump_identifiers().contains(&(
+            id.0.to_usize(),
+            "dump_identifiers_contains_interned".to_owned()
+        )));
+    }
+
+    #[test]
+    fn dump_every_interner() {
+        let names = ["x", "y", "z"];
+        let strings = names.map(StringId::from);
+        let paths = names.map(PathId::from);
+        let expected = vec![
+            (0, "x".to_owned()),
+            (1, "y".to_owned()),
+            (2, "z".to_owned()),
+        ];
+
+        assert_eq!(dump_strings(), expected);
+        assert_eq!(dump_paths(), expected);
+        assert_eq!(strings.map(|id| id.0.to_usize()), [0, 1, 2]);
+        assert_eq!(paths.map(|id| id.0.to_usize()), [0, 1, 2]);
+    }
+
+    #[test]
+    fn synthetic_symbol_round_trip() {
+        let symbol = intern_symbol("+(number,number)");
+
+        assert_eq!(intern_symbol("+(number,number)"), symbol);
+        assert_ne!(intern_symbol("+(string,string)"), symbol);
+        assert_eq!(resolve_symbol(symbol).as_deref(), Some("+(number,number)"));
+    }
+
+    #[test]
+    fn interners_are_isolated_between_tests() {
+        let a = IdentifierId::from("a");
+        let b = IdentifierId::from("b");
+
+        assert_eq!(IdentifierId::from("a"), a);
+        assert_ne!(a, b);
+
+        assert_eq!(
+            dump_identifiers(),
+            vec![(0, "a".to_owned()), (1, "b".to_owned())]
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "single-threaded"))]
+    fn shared_interner_is_used_by_all_threads() {
+        let intern = || shared_identifier_interner(|interner| interner.get_or_intern("shared"));
+        let symbol = intern();
+        let other_thread_symbols = (0..4)
+            .map(|_| std::thread::spawn(intern))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|thread| thread.join().unwrap());
+
+        for other_thread_symbol in other_thread_symbols {
+            assert_eq!(other_thread_symbol, symbol);
+        }
+
+        assert_eq!(
+            shared_identifier_interner(|interner| interner.resolve(symbol).map(ToOwned::to_owned)),
+            Some("shared".to_owned())
+        );
+        assert_eq!(
+            local_identifier_interner(|interner| interner.get("shared")),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "single-threaded"))]
+    fn thread_local_ids_are_stable() {
+        let names = (0..1000).map(|idx| format!("x{idx}")).collect::<Vec<_>>();
+        let ids = names.iter().map(IdentifierId::from).collect::<Vec<_>>();
+
+        for (name, id) in names.iter().zip(&ids) {
+            assert_eq!(IdentifierId::from(name), *id);
+            assert_eq!(String::from(*id), *name);
+        }
+
+        // Other threads have their own interners, so ids aren't comparable
+        // across threads: `x999` gets the id of `x0` here.
+        let other_thread_id = std::thread::spawn(|| IdentifierId::from("x999"))
+            .join()
+            .unwrap();
+
+        assert_eq!(other_thread_id, ids[0]);
+    }
+
+    #[test]
+    fn display_ids() {
+        assert_eq!(IdentifierId::from("displayed").to_string(), "displayed");
+        assert_eq!(StringId::from("a \"b\"").to_string(), "a \"b\"");
+        assert_eq!(DUMMY_IDENTIFIER_ID.to_string(), "<dummy>");
+        assert_eq!(DUMMY_STRING_ID.to_string(), "<dummy>");
+        assert_eq!(
+            IdentifierId(SymbolUsize::new(1000)).to_string(),
+            "<unknown>"
+        );
+        assert_eq!(StringId(SymbolUsize::new(1000)).to_string(), "<unknown>");
+    }
 }
@@ -0,0 +1,959 @@
+//! Tree-walking interpreter evaluating the AST into [`Value`]s.
+
+use std::{collections::HashMap, rc::Rc};
+
+use derive_more::Display;
+
+use crate::{
+    ast::{
+        ArrayExpression, AssignmentExpression, BinaryExpression, CallExpression, Expression,
+        FunctionDeclaration, IfExpression, IndexExpression, MapExpression, PrintStatement,
+        RawStatement, ReturnStatement, Statement, StatementsBlock, WhileExpression,
+    },
+    interner::IdentifierId,
+    token::Punctuator,
+    value::{Function, MapKey, Value},
+};
+
+/// Error that evaluation of the program can fail with.
+#[derive(Clone, Debug, PartialEq, Eq, Display)]
+pub enum RuntimeError {
+    #[display(fmt = "index {index} is out of bounds for length {length}")]
+    IndexOutOfBounds { index: i64, length: usize },
+    /// Value of the wrong type was used, see [`Value::type_name`].
+    #[display(fmt = "expected {expected}, found {found}")]
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// Shift amount is negative or too large, e.g. `1 << 64`.
+    #[display(fmt = "cannot shift by {amount}, the amount must be in 0..64")]
+    InvalidShift { amount: i64 },
+    #[display(fmt = "undefined variable `{name}`")]
+    UndefinedVariable { name: String },
+    /// `NaN` was used as a map key, see [`crate::value::MapKey`].
+    #[display(fmt = "NaN cannot be used as a map key")]
+    NanMapKey,
+    /// Map was indexed with a key it doesn't contain.
+    #[display(fmt = "key `{key}` not found")]
+    KeyNotFound { key: String },
+    /// Function was called with a wrong number of arguments.
+    #[display(fmt = "expected {expected} arguments, found {found}")]
+    ArityMismatch { expected: usize, found: usize },
+    /// Integer remainder of division by zero, e.g. `1 % 0`.
+    #[display(fmt = "division by zero")]
+    DivisionByZero,
+    /// Result of integer arithmetic doesn't fit into 64 bits.
+    #[display(fmt = "integer overflow")]
+    IntegerOverflow,
+    /// Construct the interpreter can't evaluate yet, e.g. a class.
+    #[display(fmt = "{construct} is not supported by the interpreter")]
+    Unsupported { construct: &'static str },
+    /// Functions are nested deeper than [`Interpreter::with_max_call_depth`]
+    /// allows, e.g. because of infinite recursion.
+    #[display(fmt = "maximum call depth of {limit} exceeded")]
+    CallDepthExceeded { limit: usize },
+}
+
+/// Default value of [`Interpreter::with_max_call_depth`]. Low enough that
+/// the calls fit in the 2 MiB stack threads get by default, even in
+/// unoptimized builds.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 200;
+
+/// Evaluates statements, keeping global variables between calls, so that
+/// the program can be evaluated piece by piece.
+#[derive(Clone, Debug)]
+pub struct Interpreter {
+    variables: HashMap<IdentifierId, Value>,
+    /// Local variables of the functions being called, the innermost call
+    /// last. Only variables of the innermost call and global ones are
+    /// visible.
+    scopes: Vec<HashMap<IdentifierId, Value>>,
+    /// Value of the `return` statement being executed, set until
+    /// the function call it leaves completes.
+    returned: Option<Value>,
+    /// Function and arguments of a call in tail position, e.g.
+    /// `return f(n - 1);`, which the function call being left makes
+    /// instead of nesting it, so that tail recursion doesn't grow the stack.
+    tail_call: Option<(Rc<Function>, Vec<Value>)>,
+    /// Number of expressions being evaluated for their value in the
+    /// innermost call, e.g. `1 + if c { return f(); }` makes the addition
+    /// wait for the `if`. Blocks, `if` and `while` in a statement of their
+    /// own don't count, so `return f();` in them is in tail position.
+    operand_depth: usize,
+    /// Text printed by `print` and `println` statements.
+    output: String,
+    max_call_depth: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self {
+            variables: HashMap::new(),
+            scopes: Vec::new(),
+            returned: None,
+            tail_call: None,
+            operand_depth: 0,
+            output: String::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+}
+
+impl Interpreter {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how deeply function calls can be nested before evaluation fails
+    /// with [`RuntimeError::CallDepthExceeded`] instead of overflowing the
+    /// stack. Calls in tail position, e.g. `return f(n - 1);`, don't nest.
+    #[inline]
+    #[must_use]
+    pub const fn with_max_call_depth(mut self, limit: usize) -> Self {
+        self.max_call_depth = limit;
+        self
+    }
+
+    /// Returns value of the variable, or `None` if it isn't defined.
+    #[inline]
+    #[must_use]
+    pub fn variable(&self, name: IdentifierId) -> Option<&Value> {
+        self.variables.get(&name)
+    }
+
+    /// Returns text printed so far and clears it. The interpreter doesn't
+    /// write to the standard output itself, so that embedders decide where
+    /// the output goes.
+    #[inline]
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Evaluates statements one by one. Returns value of the last one, or
+    /// [`Value::Null`] if there are no statements. Statements after
+    /// `return` are skipped.
+    ///
+    /// # Errors
+    /// Returns the first [`RuntimeError`] any statement fails with.
+    pub fn eval_block(&mut self, block: &StatementsBlock) -> Result<Value, RuntimeError> {
+        let mut value = Value::Null;
+
+        for statement in block {
+            value = self.eval_statement(statement)?;
+
+            if self.returned.is_some() {
+                break;
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Evaluates statement. Class declarations are not supported yet.
+    ///
+    /// # Errors
+    /// Returns [`RuntimeError`] if evaluation of the statement fails.
+    pub fn eval_statement(&mut self, statement: &Statement) -> Result<Value, RuntimeError> {
+        match statement.raw() {
+            RawStatement::Expression(expression) => self.eval_expression(expression),
+            RawStatement::Return(_) if self.scopes.is_empty() => Err(RuntimeError::Unsupported {
+                construct: "`return` outside of a function",
+            }),
+            RawStatement::Return(return_) => self.eval_return_statement(return_),
+            RawStatement::Print(print) => self.eval_print_statement(print),
+            RawStatement::Function(declaration) => {
+                self.eval_function_declaration(declaration);
+                Ok(Value::Null)
+            }
+            RawStatement::Class(_) => Err(RuntimeError::Unsupported { construct: "class" }),
+        }
+    }
+
+    fn eval_return_statement(&mut self, return_: &ReturnStatement) -> Result<Value, RuntimeError> {
+        // A call whose value an enclosing expression waits for is made
+        // right away, as that expression goes on being evaluated.
+        if let (Some(Expression::Call(call)), 0) = (return_.expression(), self.operand_depth) {
+            let (function, arguments) = self.eval_callee_and_arguments(call)?;
+            self.tail_call = Some((function, arguments));
+            self.returned = Some(Value::Null);
+
+            return Ok(Value::Null);
+        }
+
+        let value = match return_.expression() {
+            Some(expression) => self.eval_expression(expression)?,
+            None => Value::Null,
+        };
+        self.returned = Some(value.clone());
+
+        Ok(value)
+    }
+
+    fn eval_print_statement(&mut self, print: &PrintStatement) -> Result<Value, RuntimeError> {
+        for (idx, argument) in print.arguments().iter().enumerate() {
+            let value = self.eval_operand(argument)?;
+
+            if idx > 0 {
+                self.output.push(' ');
+            }
+
+            self.output.push_str(&value.to_string());
+        }
+
+        if print.is_println() {
+            self.output.push('\n');
+        }
+
+        Ok(Value::Null)
+    }
+
+    /// Declares function. Functions declared inside another one are always
+    /// local, even if a global variable has the same name.
+    fn eval_function_declaration(&mut self, declaration: &FunctionDeclaration) {
+        let captured = self.scopes.last().cloned();
+        let function = Value::Function(Rc::new(Function::new(declaration.clone(), captured)));
+
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.insert(declaration.name(), function);
+            }
+            None => {
+                self.variables.insert(declaration.name(), function);
+            }
+        }
+    }
+
+    /// Evaluates expression.
+    ///
+    /// # Errors
+    /// Returns [`RuntimeError`] if evaluation of the expression fails.
+    pub fn eval_expression(&mut self, expression: &Expression) -> Result<Value, RuntimeError> {
+        let is_operand = !matches!(
+            expression,
+            Expression::Block(_) | Expression::While(_) | Expression::If(_)
+        );
+        self.operand_depth += usize::from(is_operand);
+
+        let result = match expression {
+            Expression::Literal(literal) => Ok(literal.value().clone()),
+            Expression::Identifier(identifier) => self.lookup(identifier.name()).cloned(),
+            Expression::Unary(unary) => {
+                let operand = self.eval_expression(unary.operand())?;
+                eval_unary_operation(unary.operator(), &operand)
+            }
+            Expression::Binary(binary) => self.eval_binary_expression(binary),
+            Expression::Assignment(assignment) => self.eval_assignment_expression(assignment),
+            Expression::Block(block) => self.eval_block(block),
+            Expression::While(while_) => self.eval_while_expression(while_),
+            Expression::If(if_) => self.eval_if_expression(if_),
+            Expression::Call(call) => self.eval_call_expression(call),
+            Expression::New(_) => Err(RuntimeError::Unsupported { construct: "`new`" }),
+            Expression::Member(_) => Err(RuntimeError::Unsupported {
+                construct: "member access",
+            }),
+            Expression::Index(index) => self.eval_index_expression(index),
+            Expression::Map(map) => self.eval_map_expression(map),
+            Expression::Array(array) => self.eval_array_expression(array),
+            Expression::Throw(_) => Err(RuntimeError::Unsupported {
+                construct: "`throw`",
+            }),
+            Expression::Break(_) => Err(RuntimeError::Unsupported {
+                construct: "`break`",
+            }),
+        };
+
+        self.operand_depth -= usize::from(is_operand);
+        result
+    }
+
+    /// Evaluates expression whose value is used, even if it is a block,
+    /// e.g. condition of `if`, see the `operand_depth` field.
+    fn eval_operand(&mut self, expression: &Expression) -> Result<Value, RuntimeError> {
+        self.operand_depth += 1;
+        let result = self.eval_expression(expression);
+        self.operand_depth -= 1;
+
+        result
+    }
+
+    fn eval_while_expression(&mut self, while_: &WhileExpression) -> Result<Value, RuntimeError> {
+        while self.eval_operand(while_.condition())?.is_truthy() {
+            self.eval_expression(while_.body())?;
+
+            if self.returned.is_some() {
+                break;
+            }
+        }
+
+        Ok(Value::Null)
+    }
+
+    fn eval_if_expression(&mut self, if_: &IfExpression) -> Result<Value, RuntimeError> {
+        if self.eval_operand(if_.condition())?.is_truthy() {
+            self.eval_expression(if_.then_branch())
+        } else if let Some(else_branch) = if_.else_branch() {
+            self.eval_expression(else_branch)
+        } else {
+            Ok(Value::Null)
+        }
+    }
+
+    fn eval_index_expression(&mut self, index: &IndexExpression) -> Result<Value, RuntimeError> {
+        let object = self.eval_expression(index.object())?;
+        let index = self.eval_expression(index.index())?;
+
+        object.index(&index)
+    }
+
+    fn eval_array_expression(&mut self, array: &ArrayExpression) -> Result<Value, RuntimeError> {
+        array
+            .iter()
+            .map(|element| self.eval_expression(element))
+            .collect::<Result<_, _>>()
+            .map(Value::Array)
+    }
+
+    fn eval_map_expression(&mut self, map: &MapExpression) -> Result<Value, RuntimeError> {
+        let mut entries = HashMap::with_capacity(map.entries().len());
+
+        for (key, value) in map.entries() {
+            let key = MapKey::try_from(self.eval_expression(key)?)?;
+            let value = self.eval_expression(value)?;
+            entries.insert(key, value);
+        }
+
+        Ok(Value::Map(entries))
+    }
+
+    /// Evaluates binary expression. The right operand of `&&`, `||` and `??`
+    /// is only evaluated if the left one doesn't determine the result, and
+    /// the operand that does is returned as is, e.g. `null ?? 0` is `0`.
+    fn eval_binary_expression(&mut self, binary: &BinaryExpression) -> Result<Value, RuntimeError> {
+        let left = self.eval_expression(binary.left())?;
+
+        match short_circuits(binary.operator(), &left) {
+            Some(true) => return Ok(left),
+            Some(false) => return self.eval_expression(binary.right()),
+            None => {}
+        }
+
+        let right = self.eval_expression(binary.right())?;
+
+        eval_binary_operation(&left, binary.operator(), &right)
+    }
+
+    fn eval_call_expression(&mut self, call: &CallExpression) -> Result<Value, RuntimeError> {
+        let (function, arguments) = self.eval_callee_and_arguments(call)?;
+        self.call(function, arguments)
+    }
+
+    fn eval_callee_and_arguments(
+        &mut self,
+        call: &CallExpression,
+    ) -> Result<(Rc<Function>, Vec<Value>), RuntimeError> {
+        let callee = self.eval_expression(call.callee())?;
+        let Value::Function(function) = callee else {
+            return Err(RuntimeError::TypeMismatch {
+                expected: "function",
+                found: callee.type_name(),
+            });
+        };
+
+        let arguments = call
+            .arguments()
+            .iter()
+            .map(|argument| self.eval_expression(argument))
+            .collect::<Result<_, _>>()?;
+
+        Ok((function, arguments))
+    }
+
+    /// Calls function in a new scope with its captured variables and
+    /// the arguments bound to the parameters. Returns the value passed to
+    /// `return`, or [`Value::Null`] if the function doesn't return.
+    ///
+    /// Calls in tail position are made in a loop here rather than
+    /// recursively, see the `tail_call` field.
+    fn call(
+        &mut self,
+        mut function: Rc<Function>,
+        mut arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        loop {
+            let declaration = function.declaration();
+
+            if arguments.len() != declaration.parameters().len() {
+                return Err(RuntimeError::ArityMismatch {
+                    expected: declaration.parameters().len(),
+                    found: arguments.len(),
+                });
+            }
+
+            if self.scopes.len() >= self.max_call_depth {
+                return Err(RuntimeError::CallDepthExceeded {
+                    limit: self.max_call_depth,
+                });
+            }
+
+            let mut scope = HashMap::new();
+
+            if let Some(captured) = function.captured() {
+                scope.clone_from(captured);
+                // The function is defined in the scope it was declared in
+                // only after capturing it, so it is added here to be able
+                // to call itself. Parameters with the same name shadow it.
+                scope.insert(declaration.name(), Value::Function(Rc::clone(&function)));
+            }
+
+            scope.extend(declaration.parameters().iter().copied().zip(arguments));
+
+            self.scopes.push(scope);
+            let operand_depth = std::mem::take(&mut self.operand_depth);
+            let result = self.eval_block(declaration.body());
+            self.operand_depth = operand_depth;
+            self.scopes.pop();
+
+            // Both are cleared even if the call fails, so that they don't
+            // leak into the next call.
+            let returned = self.returned.take();
+            let tail_call = self.tail_call.take();
+            result?;
+
+            match tail_call {
+                Some((next_function, next_arguments)) => {
+                    function = next_function;
+                    arguments = next_arguments;
+                }
+                None => return Ok(returned.unwrap_or(Value::Null)),
+            }
+        }
+    }
+
+    /// Evaluates assignment to a variable, e.g. `a = 1` or `a += 1`.
+    fn eval_assignment_expression(
+        &mut self,
+        assignment: &AssignmentExpression,
+    ) -> Result<Value, RuntimeError> {
+        let Expression::Identifier(target) = assignment.target() else {
+            return Err(RuntimeError::Unsupported {
+                construct: "assignment to member or element",
+            });
+        };
+
+        let value = self.eval_expression(assignment.value())?;
+        let value = match assignment.operator().compound_base() {
+            Some(operator) => eval_binary_operation(self.lookup(target.name())?, operator, &value)?,
+            None => value,
+        };
+
+        self.define(target.name(), value.clone());
+        Ok(value)
+    }
+
+    /// Assigns the variable. Inside a function, global variables are
+    /// assigned only if they exist and the function has no local variable
+    /// with the name, otherwise a local variable is created.
+    fn define(&mut self, name: IdentifierId, value: Value) {
+        match self.scopes.last_mut() {
+            Some(scope) if scope.contains_key(&name) || !self.variables.contains_key(&name) => {
+                scope.insert(name, value);
+            }
+            _ => {
+                self.variables.insert(name, value);
+            }
+        }
+    }
+
+    pub(crate) fn lookup(&self, name: IdentifierId) -> Result<&Value, RuntimeError> {
+        self.scopes
+            .last()
+            .and_then(|scope| scope.get(&name))
+            .or_else(|| self.variables.get(&name))
+            .ok_or_else(|| RuntimeError::UndefinedVariable { name: name.into() })
+    }
+}
+
+/// Returns `Some(true)` if the left operand of `&&`, `||` or `??` is the
+/// result, `Some(false)` if the right one is, and `None` for other
+/// operators, whose both operands are evaluated.
+pub(crate) fn short_circuits(operator: Punctuator, left: &Value) -> Option<bool> {
+    match operator {
+        Punctuator::DoubleAmpersand => Some(!left.is_truthy()),
+        Punctuator::DoubleBar => Some(left.is_truthy()),
+        Punctuator::DoubleQuestion => Some(*left != Value::Null),
+        _ => None,
+    }
+}
+
+pub(crate) fn eval_unary_operation(
+    operator: Punctuator,
+    operand: &Value,
+) -> Result<Value, RuntimeError> {
+    match (operator, operand) {
+        (Punctuator::Bang, _) => Ok(Value::Bool(!operand.is_truthy())),
+        (Punctuator::Tilde, Value::Int(number)) => Ok(Value::Int(!number)),
+        (Punctuator::Tilde, _) => Err(RuntimeError::TypeMismatch {
+            expected: "integer",
+            found: operand.type_name(),
+        }),
+        (_, Value::Int(_)) => operand
+            .unary_operation(operator)
+            .ok_or(RuntimeError::IntegerOverflow),
+        _ => operand
+            .unary_operation(operator)
+            .ok_or(RuntimeError::TypeMismatch {
+                expected: "number",
+                found: operand.type_name(),
+            }),
+    }
+}
+
+pub(crate) fn eval_binary_operation(
+    left: &Value,
+    operator: Punctuator,
+    right: &Value,
+) -> Result<Value, RuntimeError> {
+    match operator {
+        Punctuator::DoubleEq => Ok(Value::Bool(left.equals(right))),
+        Punctuator::BangEq => Ok(Value::Bool(!left.equals(right))),
+        Punctuator::Less | Punctuator::LessEq | Punctuator::Greater | Punctuator::GreaterEq => {
+            if let Some(found) = [left, right]
+                .into_iter()
+                .find(|value| value.as_f64().is_none())
+            {
+                return Err(RuntimeError::TypeMismatch {
+                    expected: "number",
+                    found: found.type_name(),
+                });
+            }
+
+            // `NaN` is neither less, equal nor greater than any number.
+            Ok(Value::Bool(left.compare(right).is_some_and(
+                |ordering| match operator {
+                    Punctuator::Less => ordering.is_lt(),
+                    Punctuator::LessEq => ordering.is_le(),
+                    Punctuator::Greater => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+            )))
+        }
+        Punctuator::Ampersand
+        | Punctuator::Bar
+        | Punctuator::Caret
+        | Punctuator::LeftShift
+        | Punctuator::RightShift
+        | Punctuator::TripleGreater => left.bitwise_operation(operator, right),
+        _ => left.binary_operation(operator, right).ok_or_else(|| {
+            if operator == Punctuator::Percent && right.as_f64() == Some(0.0) {
+                RuntimeError::DivisionByZero
+            } else if matches!((left, right), (Value::Int(_), Value::Int(_))) {
+                RuntimeError::IntegerOverflow
+            } else {
+                RuntimeError::TypeMismatch {
+                    expected: "number",
+                    found: if left.as_f64().is_none() { left } else { right }.type_name(),
+                }
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn eval(source: &str) -> Result<Value, RuntimeError> {
+        Interpreter::new().eval_block(&parse("test.lzr", source).unwrap())
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(eval("1 + 2 * 3;"), Ok(Value::Int(7)));
+        assert_eq!(eval("7 / 2;"), Ok(Value::Float(3.5)));
+        assert_eq!(eval("\"a\" + \"b\";"), Ok(Value::String("ab".to_owned())));
+        assert_eq!(eval("1 % 0;"), Err(RuntimeError::DivisionByZero));
+        assert_eq!(
+            eval("9007199254740993 == 9007199254740992;"),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            eval("9007199254740992 < 9007199254740993;"),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(eval("1 == 1.0;"), Ok(Value::Bool(true)));
+        assert_eq!(eval("1 < 1.5;"), Ok(Value::Bool(true)));
+        assert_eq!(
+            eval("9223372036854775807 + 1;"),
+            Err(RuntimeError::IntegerOverflow)
+        );
+        assert_eq!(
+            eval("x = 9223372036854775807; x += 1;"),
+            Err(RuntimeError::IntegerOverflow)
+        );
+        assert_eq!(
+            eval("-(-9223372036854775807 - 1);"),
+            Err(RuntimeError::IntegerOverflow)
+        );
+        assert_eq!(
+            eval("1 + \"a\";"),
+            Err(RuntimeError::TypeMismatch {
+                expected: "number",
+                found: "string"
+            })
+        );
+    }
+
+    #[test]
+    fn string_indexing() {
+        assert_eq!(eval("\"hello\"[0];"), Ok(Value::from("h".to_owned())));
+        assert_eq!(
+            eval("s = \"hello\"; s[2 + 2];"),
+            Ok(Value::from("o".to_owned()))
+        );
+        assert_eq!(
+            eval("\"hello\"[5];"),
+            Err(RuntimeError::IndexOutOfBounds {
+                index: 5,
+                length: 5
+            })
+        );
+        assert_eq!(
+            eval("\"hello\"[-1];"),
+            Err(RuntimeError::IndexOutOfBounds {
+                index: -1,
+                length: 5
+            })
+        );
+        assert_eq!(
+            eval("\"hello\"[\"a\"];"),
+            Err(RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: "string"
+            })
+        );
+    }
+
+    #[test]
+    fn function_calls() {
+        assert_eq!(
+            eval("func add(a, b) { return a + b; } add(1, 2);"),
+            Ok(Value::Int(3))
+        );
+        assert_eq!(
+            eval("func fib(n) { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); } fib(10);"),
+            Ok(Value::Int(55))
+        );
+        assert_eq!(
+            eval("func f() { i = 0; while 1 { i += 1; if i == 3 { return i; } } } f();"),
+            Ok(Value::Int(3))
+        );
+        assert_eq!(eval("func f() { 1; } f();"), Ok(Value::Null));
+        assert_eq!(eval("func f() { return; 1; } f();"), Ok(Value::Null));
+        assert_eq!(
+            eval("func add(a, b) { return a + b; } add(1);"),
+            Err(RuntimeError::ArityMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            eval("f = 1; f();"),
+            Err(RuntimeError::TypeMismatch {
+                expected: "function",
+                found: "integer"
+            })
+        );
+        assert_eq!(
+            eval("return 1;"),
+            Err(RuntimeError::Unsupported {
+                construct: "`return` outside of a function"
+            })
+        );
+    }
+
+    #[test]
+    fn long_block() {
+        let source = "i = 0;\n".to_owned() + &"i += 1;\n".repeat(99_999);
+
+        assert_eq!(eval(&source), Ok(Value::Int(99_999)));
+    }
+
+    #[test]
+    fn tail_calls_do_not_grow_stack() {
+        assert_eq!(
+            eval(
+                "func count(n, acc) { if n == 0 { return acc; } return count(n - 1, acc + 1); } \
+                 count(100000, 0);"
+            ),
+            Ok(Value::Int(100_000))
+        );
+        assert_eq!(
+            eval("func f(a) { return g(a); } func g(a, b) { return a; } f(1);"),
+            Err(RuntimeError::ArityMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn return_call_inside_expression() {
+        let mut interpreter = Interpreter::new();
+        let mut eval = |source| interpreter.eval_block(&parse("test.lzr", source).unwrap());
+
+        assert_eq!(
+            eval(
+                "func k() { println \"k ran\"; return 1; } \
+                 func g(c) { x = 1 + if c { return k(); }; return x; } \
+                 func h() { return 2; }"
+            ),
+            Ok(Value::Null)
+        );
+        assert_eq!(eval("g(1 == 1);"), Ok(Value::Int(1)));
+        assert_eq!(eval("h();"), Ok(Value::Int(2)));
+        assert_eq!(
+            eval("func e() { x = \"a\" - if 1 == 1 { return k(); }; } e();"),
+            Err(RuntimeError::TypeMismatch {
+                expected: "number",
+                found: "string"
+            })
+        );
+        assert_eq!(eval("h();"), Ok(Value::Int(2)));
+        assert_eq!(
+            eval("func t(n) { if n > 0 { return t(n - 1); } return n; } t(5);"),
+            Ok(Value::Int(0))
+        );
+        assert_eq!(interpreter.take_output(), "k ran\nk ran\n");
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(eval("[];"), Ok(Value::Array(Vec::new())));
+        assert_eq!(eval("a = 2; [1, a + 3,][1];"), Ok(Value::Int(5)));
+        assert_eq!(eval("[1, [2]] == [1, [2.0]];"), Ok(Value::Bool(true)));
+        assert_eq!(
+            eval("[1][1];"),
+            Err(RuntimeError::IndexOutOfBounds {
+                index: 1,
+                length: 1
+            })
+        );
+        assert_eq!(
+            eval("[1, undefined];"),
+            Err(RuntimeError::UndefinedVariable {
+                name: "undefined".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn maps() {
+        assert_eq!(eval("m = {};"), Ok(Value::Map(HashMap::new())));
+        assert_eq!(eval("m = {\"a\": 1}; m[\"a\"];"), Ok(Value::Int(1)));
+        assert_eq!(
+            eval("{1: \"a\", 1: \"b\"}[1];"),
+            Ok(Value::String("b".to_owned()))
+        );
+        assert_eq!(
+            eval("x = 2; m = {x: x * 2, \"y\": {}}; m;")
+                .unwrap()
+                .to_string(),
+            "{\"y\": {}, 2: 4}"
+        );
+        assert_eq!(
+            eval("{1: 2}[3];"),
+            Err(RuntimeError::KeyNotFound {
+                key: "3".to_owned()
+            })
+        );
+        assert_eq!(eval("{0.0 / 0.0: 1};"), Err(RuntimeError::NanMapKey));
+        assert_eq!(eval("m = {1: 2}; m[1.0];"), Ok(Value::Int(2)));
+        assert_eq!(eval("m = {1.0: 2}; m[1];"), Ok(Value::Int(2)));
+        assert_eq!(
+            eval("{1: 2}[1.5];"),
+            Err(RuntimeError::KeyNotFound {
+                key: "1.5".to_owned()
+            })
+        );
+        assert_eq!(
+            eval("{1.5: 2}[3.0];"),
+            Err(RuntimeError::KeyNotFound {
+                key: "3.0".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn closures_and_scopes() {
+        assert_eq!(
+            eval(
+                "func adder(a) { func add(b) { return a + b; } return add; } \
+                 add2 = adder(2); add2(3);"
+            ),
+            Ok(Value::Int(5))
+        );
+        assert_eq!(
+            eval("count = 0; func f(a) { count += 1; b = a; } f(1); f(2); count;"),
+            Ok(Value::Int(2))
+        );
+        assert_eq!(
+            eval("func f(a) { b = a; } f(1); b;"),
+            Err(RuntimeError::UndefinedVariable {
+                name: "b".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        let countdown = |n: usize| {
+            format!("func c(n) {{ if n == 0 {{ return 0; }} return 1 + c(n - 1); }} c({n});")
+        };
+
+        assert_eq!(
+            eval(&countdown(20_000)),
+            Err(RuntimeError::CallDepthExceeded {
+                limit: DEFAULT_MAX_CALL_DEPTH
+            })
+        );
+        assert_eq!(
+            eval(&countdown(DEFAULT_MAX_CALL_DEPTH - 1)),
+            Ok(Value::Int(DEFAULT_MAX_CALL_DEPTH as i64 - 1))
+        );
+
+        let program = parse("test.lzr", &countdown(10)).unwrap();
+
+        assert_eq!(
+            Interpreter::new()
+                .with_max_call_depth(10)
+                .eval_block(&program),
+            Err(RuntimeError::CallDepthExceeded { limit: 10 })
+        );
+        assert_eq!(
+            Interpreter::new()
+                .with_max_call_depth(11)
+                .eval_block(&program),
+            Ok(Value::Int(10))
+        );
+    }
+
+    #[test]
+    fn nested_recursion() {
+        assert_eq!(
+            eval(
+                "func outer() { func inner(n) { if n == 0 { return 0; } return inner(n - 1); } \
+                 return inner(3); } outer();"
+            ),
+            Ok(Value::Int(0))
+        );
+        assert_eq!(
+            eval(
+                "inner = 1; func outer(k) { func inner(n) { if n == 0 { return k; } \
+                 return 1 + inner(n - 1); } return inner(3); } outer(10);"
+            ),
+            Ok(Value::Int(13))
+        );
+        assert_eq!(
+            eval(
+                "inner = 1; func outer() { func inner(n) { if n == 0 { return 0; } \
+                 return inner(n - 1); } return inner(3); } outer(); inner;"
+            ),
+            Ok(Value::Int(1))
+        );
+        assert_eq!(
+            eval("func outer() { func inner(inner) { return inner; } return inner(5); } outer();"),
+            Ok(Value::Int(5))
+        );
+    }
+
+    #[test]
+    fn print_statements() {
+        let mut interpreter = Interpreter::new();
+        let program = parse(
+            "test.lzr",
+            "println 1, 2, 3; print \"a\", 1 + 1.5; println;",
+        )
+        .unwrap();
+
+        assert_eq!(interpreter.eval_block(&program), Ok(Value::Null));
+        assert_eq!(interpreter.take_output(), "1 2 3\na 2.5\n");
+        assert!(interpreter.take_output().is_empty());
+    }
+
+    #[test]
+    fn bitwise_operations() {
+        assert_eq!(eval("6 & 3;"), Ok(Value::Int(2)));
+        assert_eq!(eval("(6 & 3) == 2;"), Ok(Value::Bool(true)));
+        assert_eq!(eval("6 | 3;"), Ok(Value::Int(7)));
+        assert_eq!(eval("6 ^ 3;"), Ok(Value::Int(5)));
+        assert_eq!(eval("~5;"), Ok(Value::Int(-6)));
+        assert_eq!(eval("1 << 4;"), Ok(Value::Int(16)));
+        assert_eq!(eval("(1 << 4) == 16;"), Ok(Value::Bool(true)));
+        assert_eq!(eval("-16 >> 2;"), Ok(Value::Int(-4)));
+        // Integers are 64-bit, so unlike in JavaScript this is not `15`.
+        assert_eq!(eval("-1 >>> 28;"), Ok(Value::Int(0xF_FFFF_FFFF)));
+        assert_eq!(eval("-1 >>> 60;"), Ok(Value::Int(15)));
+        assert_eq!(
+            eval("1.5 & 1;"),
+            Err(RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: "float"
+            })
+        );
+        assert_eq!(
+            eval("~\"a\";"),
+            Err(RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: "string"
+            })
+        );
+        assert_eq!(
+            eval("1 << -1;"),
+            Err(RuntimeError::InvalidShift { amount: -1 })
+        );
+    }
+
+    #[test]
+    fn short_circuit_evaluation() {
+        let prelude = "yes = 1 < 2; no = 1 > 2; nothing = if no { 1; };";
+        let eval = |source: &str| eval(&format!("{prelude} {source}"));
+
+        // `undefined` would fail with an error if it were evaluated.
+        assert_eq!(eval("no && undefined;"), Ok(Value::Bool(false)));
+        assert_eq!(eval("nothing && undefined;"), Ok(Value::Null));
+        assert_eq!(eval("1 || undefined;"), Ok(Value::Int(1)));
+        assert_eq!(eval("2 ?? undefined;"), Ok(Value::Int(2)));
+
+        assert_eq!(eval("yes && 2;"), Ok(Value::Int(2)));
+        assert_eq!(eval("no || \"b\";"), Ok(Value::String("b".to_owned())));
+        assert_eq!(eval("nothing ?? 3;"), Ok(Value::Int(3)));
+        assert_eq!(
+            eval("yes && undefined;"),
+            Err(RuntimeError::UndefinedVariable {
+                name: "undefined".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn variables_and_control_flow() {
+        assert_eq!(
+            eval("i = 0; sum = 0; while i < 5 { i += 1; sum += i; } sum;"),
+            Ok(Value::Int(15))
+        );
+        assert_eq!(
+            eval("a = 2; if a > 1 { 10; } else { 20; }"),
+            Ok(Value::Int(10))
+        );
+        assert_eq!(
+            eval("a;"),
+            Err(RuntimeError::UndefinedVariable {
+                name: "a".to_owned()
+            })
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use std::str::Chars;
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    str::Chars,
+};
 
 use crate::{
-    interner::{IdentifierId, PathId, StringId, DUMMY_IDENTIFIER_ID, DUMMY_STRING_ID},
+    diagnostic::Diagnostic,
+    interner::{IdentifierId, PathId, StringId},
     location::{CharLocation, Location},
-    stable_likely::unlikely,
-    token::{Keyword, Punctuator, RawLexError, RawToken, Token},
+    stable_likely::{likely, unlikely},
+    token::{Keyword, LexError, Punctuator, RawLexError, RawToken, Token},
 };
 
 pub struct Lexer<'s> {
@@ -37,39 +42,250 @@ pub struct Lexer<'s> {
     /// Offset of the current character in the source text.
     location: CharLocation,
 
-    /// Last processed identifier.
-    processed_identifier: IdentifierId,
+    /// Non-fatal diagnostics (warnings and notes) reported while scanning.
+    diagnostics: Vec<Diagnostic>,
+
+    /// Maximum length of identifiers and string literals in bytes.
+    max_token_length: Option<usize>,
+
+    /// Whether comments are emitted as [`RawToken::Comment`] tokens
+    /// instead of being skipped.
+    emit_trivia: bool,
+
+    /// Keywords that are scanned as plain identifiers.
+    disabled_keywords: HashSet<Keyword>,
+
+    /// Whether hexadecimal floating point numbers, e.g. `0x1.8p3`, are
+    /// scanned.
+    hex_floats: bool,
+
+    /// Whether indentation mixing tabs and spaces is reported.
+    warn_mixed_indentation: bool,
+
+    /// Whether scanning stops after the first [`RawToken::Error`].
+    fail_fast: bool,
+
+    /// Set once an error is emitted in fail-fast mode.
+    stopped: bool,
+}
+
+/// Byte order mark, which some editors put at the start of UTF-8 files.
+pub(crate) const BYTE_ORDER_MARK: char = '\u{FEFF}';
+
+/// Scans the whole source, skipping comments. The source length is not
+/// limited, see [`tokenize_with_max_source_length`].
+#[must_use]
+pub fn tokenize(path: impl Into<PathId>, source: &str) -> Vec<Token> {
+    Lexer::new(path.into(), source).collect()
+}
+
+/// Scans the whole source like [`tokenize`], unless it is longer than
+/// `max_source_length` bytes. `None` means no limit.
+///
+/// # Errors
+/// Returns [`SourceTooLarge`] without scanning anything if the source
+/// exceeds the limit.
+pub fn tokenize_with_max_source_length(
+    path: impl Into<PathId>,
+    source: &str,
+    max_source_length: Option<usize>,
+) -> Result<Vec<Token>, SourceTooLarge> {
+    check_source_length(source, max_source_length)?;
+
+    Ok(tokenize(path, source))
+}
+
+/// Source is longer than the configured limit, e.g. a huge file was
+/// opened by accident. See [`tokenize_with_max_source_length`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SourceTooLarge {
+    length: usize,
+    limit: usize,
+}
+
+impl SourceTooLarge {
+    /// Returns length of the source in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the maximum allowed length in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl Display for SourceTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "source is too large: {} bytes (the limit is {})",
+            self.length, self.limit
+        )
+    }
+}
+
+impl std::error::Error for SourceTooLarge {}
+
+/// Fails if `source` is longer than `max_source_length` bytes.
+pub(crate) fn check_source_length(
+    source: &str,
+    max_source_length: Option<usize>,
+) -> Result<(), SourceTooLarge> {
+    match max_source_length {
+        Some(limit) if source.len() > limit => Err(SourceTooLarge {
+            length: source.len(),
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Scans the whole source, keeping comments as trivia tokens. Useful for
+/// tools that reproduce the source, like formatters.
+#[must_use]
+pub fn tokenize_with_trivia(path: impl Into<PathId>, source: &str) -> Vec<Token> {
+    Lexer::new(path.into(), source).with_trivia().collect()
+}
 
-    /// Last processed string.
-    processed_string: StringId,
+/// Scans the whole source like [`tokenize_with_trivia`], unless it is
+/// longer than `max_source_length` bytes. `None` means no limit.
+///
+/// # Errors
+/// Returns [`SourceTooLarge`] without scanning anything if the source
+/// exceeds the limit.
+pub fn tokenize_with_trivia_and_max_source_length(
+    path: impl Into<PathId>,
+    source: &str,
+    max_source_length: Option<usize>,
+) -> Result<Vec<Token>, SourceTooLarge> {
+    check_source_length(source, max_source_length)?;
 
-    /// Last processed number.
-    processed_number: f64,
+    Ok(tokenize_with_trivia(path, source))
 }
 
 impl<'s> Lexer<'s> {
+    /// Creates lexer for `source`. A byte order mark at the start of
+    /// the source is skipped, offsets of tokens still count its bytes.
+    /// Anywhere else it is an unexpected character.
     #[inline]
     #[must_use]
     pub fn new(path: PathId, source: &'s str) -> Self {
-        let eof_offset = source.len();
-        let mut chars = source.chars();
+        let bom_length = if source.starts_with(BYTE_ORDER_MARK) {
+            BYTE_ORDER_MARK.len_utf8()
+        } else {
+            0
+        };
+        let mut chars = source[bom_length..].chars();
 
         let current = chars.next();
         let next = chars.next();
 
         Self {
             path,
-            chars: source.chars(),
+            chars,
             source,
-            location: CharLocation::new(1, 0, 0),
+            location: CharLocation::new(1, 0, bom_length),
             current,
             next,
-            processed_identifier: DUMMY_IDENTIFIER_ID,
-            processed_string: DUMMY_STRING_ID,
-            processed_number: 0.0,
+            diagnostics: Vec::new(),
+            max_token_length: None,
+            emit_trivia: false,
+            disabled_keywords: HashSet::new(),
+            hex_floats: false,
+            warn_mixed_indentation: false,
+            fail_fast: false,
+            stopped: false,
         }
     }
 
+    /// Makes the lexer emit comments as [`RawToken::Comment`] tokens.
+    #[inline]
+    #[must_use]
+    pub const fn with_trivia(mut self) -> Self {
+        self.emit_trivia = true;
+        self
+    }
+
+    /// Limits length of identifiers and string literals (in bytes, including
+    /// quotes and backticks). Longer ones are reported as
+    /// [`RawLexError::TokenTooLong`]. There is no limit by default.
+    #[inline]
+    #[must_use]
+    pub const fn with_max_token_length(mut self, limit: usize) -> Self {
+        self.max_token_length = Some(limit);
+        self
+    }
+
+    /// Enables hexadecimal floating point numbers with a hexadecimal
+    /// mantissa and a binary exponent, e.g. `0x1.8p3` is `1.5 * 2^3`.
+    ///
+    /// They are opt-in, because they complicate the number grammar: a dot
+    /// followed by a hexadecimal digit always continues the number, so
+    /// `0xA.b` is a number rather than member access on `0xA`.
+    #[inline]
+    #[must_use]
+    pub const fn with_hex_floats(mut self) -> Self {
+        self.hex_floats = true;
+        self
+    }
+
+    /// Reports a warning for lines indented with both tabs and spaces,
+    /// e.g. a tab followed by spaces. Whitespace after the first
+    /// non-whitespace character of the line is not checked.
+    #[inline]
+    #[must_use]
+    pub const fn with_mixed_indentation_warnings(mut self) -> Self {
+        self.warn_mixed_indentation = true;
+        self
+    }
+
+    /// Stops scanning after the first [`RawToken::Error`] token, so that
+    /// errors it causes further on are not reported. By default scanning
+    /// continues to the end of the source.
+    #[inline]
+    #[must_use]
+    pub const fn with_fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+
+    /// Disables the given keywords, so that they are scanned as plain
+    /// identifiers. Useful for sandboxing embedded scripts. All keywords
+    /// are enabled by default.
+    #[inline]
+    #[must_use]
+    pub fn with_disabled_keywords(mut self, keywords: impl IntoIterator<Item = Keyword>) -> Self {
+        self.disabled_keywords.extend(keywords);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn path(&self) -> PathId {
+        self.path
+    }
+
+    /// Returns position the lexer stopped at: right after the last scanned
+    /// token, before any whitespace following it.
+    #[inline]
+    #[must_use]
+    pub const fn location(&self) -> CharLocation {
+        self.location
+    }
+
+    /// Returns warnings and notes reported so far. Errors are not included
+    /// here, because they are emitted as [`RawToken::Error`] tokens.
+    #[inline]
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     const fn is_eof(&self) -> bool {
         self.current.is_none()
     }
@@ -83,11 +299,14 @@ impl<'s> Lexer<'s> {
                 },
         );
 
-        if self.current == Some('\n') {
+        if likely(!matches!(self.current, Some('\n' | '\r'))) {
+            self.location.set_column(self.location.column() + 1);
+        } else if self.current == Some('\r') && self.next == Some('\n') {
+            // `\r\n` is a single line break, which is counted on `\n`.
+        } else {
+            // `\n` or bare `\r`.
             self.location.set_line(self.location.line() + 1);
             self.location.set_column(0);
-        } else {
-            self.location.set_column(self.location.column() + 1);
         }
 
         self.current = self.next;
@@ -99,22 +318,63 @@ impl<'s> Lexer<'s> {
         self.advance();
     }
 
-    const fn current_byte_location(&self) -> Location {
-        Location::new(self.location, self.location.next_byte_location())
-    }
-
     fn advance_with(&mut self, raw: impl Into<RawToken>) -> Option<Token> {
-        let token = Token::new(raw.into(), self.current_byte_location());
+        let start_location = self.location;
         self.advance();
 
-        Some(token)
+        Some(Token::new(raw.into(), self.location_from(start_location)))
     }
 
     fn advance_twice_with(&mut self, raw: impl Into<RawToken>) -> Option<Token> {
-        let token = Token::new(raw.into(), self.current_byte_location());
+        let start_location = self.location;
         self.advance_twice();
 
-        Some(token)
+        Some(Token::new(raw.into(), self.location_from(start_location)))
+    }
+
+    /// Scans two-character punctuator `short`, or three-character `long`
+    /// one if the third character is `third`, e.g. `>>` or `>>>`.
+    fn advance_two_or_three_with(
+        &mut self,
+        third: char,
+        short: Punctuator,
+        long: Punctuator,
+    ) -> Option<Token> {
+        let start_location = self.location;
+        self.advance_twice();
+
+        let raw = if self.current == Some(third) {
+            self.advance();
+            long
+        } else {
+            short
+        };
+
+        Some(Token::new(raw.into(), self.location_from(start_location)))
+    }
+
+    /// Scans `>>`, `>>=`, `>>>` or `>>>=`.
+    fn next_right_shift(&mut self) -> Option<Token> {
+        let start_location = self.location;
+        self.advance_twice();
+
+        let raw = match (self.current, self.next) {
+            (Some('>'), Some('=')) => {
+                self.advance_twice();
+                Punctuator::TripleRightShiftEq
+            }
+            (Some('>'), _) => {
+                self.advance();
+                Punctuator::TripleGreater
+            }
+            (Some('='), _) => {
+                self.advance();
+                Punctuator::RightShiftEq
+            }
+            _ => Punctuator::RightShift,
+        };
+
+        Some(Token::new(raw.into(), self.location_from(start_location)))
     }
 
     fn advance_while(
@@ -130,11 +390,41 @@ impl<'s> Lexer<'s> {
     }
 
     fn skip_whitespaces(&mut self) {
-        self.advance_while(self.location, |current, _| current.is_whitespace());
+        let start_location = self.location;
+        let whitespaces = self.advance_while(start_location, |current, _| current.is_whitespace());
+
+        if self.warn_mixed_indentation && !self.is_eof() {
+            self.check_indentation(start_location, whitespaces);
+        }
+    }
+
+    /// Warns if `whitespaces` skipped before the current token end with
+    /// indentation of its line that mixes tabs and spaces.
+    fn check_indentation(&mut self, start_location: CharLocation, whitespaces: &str) {
+        let indentation_start = match whitespaces.rfind(['\n', '\r']) {
+            Some(idx) => idx + 1,
+            None if start_location.column() == 0 => 0,
+            // The token doesn't start its line.
+            None => return,
+        };
+        let indentation = &whitespaces[indentation_start..];
+
+        if indentation.contains(' ') && indentation.contains('\t') {
+            let start = CharLocation::new(
+                self.location.line(),
+                0,
+                start_location.offset() + indentation_start,
+            );
+
+            self.diagnostics.push(Diagnostic::warning(
+                "indentation mixes tabs and spaces",
+                Location::new(self.path, start, self.location),
+            ));
+        }
     }
 
     fn location_from(&self, start_location: CharLocation) -> Location {
-        Location::new(start_location, self.location)
+        Location::new(self.path, start_location, self.location)
     }
 
     fn next_identifier_or_keyword(&mut self) -> Token {
@@ -142,25 +432,499 @@ impl<'s> Lexer<'s> {
         let identifier_candidate =
             self.advance_while(start_location, |current, _| current.is_id_continue());
 
-        match Keyword::from(identifier_candidate) {
-            Some(kw) => Token::new(RawToken::Keyword(kw), self.location_from(start_location)),
-            None => Token::new(RawToken::Identifier, self.location_from(start_location)),
+        let location = self.location_from(start_location);
+
+        match self.keyword(identifier_candidate) {
+            Some(kw) => Token::new(RawToken::Keyword(kw), location),
+            None if self.exceeds_max_token_length(location) => {
+                Token::new(RawLexError::TokenTooLong.into(), location)
+            }
+            None => Token::new(RawToken::Identifier(identifier_candidate.into()), location),
+        }
+    }
+
+    /// Returns keyword with the given name, unless it is disabled.
+    fn keyword(&self, name: &str) -> Option<Keyword> {
+        Keyword::from(name).filter(|keyword| !self.disabled_keywords.contains(keyword))
+    }
+
+    /// Returns `true` if identifier or string literal at `location` is longer
+    /// than the maximum token length. Checked before the text is interned,
+    /// so that oversized tokens never reach the interner.
+    fn exceeds_max_token_length(&self, location: Location) -> bool {
+        self.max_token_length
+            .is_some_and(|limit| location.end().offset() - location.start().offset() > limit)
+    }
+
+    /// Scans identifier wrapped in backticks, e.g. `` `while` ``, which allows
+    /// using keywords as identifiers.
+    fn next_wrapped_identifier(&mut self) -> Token {
+        let start_location = self.location;
+        self.advance(); // `
+
+        let name_start_location = self.location;
+        let name = self.advance_while(name_start_location, |current, _| {
+            current != Some('`') && current != Some('\n')
+        });
+
+        if self.current != Some('`') {
+            return Token::new(
+                RawLexError::UnterminatedWrappedIdentifier.into(),
+                self.location_from(start_location),
+            );
+        }
+
+        self.advance(); // `
+
+        let location = self.location_from(start_location);
+
+        if name.is_empty() {
+            return Token::new(RawLexError::EmptyWrappedIdentifier.into(), location);
+        }
+
+        if self.exceeds_max_token_length(location) {
+            return Token::new(RawLexError::TokenTooLong.into(), location);
+        }
+
+        if self.keyword(name).is_none() {
+            self.diagnostics.push(Diagnostic::warning(
+                format!("unnecessary backticks around identifier `{name}`"),
+                location,
+            ));
+        }
+
+        Token::new(RawToken::Identifier(IdentifierId::from(name)), location)
+    }
+
+    /// Scans line comment (`// ...`) or block comment (`/* ... */`).
+    fn next_comment(&mut self) -> Token {
+        let start_location = self.location;
+
+        if self.next == Some('/') {
+            self.advance_while(start_location, |current, _| {
+                !matches!(current, Some('\n' | '\r'))
+            });
+
+            return Token::new(RawToken::Comment, self.location_from(start_location));
+        }
+
+        self.advance_twice(); // /*
+
+        while self.current != Some('*') || self.next != Some('/') {
+            if self.is_eof() {
+                return Token::new(
+                    RawLexError::UnterminatedBlockComment.into(),
+                    self.location_from(start_location),
+                );
+            }
+
+            self.advance();
+        }
+
+        self.advance_twice(); // */
+        Token::new(RawToken::Comment, self.location_from(start_location))
+    }
+
+    /// Scans string literal, e.g. `"hello\n"`.
+    ///
+    /// If the string contains an invalid escape sequence, scanning continues
+    /// up to the closing quote and an error located at the first invalid
+    /// escape sequence is returned.
+    fn next_string(&mut self) -> Token {
+        let start_location = self.location;
+        self.advance(); // "
+
+        let mut value = String::new();
+        let mut error = None;
+
+        loop {
+            match self.current {
+                None => {
+                    return Token::new(
+                        RawLexError::UnterminatedStringLiteral.into(),
+                        self.location_from(start_location),
+                    );
+                }
+                Some('"') => break,
+                Some('\\') => match self.next_escape_sequence() {
+                    Ok(c) => value.push(c),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                },
+                Some(c) => {
+                    value.push(c);
+                    self.advance();
+                }
+            }
+        }
+
+        self.advance(); // "
+
+        if let Some(error) = error {
+            return Token::from(error);
+        }
+
+        let location = self.location_from(start_location);
+
+        if self.exceeds_max_token_length(location) {
+            return Token::new(RawLexError::TokenTooLong.into(), location);
+        }
+
+        Token::new(RawToken::Text(StringId::from(value)), location)
+    }
+
+    /// Scans escape sequence, e.g. `\n`, starting at the backslash.
+    fn next_escape_sequence(&mut self) -> Result<char, LexError> {
+        let start_location = self.location;
+        self.advance(); // \
+
+        let c = match self.current {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+            Some('u') => return self.next_unicode_escape_sequence(start_location),
+            None => {
+                return Err(LexError::new(
+                    RawLexError::EmptyEscapeSequence,
+                    self.location_from(start_location),
+                ));
+            }
+            Some(c) => {
+                self.advance();
+
+                let location = self.location_from(start_location);
+                self.diagnostics
+                    .push(Diagnostic::note(escape_sequence_suggestion(c), location));
+
+                return Err(LexError::new(RawLexError::UnknownEscapeSequence, location));
+            }
+        };
+
+        self.advance();
+        Ok(c)
+    }
+
+    /// Scans Unicode escape sequence, e.g. `\u{1F600}`, starting at the `u`.
+    ///
+    /// Surrogates and values above `0x10FFFF` are reported as
+    /// [`RawLexError::InvalidUnicodeEscapeSequence`] located at the whole
+    /// escape sequence.
+    fn next_unicode_escape_sequence(
+        &mut self,
+        start_location: CharLocation,
+    ) -> Result<char, LexError> {
+        self.advance(); // u
+
+        if self.current != Some('{') {
+            return Err(LexError::new(
+                RawLexError::ExpectedOpenBracketInUnicodeEscapeSequence,
+                self.location_from(start_location),
+            ));
+        }
+
+        self.advance(); // {
+
+        let digits_start_location = self.location;
+        let digits = self.advance_while(digits_start_location, |current, _| {
+            current.is_ascii_hexdigit()
+        });
+
+        if digits.is_empty() {
+            return Err(LexError::new(
+                RawLexError::ExpectedDigitInUnicodeEscapeSequence,
+                self.location_from(start_location),
+            ));
+        }
+
+        if self.current != Some('}') {
+            return Err(LexError::new(
+                RawLexError::ExpectedCloseBracketInUnicodeEscapeSequence,
+                self.location_from(start_location),
+            ));
+        }
+
+        self.advance(); // }
+
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                LexError::new(
+                    RawLexError::InvalidUnicodeEscapeSequence,
+                    self.location_from(start_location),
+                )
+            })
+    }
+
+    /// Scans digits of a decimal number, allowing underscores between them.
+    /// Returns `false` if underscores don't separate successive digits.
+    fn advance_digits(&mut self) -> bool {
+        let mut valid = true;
+
+        while self.current.is_ascii_digit() || self.current == Some('_') {
+            if self.current == Some('_') && !self.next.is_ascii_digit() {
+                valid = false;
+            }
+
+            self.advance();
+        }
+
+        valid
+    }
+
+    /// Scans integer with a radix prefix, e.g. `0xFF`, `0o17` or `0b101`,
+    /// starting at the prefix. Integers that don't fit into `i64`, but fit
+    /// into `u64`, e.g. `0xFFFF_FFFF_FFFF_FFFF`, are scanned as floats.
+    fn next_radix_number(&mut self, radix: u32) -> Token {
+        let start_location = self.location;
+        self.advance_twice(); // 0x, 0o or 0b
+
+        let mut valid = true;
+        let mut has_digits = false;
+        let mut corresponds_to_base = true;
+
+        // Decimal digits are scanned regardless of the radix, so that `0b12`
+        // is reported as a single invalid number rather than `0b1` and `2`.
+        let is_digit = |c: Option<char>| matches!(c, Some(c) if c.is_ascii_digit() || radix == 16 && c.is_ascii_hexdigit());
+
+        while is_digit(self.current) || self.current == Some('_') {
+            if self.current == Some('_') {
+                valid &= is_digit(self.next);
+            } else {
+                has_digits = true;
+                corresponds_to_base &= matches!(self.current, Some(c) if c.is_digit(radix));
+            }
+
+            self.advance();
+        }
+
+        let location = self.location_from(start_location);
+
+        if !has_digits {
+            return Token::new(RawLexError::NumberContainsNoDigits.into(), location);
+        }
+
+        if !corresponds_to_base {
+            return Token::new(RawLexError::DigitDoesNotCorrespondToBase.into(), location);
+        }
+
+        if !valid {
+            return Token::new(
+                RawLexError::UnderscoreMustSeparateSuccessiveDigits.into(),
+                location,
+            );
+        }
+
+        if radix == 16
+            && self.hex_floats
+            && (self.current == Some('.') && self.next.is_ascii_hexdigit()
+                || matches!(self.current, Some('p' | 'P')))
+        {
+            return self.next_hex_float(start_location);
+        }
+
+        let digits =
+            self.source[start_location.offset() + 2..self.location.offset()].replace('_', "");
+
+        let raw = i64::from_str_radix(&digits, radix)
+            .map(RawToken::Int)
+            .or_else(|_| {
+                u64::from_str_radix(&digits, radix).map(|number| RawToken::Float(number as f64))
+            });
+
+        Token::new(
+            raw.unwrap_or(RawLexError::NumberParseError.into()),
+            location,
+        )
+    }
+
+    /// Scans fractional part and binary exponent of a hexadecimal floating
+    /// point number, e.g. `.8p3` in `0x1.8p3`, see [`Lexer::with_hex_floats`].
+    fn next_hex_float(&mut self, start_location: CharLocation) -> Token {
+        let mut valid = true;
+        let mut fraction_digits = 0;
+
+        if self.current == Some('.') {
+            self.advance(); // .
+
+            while self.current.is_ascii_hexdigit() || self.current == Some('_') {
+                if self.current == Some('_') {
+                    valid &= self.next.is_ascii_hexdigit();
+                } else {
+                    fraction_digits += 1;
+                }
+
+                self.advance();
+            }
+        }
+
+        let mantissa_end = self.location.offset();
+        let mut exponent = 0;
+
+        if matches!(self.current, Some('p' | 'P')) {
+            self.advance(); // p
+
+            let exponent_start = self.location.offset();
+
+            if matches!(self.current, Some('+' | '-')) {
+                self.advance();
+            }
+
+            if !self.current.is_ascii_digit() {
+                return Token::new(
+                    RawLexError::ExponentHasNoDigits.into(),
+                    self.location_from(start_location),
+                );
+            }
+
+            valid &= self.advance_digits();
+
+            match self.source[exponent_start..self.location.offset()]
+                .replace('_', "")
+                .parse::<i32>()
+            {
+                Ok(value) => exponent = value,
+                Err(_) => {
+                    return Token::new(
+                        RawLexError::NumberParseError.into(),
+                        self.location_from(start_location),
+                    )
+                }
+            }
+        }
+
+        let location = self.location_from(start_location);
+
+        if !valid {
+            return Token::new(
+                RawLexError::UnderscoreMustSeparateSuccessiveDigits.into(),
+                location,
+            );
+        }
+
+        let digits = self.source[start_location.offset() + 2..mantissa_end].replace(['_', '.'], "");
+
+        match u64::from_str_radix(&digits, 16) {
+            // Every hexadecimal digit of the fraction is 4 bits.
+            Ok(mantissa) => Token::new(
+                RawToken::Float(
+                    mantissa as f64 * 2_f64.powi(exponent.saturating_sub(4 * fraction_digits)),
+                ),
+                location,
+            ),
+            Err(_) => Token::new(RawLexError::NumberParseError.into(), location),
+        }
+    }
+
+    /// Scans number literal.
+    ///
+    /// Integers with a leading zero, like `0123`, are decimal (unlike in C,
+    /// where they are octal). Octal integers must be written with the `0o`
+    /// prefix, e.g. `0o123`, hexadecimal with `0x` and binary with `0b`.
+    ///
+    /// Numbers with a radix point or an exponent are floats, other ones are
+    /// integers, unless they don't fit into `i64` and are scanned as floats.
+    ///
+    /// Numbers are never negative: `-5` is scanned as [`Punctuator::Minus`]
+    /// followed by `5` and it is up to the parser to build unary expression.
+    ///
+    /// Integer part may be omitted, e.g. `.5` is `0.5`: a dot followed by a
+    /// digit always starts a number, so `a.5` is `a` followed by `.5` rather
+    /// than member access. Fractional part can't be omitted, `1.` is `1`
+    /// followed by a dot, so that methods can be called on number literals.
+    fn next_number(&mut self) -> Token {
+        if self.current == Some('0') {
+            match self.next {
+                Some('x' | 'X') => return self.next_radix_number(16),
+                Some('o' | 'O') => return self.next_radix_number(8),
+                Some('b' | 'B') => return self.next_radix_number(2),
+                _ => {}
+            }
+        }
+
+        let start_location = self.location;
+        let mut valid = self.advance_digits();
+        let mut is_float = false;
+
+        if self.current == Some('.') && self.next.is_ascii_digit() {
+            self.advance(); // .
+            valid &= self.advance_digits();
+            is_float = true;
+        }
+
+        if matches!(self.current, Some('e' | 'E')) {
+            self.advance(); // e
+            is_float = true;
+
+            if matches!(self.current, Some('+' | '-')) {
+                self.advance();
+            }
+
+            if !self.current.is_ascii_digit() {
+                return Token::new(
+                    RawLexError::ExponentHasNoDigits.into(),
+                    self.location_from(start_location),
+                );
+            }
+
+            valid &= self.advance_digits();
+        }
+
+        let location = self.location_from(start_location);
+
+        if !valid {
+            return Token::new(
+                RawLexError::UnderscoreMustSeparateSuccessiveDigits.into(),
+                location,
+            );
         }
+
+        let number = self.source[start_location.offset()..self.location.offset()].replace('_', "");
+        let raw = (!is_float)
+            .then(|| number.parse().map(RawToken::Int).ok())
+            .flatten()
+            .or_else(|| number.parse().map(RawToken::Float).ok());
+
+        Token::new(
+            raw.unwrap_or(RawLexError::NumberParseError.into()),
+            location,
+        )
     }
 }
 
-impl Iterator for Lexer<'_> {
-    type Item = Token;
+impl Lexer<'_> {
+    fn next_token(&mut self) -> Option<Token> {
+        loop {
+            self.skip_whitespaces();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.skip_whitespaces();
+            if self.current != Some('/') || !matches!(self.next, Some('/' | '*')) {
+                break;
+            }
+
+            let comment = self.next_comment();
+
+            if self.emit_trivia || comment.raw() != RawToken::Comment {
+                return Some(comment);
+            }
+        }
 
         if unlikely(self.is_eof()) {
             return None;
         }
 
         match (self.current, self.next) {
+            (Some(':'), Some(':')) => self.advance_two_or_three_with(
+                '=',
+                Punctuator::DoubleColon,
+                Punctuator::DoubleColonEq,
+            ),
             (Some(':'), _) => self.advance_with(Punctuator::Colon),
+            (Some('@'), Some('=')) => self.advance_twice_with(Punctuator::AtEq),
             (Some('@'), _) => self.advance_with(Punctuator::At),
             (Some('+'), Some('+')) => self.advance_twice_with(Punctuator::DoublePlus),
             (Some('+'), Some('=')) => self.advance_twice_with(Punctuator::PlusEq),
@@ -176,10 +940,12 @@ impl Iterator for Lexer<'_> {
             (Some('/'), _) => self.advance_with(Punctuator::Slash),
             (Some('!'), Some('=')) => self.advance_twice_with(Punctuator::BangEq),
             (Some('!'), _) => self.advance_with(Punctuator::Bang),
-            (Some('>'), Some('>')) => self.advance_twice_with(Punctuator::RightShift),
+            (Some('>'), Some('>')) => self.next_right_shift(),
             (Some('>'), Some('=')) => self.advance_twice_with(Punctuator::GreaterEq),
             (Some('>'), _) => self.advance_with(Punctuator::Greater),
-            (Some('<'), Some('<')) => self.advance_twice_with(Punctuator::LeftShift),
+            (Some('<'), Some('<')) => {
+                self.advance_two_or_three_with('=', Punctuator::LeftShift, Punctuator::LeftShiftEq)
+            }
             (Some('<'), Some('=')) => self.advance_twice_with(Punctuator::LessEq),
             (Some('<'), _) => self.advance_with(Punctuator::Less),
             (Some('='), Some('=')) => self.advance_twice_with(Punctuator::DoubleEq),
@@ -191,8 +957,10 @@ impl Iterator for Lexer<'_> {
             (Some('?'), Some(':')) => self.advance_twice_with(Punctuator::QuestionColon),
             (Some('?'), _) => self.advance_with(Punctuator::Question),
             (Some('&'), Some('&')) => self.advance_twice_with(Punctuator::DoubleAmpersand),
+            (Some('&'), Some('=')) => self.advance_twice_with(Punctuator::AmpersandEq),
             (Some('&'), _) => self.advance_with(Punctuator::Ampersand),
             (Some('^'), Some('=')) => self.advance_twice_with(Punctuator::CaretEq),
+            (Some('^'), Some('^')) => self.advance_twice_with(Punctuator::DoubleCaret),
             (Some('^'), _) => self.advance_with(Punctuator::Caret),
             (Some('~'), _) => self.advance_with(Punctuator::Tilde),
             (Some('('), _) => self.advance_with(Punctuator::OpenParent),
@@ -203,10 +971,19 @@ impl Iterator for Lexer<'_> {
             (Some('}'), _) => self.advance_with(Punctuator::CloseBrace),
             (Some(','), _) => self.advance_with(Punctuator::Comma),
             (Some(';'), _) => self.advance_with(Punctuator::Semicolon),
-            (Some('%'), Some('=')) => self.advance_with(Punctuator::PercentEq),
+            (Some('%'), Some('=')) => self.advance_twice_with(Punctuator::PercentEq),
             (Some('%'), _) => self.advance_with(Punctuator::Percent),
             (Some('.'), Some('.')) => self.advance_twice_with(Punctuator::DoubleDot),
+            (Some('.'), next) if !next.is_ascii_digit() => self.advance_with(Punctuator::Dot),
+            (Some('`'), _) => Some(self.next_wrapped_identifier()),
+            (Some('"'), _) => Some(self.next_string()),
             _ => {
+                if self.current.is_ascii_digit()
+                    || (self.current == Some('.') && self.next.is_ascii_digit())
+                {
+                    return Some(self.next_number());
+                }
+
                 if self.current.is_id_start() {
                     return Some(self.next_identifier_or_keyword());
                 }
@@ -217,10 +994,42 @@ impl Iterator for Lexer<'_> {
     }
 }
 
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped {
+            return None;
+        }
+
+        let token = self.next_token()?;
+
+        if self.fail_fast && matches!(token.raw(), RawToken::Error(_)) {
+            self.stopped = true;
+        }
+
+        Some(token)
+    }
+}
+
+/// Returns note for unknown escape sequence `\{c}`, suggesting the known
+/// one differing only in case, e.g. `\n` for `\N`, or listing all of them.
+fn escape_sequence_suggestion(c: char) -> String {
+    match c.to_ascii_lowercase() {
+        known @ ('n' | 'r' | 't') => format!("did you mean `\\{known}`?"),
+        'u' => "did you mean `\\u{...}`?".to_owned(),
+        _ => "known escape sequences are `\\n`, `\\r`, `\\t`, `\\0`, `\\\\`, \
+              `\\'`, `\\\"` and `\\u{...}`"
+            .to_owned(),
+    }
+}
+
 trait CharExt {
     fn is_whitespace(&self) -> bool;
     fn is_id_start(&self) -> bool;
     fn is_id_continue(&self) -> bool;
+    fn is_ascii_digit(&self) -> bool;
+    fn is_ascii_hexdigit(&self) -> bool;
 }
 
 impl CharExt for Option<char> {
@@ -257,4 +1066,637 @@ impl CharExt for Option<char> {
     fn is_id_continue(&self) -> bool {
         matches!(self, Some(c) if unicode_xid::UnicodeXID::is_xid_continue(*c))
     }
+
+    fn is_ascii_digit(&self) -> bool {
+        matches!(self, Some(c) if c.is_ascii_digit())
+    }
+
+    fn is_ascii_hexdigit(&self) -> bool {
+        matches!(self, Some(c) if c.is_ascii_hexdigit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+    use crate::location::HasLocation;
+
+    #[test]
+    fn unnecessary_wrapped_identifier_warns() {
+        let mut lexer = Lexer::new(PathId::from("test.lzr"), "`foo`");
+
+        assert_eq!(
+            lexer.next().map(|token| token.raw()),
+            Some(RawToken::Identifier(IdentifierId::from("foo")))
+        );
+        assert_eq!(lexer.diagnostics().len(), 1);
+        assert_eq!(lexer.diagnostics()[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn unknown_escape_sequence_suggestion() {
+        let mut lexer = Lexer::new(PathId::from("test.lzr"), r#""a\Nb" "\q""#);
+
+        let error = lexer.next().unwrap();
+        assert_eq!(
+            error.raw(),
+            RawToken::Error(RawLexError::UnknownEscapeSequence)
+        );
+
+        let note = &lexer.diagnostics()[0];
+        assert_eq!(note.severity(), Severity::Note);
+        assert_eq!(note.message(), r"did you mean `\n`?");
+        assert_eq!(note.location(), error.location());
+
+        lexer.next();
+        assert!(lexer.diagnostics()[1]
+            .message()
+            .starts_with(r"known escape sequences are `\n`"));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let source = "\u{FEFF}a;";
+        let tokens = tokenize("test.lzr", source);
+
+        assert_eq!(
+            tokens[0].raw(),
+            RawToken::Identifier(IdentifierId::from("a"))
+        );
+        assert_eq!(tokens[0].lexeme(source), "a");
+        assert_eq!(tokens[0].location().start(), CharLocation::new(1, 0, 3));
+        assert_eq!(tokens.len(), 2);
+
+        assert_eq!(
+            tokenize("test.lzr", "a\u{FEFF}")[1].raw(),
+            RawToken::Error(RawLexError::UnexpectedChar)
+        );
+    }
+
+    #[test]
+    fn new_is_a_keyword() {
+        assert_eq!(
+            tokenize("test.lzr", "new")
+                .iter()
+                .map(Token::raw)
+                .collect::<Vec<_>>(),
+            vec![RawToken::Keyword(Keyword::New)]
+        );
+    }
+
+    #[test]
+    fn wrapped_keyword_does_not_warn() {
+        let mut lexer = Lexer::new(PathId::from("test.lzr"), "`while`");
+
+        assert_eq!(
+            lexer.next().map(|token| token.raw()),
+            Some(RawToken::Identifier(IdentifierId::from("while")))
+        );
+        assert!(lexer.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn token_length_limit() {
+        let tokens = Lexer::new(PathId::from("test.lzr"), "abc abcd \"ab\" \"abc\"")
+            .with_max_token_length(4)
+            .map(|token| (token.raw(), token.location().start().offset()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (RawToken::Identifier(IdentifierId::from("abc")), 0),
+                (RawToken::Identifier(IdentifierId::from("abcd")), 4),
+                (RawToken::Text(StringId::from("ab")), 9),
+                (RawToken::Error(RawLexError::TokenTooLong), 14),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_long_tokens_are_not_interned() {
+        let tokens = Lexer::new(PathId::from("test.lzr"), "toolong `toolong2` \"toolong3\"")
+            .with_max_token_length(4)
+            .map(|token| token.raw())
+            .collect::<Vec<_>>();
+
+        assert_eq!(tokens, vec![RawToken::Error(RawLexError::TokenTooLong); 3]);
+        assert!(crate::interner::dump_identifiers()
+            .iter()
+            .all(|(_, name)| !name.starts_with("toolong")));
+        assert!(crate::interner::dump_strings()
+            .iter()
+            .all(|(_, string)| !string.starts_with("toolong")));
+    }
+
+    #[test]
+    fn no_token_length_limit_by_default() {
+        let source = "a".repeat(100_000);
+
+        assert!(matches!(
+            Lexer::new(PathId::from("test.lzr"), &source)
+                .next()
+                .map(|token| token.raw()),
+            Some(RawToken::Identifier(_))
+        ));
+    }
+
+    fn lex_single(source: &str) -> Token {
+        let tokens = Lexer::new(PathId::from("test.lzr"), source).collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 1, "expected a single token in {source:?}");
+
+        tokens[0]
+    }
+
+    #[test]
+    fn string_span_includes_quotes() {
+        let token = lex_single("\"abc\"");
+
+        assert_eq!(token.raw(), RawToken::Text(StringId::from("abc")));
+        assert_eq!(token.location().start().offset(), 0);
+        assert_eq!(token.location().end().offset(), 5);
+    }
+
+    #[test]
+    fn hex_number_span_includes_prefix() {
+        let token = lex_single("0xFF");
+
+        assert_eq!(token.raw(), RawToken::Int(255));
+        assert_eq!(token.location().start().offset(), 0);
+        assert_eq!(token.location().end().offset(), 4);
+    }
+
+    #[test]
+    fn operator_span_covers_all_characters() {
+        let token = lex_single("==");
+
+        assert_eq!(token.raw(), RawToken::Punctuator(Punctuator::DoubleEq));
+        assert_eq!(token.location().end().offset(), 2);
+        assert_eq!(token.location().end().column(), 2);
+    }
+
+    #[test]
+    fn error_span_covers_multibyte_character() {
+        let token = lex_single("№");
+
+        assert_eq!(token.raw(), RawToken::Error(RawLexError::UnexpectedChar));
+        assert_eq!(token.location().end().offset(), "№".len());
+    }
+
+    #[test]
+    fn unicode_escape_sequence() {
+        assert_eq!(
+            lex_single("\"\\u{48}\\u{1F600}\"").raw(),
+            RawToken::Text(StringId::from("H\u{1F600}"))
+        );
+    }
+
+    #[test]
+    fn surrogate_unicode_escape_sequence() {
+        let mut lexer = Lexer::new(PathId::from("test.lzr"), "\"a\\u{D800}b\" x");
+        let token = lexer.next().unwrap();
+
+        assert_eq!(
+            token.raw(),
+            RawToken::Error(RawLexError::InvalidUnicodeEscapeSequence)
+        );
+        assert_eq!(token.location().start().offset(), 2);
+        assert_eq!(token.location().end().offset(), 10);
+        assert_eq!(
+            lexer.next().map(|token| token.raw()),
+            Some(RawToken::Identifier(IdentifierId::from("x")))
+        );
+    }
+
+    #[test]
+    fn out_of_range_unicode_escape_sequence() {
+        let mut lexer = Lexer::new(
+            PathId::from("test.lzr"),
+            "\"\\u{110000}\" \"\\u{FFFFFFFFF}\"",
+        );
+
+        for _ in 0..2 {
+            assert_eq!(
+                lexer.next().map(|token| token.raw()),
+                Some(RawToken::Error(RawLexError::InvalidUnicodeEscapeSequence))
+            );
+        }
+
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn token_locations_carry_path() {
+        let a = PathId::from("a.lzr");
+        let b = PathId::from("b.lzr");
+
+        let token_a = Lexer::new(a, "x").next().unwrap();
+        let token_b = Lexer::new(b, "x").next().unwrap();
+
+        assert_eq!(token_a.location().path(), a);
+        assert_eq!(token_b.location().path(), b);
+        assert_ne!(token_a.location(), token_b.location());
+    }
+
+    #[test]
+    fn comments_are_skipped_by_default() {
+        assert_eq!(
+            tokenize("test.lzr", "// a\n/* b */ x // c")
+                .iter()
+                .map(Token::raw)
+                .collect::<Vec<_>>(),
+            vec![RawToken::Identifier(IdentifierId::from("x"))]
+        );
+    }
+
+    #[test]
+    fn comments_are_trivia_tokens() {
+        let tokens = tokenize_with_trivia("test.lzr", "// a\n/* b */ x");
+
+        assert_eq!(tokens.len(), 3);
+        assert!(tokens[0].raw().is_trivia());
+        assert_eq!(tokens[0].len(), 4);
+        assert!(tokens[1].raw().is_trivia());
+        assert_eq!(tokens[1].len(), 7);
+        assert!(!tokens[2].raw().is_trivia());
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        assert_eq!(
+            lex_single("/* a").raw(),
+            RawToken::Error(RawLexError::UnterminatedBlockComment)
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let lines = |source: &str| {
+            tokenize("test.lzr", source)
+                .iter()
+                .map(|token| {
+                    let location = token.location();
+
+                    (
+                        location.start().line(),
+                        location.start().column(),
+                        location.end().line(),
+                        location.end().column(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let lf = lines("a = 1;\n  b = \"x\ny\";\n\nc");
+
+        assert_eq!(lines("a = 1;\r\n  b = \"x\r\ny\";\r\n\r\nc"), lf);
+        assert_eq!(lines("a = 1;\r  b = \"x\ry\";\r\rc"), lf);
+        assert_eq!(lines("// a\r\nb"), lines("// a\nb"));
+        assert_eq!(lines("// a\rb"), lines("// a\nb"));
+    }
+
+    #[test]
+    fn leading_zero_is_decimal() {
+        assert_eq!(lex_single("0123").raw(), RawToken::Int(123));
+        assert_eq!(lex_single("08").raw(), RawToken::Int(8));
+        assert_eq!(lex_single("00").raw(), RawToken::Int(0));
+    }
+
+    #[test]
+    fn integers_and_floats() {
+        assert_eq!(lex_single("42").raw(), RawToken::Int(42));
+        assert_eq!(lex_single("42.0").raw(), RawToken::Float(42.0));
+        assert_eq!(lex_single("4e2").raw(), RawToken::Float(400.0));
+        assert_eq!(
+            lex_single("9223372036854775807").raw(),
+            RawToken::Int(i64::MAX)
+        );
+        assert_eq!(
+            lex_single("9223372036854775808").raw(),
+            RawToken::Float(9_223_372_036_854_775_808.0)
+        );
+        assert_eq!(
+            lex_single("0xFFFF_FFFF_FFFF_FFFF").raw(),
+            RawToken::Float(u64::MAX as f64)
+        );
+        assert_eq!(
+            lex_single("0x1_0000_0000_0000_0000").raw(),
+            RawToken::Error(RawLexError::NumberParseError)
+        );
+    }
+
+    #[test]
+    fn radix_prefixes() {
+        assert_eq!(lex_single("0o123").raw(), RawToken::Int(83));
+        assert_eq!(lex_single("0b1_01").raw(), RawToken::Int(5));
+        assert_eq!(lex_single("0x1F").raw(), RawToken::Int(31));
+        assert_eq!(
+            lex_single("0o8").raw(),
+            RawToken::Error(RawLexError::DigitDoesNotCorrespondToBase)
+        );
+        assert_eq!(
+            lex_single("0b12").raw(),
+            RawToken::Error(RawLexError::DigitDoesNotCorrespondToBase)
+        );
+        assert_eq!(
+            lex_single("0o").raw(),
+            RawToken::Error(RawLexError::NumberContainsNoDigits)
+        );
+    }
+
+    #[test]
+    fn disabled_keywords() {
+        let raw = |lexer: Lexer<'_>| lexer.map(|token| token.raw()).collect::<Vec<_>>();
+        let source = "include using";
+
+        assert_eq!(
+            raw(Lexer::new(PathId::from("test.lzr"), source)),
+            vec![
+                RawToken::Keyword(Keyword::Include),
+                RawToken::Keyword(Keyword::Using)
+            ]
+        );
+        assert_eq!(
+            raw(Lexer::new(PathId::from("test.lzr"), source)
+                .with_disabled_keywords([Keyword::Include])),
+            vec![
+                RawToken::Identifier(IdentifierId::from("include")),
+                RawToken::Keyword(Keyword::Using)
+            ]
+        );
+    }
+
+    #[test]
+    fn shifts() {
+        let raw = |source| {
+            tokenize("test.lzr", source)
+                .iter()
+                .map(Token::raw)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            raw(">>> >> >"),
+            vec![
+                RawToken::Punctuator(Punctuator::TripleGreater),
+                RawToken::Punctuator(Punctuator::RightShift),
+                RawToken::Punctuator(Punctuator::Greater)
+            ]
+        );
+    }
+
+    #[test]
+    fn location_after_tokens() {
+        let mut lexer = Lexer::new(PathId::from("test.lzr"), "ab cd\n  ef");
+        assert_eq!(lexer.location(), CharLocation::new(1, 0, 0));
+
+        lexer.nth(1);
+        assert_eq!(lexer.location(), CharLocation::new(1, 5, 5));
+
+        lexer.next();
+        assert_eq!(lexer.location(), CharLocation::new(2, 4, 10));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn mixed_indentation() {
+        let warnings = |source| {
+            let mut lexer =
+                Lexer::new(PathId::from("test.lzr"), source).with_mixed_indentation_warnings();
+            lexer.by_ref().for_each(drop);
+
+            lexer
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| diagnostic.location().start().line())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(warnings("a;\n\t b;\n \tc;"), vec![2, 3]);
+        assert_eq!(warnings("\t \ta;"), vec![1]);
+        assert!(warnings("a;\n\t\tb;\n    c;").is_empty());
+        assert!(warnings("a;\t \tb;\n\t \n\tc; \t").is_empty());
+
+        // The check is opt-in.
+        let mut lexer = Lexer::new(PathId::from("test.lzr"), "\t a;");
+        lexer.by_ref().for_each(drop);
+        assert!(lexer.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn fail_fast() {
+        let source = "a # b $ c";
+        let errors = |lexer: Lexer| {
+            lexer
+                .filter(|token| matches!(token.raw(), RawToken::Error(_)))
+                .count()
+        };
+
+        assert_eq!(errors(Lexer::new(PathId::from("test.lzr"), source)), 2);
+        assert_eq!(
+            errors(Lexer::new(PathId::from("test.lzr"), source).with_fail_fast()),
+            1
+        );
+
+        let mut lexer = Lexer::new(PathId::from("test.lzr"), source).with_fail_fast();
+        assert_eq!(
+            lexer.nth(1).map(|token| token.location().start().offset()),
+            Some(2)
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn maximal_munch() {
+        // Pairs of an operator and its shorter prefix, which the lexer
+        // must not stop at.
+        const PAIRS: &[(&str, &str)] = &[
+            ("->", "-"),
+            ("-=", "-"),
+            ("--", "-"),
+            ("+=", "+"),
+            ("++", "+"),
+            ("*=", "*"),
+            ("**", "*"),
+            ("/=", "/"),
+            ("%=", "%"),
+            ("@=", "@"),
+            ("&=", "&"),
+            ("&&", "&"),
+            ("^=", "^"),
+            ("^^", "^"),
+            ("|=", "|"),
+            ("||", "|"),
+            ("==", "="),
+            ("!=", "!"),
+            ("?:", "?"),
+            ("??", "?"),
+            ("..", "."),
+            ("::", ":"),
+            ("::=", "::"),
+            ("::=", ":"),
+            ("<=", "<"),
+            ("<<", "<"),
+            ("<<=", "<<"),
+            ("<<=", "<"),
+            (">=", ">"),
+            (">>", ">"),
+            (">>=", ">>"),
+            (">>=", ">"),
+            (">>>", ">>"),
+            (">>>", ">"),
+            (">>>=", ">>>"),
+            (">>>=", ">>"),
+            (">>>=", ">"),
+        ];
+
+        for (longer, prefix) in PAIRS {
+            assert!(longer.starts_with(prefix));
+
+            let tokens = tokenize("test.lzr", longer);
+
+            assert_eq!(
+                tokens.iter().map(Token::raw).collect::<Vec<_>>(),
+                vec![RawToken::Punctuator(Punctuator::try_from(*longer).unwrap())],
+                "`{longer}` is split after `{prefix}`"
+            );
+        }
+
+        // New operators must be added to the table.
+        for longer in Punctuator::ALL {
+            for prefix in Punctuator::ALL {
+                let (longer, prefix) = (longer.to_string(), prefix.to_string());
+
+                if longer != prefix && longer.starts_with(&prefix) {
+                    assert!(
+                        PAIRS.contains(&(longer.as_str(), prefix.as_str())),
+                        "missing (`{longer}`, `{prefix}`)"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_punctuator_round_trips() {
+        for &punctuator in Punctuator::ALL {
+            let spelling = punctuator.to_string();
+
+            assert_eq!(Punctuator::try_from(spelling.as_str()), Ok(punctuator));
+
+            let tokens = tokenize("test.lzr", &spelling);
+
+            assert_eq!(
+                tokens.iter().map(Token::raw).collect::<Vec<_>>(),
+                vec![RawToken::Punctuator(punctuator)],
+                "`{spelling}` is not lexed as a single punctuator"
+            );
+            assert_eq!(tokens[0].location().end().offset(), spelling.len());
+        }
+    }
+
+    #[test]
+    fn leading_dot_floats() {
+        let raw = |source| {
+            tokenize("test.lzr", source)
+                .iter()
+                .map(Token::raw)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(raw(".5"), vec![RawToken::Float(0.5)]);
+        assert_eq!(raw(".5e1"), vec![RawToken::Float(5.0)]);
+        assert_eq!(
+            raw("a.5"),
+            vec![
+                RawToken::Identifier(IdentifierId::from("a")),
+                RawToken::Float(0.5)
+            ]
+        );
+        assert_eq!(
+            raw("1."),
+            vec![RawToken::Int(1), RawToken::Punctuator(Punctuator::Dot)]
+        );
+        assert_eq!(
+            raw("a.b"),
+            vec![
+                RawToken::Identifier(IdentifierId::from("a")),
+                RawToken::Punctuator(Punctuator::Dot),
+                RawToken::Identifier(IdentifierId::from("b"))
+            ]
+        );
+        assert_eq!(
+            raw("..5"),
+            vec![
+                RawToken::Punctuator(Punctuator::DoubleDot),
+                RawToken::Int(5)
+            ]
+        );
+    }
+
+    #[test]
+    fn hex_floats() {
+        let raw = |source| {
+            Lexer::new(PathId::from("test.lzr"), source)
+                .with_hex_floats()
+                .map(|token| token.raw())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(raw("0x1p4"), vec![RawToken::Float(16.0)]);
+        assert_eq!(raw("0x1.8p1"), vec![RawToken::Float(3.0)]);
+        assert_eq!(raw("0x1.8p3"), vec![RawToken::Float(12.0)]);
+        assert_eq!(raw("0xA.8"), vec![RawToken::Float(10.5)]);
+        assert_eq!(raw("0x1P-2"), vec![RawToken::Float(0.25)]);
+        assert_eq!(raw("0xFF"), vec![RawToken::Int(255)]);
+        assert_eq!(
+            raw("0x1p"),
+            vec![RawToken::Error(RawLexError::ExponentHasNoDigits)]
+        );
+        assert_eq!(
+            raw("0x1.8_p1"),
+            vec![RawToken::Error(
+                RawLexError::UnderscoreMustSeparateSuccessiveDigits
+            )]
+        );
+
+        // Hexadecimal floats are opt-in.
+        assert_eq!(
+            tokenize("test.lzr", "0x1p4")
+                .iter()
+                .map(Token::raw)
+                .collect::<Vec<_>>(),
+            vec![
+                RawToken::Int(1),
+                RawToken::Identifier(IdentifierId::from("p4"))
+            ]
+        );
+    }
+
+    #[test]
+    fn source_too_large() {
+        let error = tokenize_with_max_source_length("test.lzr", "abc", Some(2)).unwrap_err();
+
+        assert_eq!(error.length(), 3);
+        assert_eq!(error.limit(), 2);
+        assert_eq!(
+            tokenize_with_max_source_length("test.lzr", "abc", Some(3)).map(|tokens| tokens.len()),
+            Ok(1)
+        );
+        assert_eq!(
+            error.to_string(),
+            "source is too large: 3 bytes (the limit is 2)"
+        );
+
+        assert!(matches!(
+            tokenize_with_trivia_and_max_source_length("test.lzr", "a // b", Some(5)),
+            Err(error) if error.length() == 6 && error.limit() == 5
+        ));
+        assert_eq!(
+            tokenize_with_trivia_and_max_source_length("test.lzr", "a // b", Some(6))
+                .map(|tokens| tokens.len()),
+            Ok(2)
+        );
+    }
 }
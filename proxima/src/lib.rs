@@ -0,0 +1,17 @@
+pub mod arena;
+pub mod ast;
+pub mod const_eval;
+pub mod diagnostic;
+pub mod interner;
+pub mod interpreter;
+pub mod lexer;
+pub mod location;
+pub mod parser;
+pub mod pretty_printer;
+pub mod reachability;
+pub mod snippet;
+pub mod stable_likely;
+pub mod to_source;
+pub mod token;
+pub mod token_tree;
+pub mod value;
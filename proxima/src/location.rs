@@ -1,3 +1,10 @@
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+use crate::interner::PathId;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct CharLocation {
     line: usize,
@@ -39,6 +46,14 @@ impl CharLocation {
         self.column
     }
 
+    /// Returns 1-based column, as displayed by editors. Use
+    /// [`CharLocation::column`] for computations.
+    #[inline]
+    #[must_use]
+    pub const fn display_column(&self) -> usize {
+        self.column + 1
+    }
+
     #[inline]
     pub fn set_column(&mut self, column: usize) {
         self.column = column;
@@ -60,10 +75,72 @@ impl CharLocation {
     pub const fn next_byte_location(&self) -> Self {
         Self::new(self.line, self.column + 1, self.offset + 1)
     }
+
+    /// Converts the location into LSP position in `source`, the text
+    /// the location points into.
+    #[must_use]
+    pub fn to_lsp_position(&self, source: &str) -> LspPosition {
+        let before = source.get(..self.offset).unwrap_or(source);
+        let line_start = before.rfind(LINE_BREAKS).map_or(0, |idx| idx + 1);
+
+        LspPosition::new(self.line - 1, before[line_start..].encode_utf16().count())
+    }
+
+    /// Converts LSP position in `source` back into location. Returns `None`
+    /// if the position is past the end of its line or of `source`, or
+    /// points into the middle of a character.
+    #[must_use]
+    pub fn from_lsp_position(position: LspPosition, source: &str) -> Option<Self> {
+        let mut line_start = 0;
+
+        for _ in 0..position.line() {
+            let rest = &source[line_start..];
+            let line_break = rest.find(LINE_BREAKS)?;
+            let line_break_len = if rest[line_break..].starts_with("\r\n") {
+                2
+            } else {
+                1
+            };
+
+            line_start += line_break + line_break_len;
+        }
+
+        let line = source[line_start..]
+            .split(LINE_BREAKS)
+            .next()
+            .unwrap_or_default();
+        let mut utf16_column = 0;
+
+        for (column, (idx, c)) in line.char_indices().chain([(line.len(), '\n')]).enumerate() {
+            if utf16_column == position.character() {
+                return Some(Self::new(position.line() + 1, column, line_start + idx));
+            }
+
+            if utf16_column > position.character() {
+                return None;
+            }
+
+            utf16_column += c.len_utf16();
+        }
+
+        None
+    }
+}
+
+/// Characters starting a line break: `\n`, `\r\n` or a bare `\r`, the same
+/// as the lexer counts.
+const LINE_BREAKS: [char; 2] = ['\n', '\r'];
+
+/// Displays the location as `line:column`, e.g. `1:1` for the first character.
+impl Display for CharLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.display_column())
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Location {
+    path: PathId,
     start: CharLocation,
     end: CharLocation,
 }
@@ -71,14 +148,29 @@ pub struct Location {
 impl Location {
     #[inline]
     #[must_use]
-    pub const fn new(start: CharLocation, end: CharLocation) -> Self {
-        Self { start, end }
+    pub const fn new(path: PathId, start: CharLocation, end: CharLocation) -> Self {
+        Self { path, start, end }
     }
 
+    /// Returns the file the location belongs to.
     #[inline]
     #[must_use]
-    pub const fn of_first_byte() -> Self {
+    pub const fn path(&self) -> PathId {
+        self.path
+    }
+
+    /// Returns the same span in another file.
+    #[inline]
+    #[must_use]
+    pub const fn with_path(self, path: PathId) -> Self {
+        Self { path, ..self }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn of_first_byte(path: PathId) -> Self {
         Self::new(
+            path,
             CharLocation::of_first_byte(),
             CharLocation::of_first_byte().next_byte_location(),
         )
@@ -95,9 +187,265 @@ impl Location {
     pub const fn end(&self) -> CharLocation {
         self.end
     }
+
+    /// Returns span from the start of this location to the end of `end`.
+    #[inline]
+    #[must_use]
+    pub const fn to(self, end: Location) -> Self {
+        Self::new(self.path, self.start, end.end)
+    }
+
+    /// Returns the span with its end moved `bytes` further, e.g. to widen
+    /// a zero-width span of an inserted token. The end stays on its line,
+    /// so `bytes` must not reach past a line break.
+    #[inline]
+    #[must_use]
+    pub const fn extend(&self, bytes: usize) -> Self {
+        Self::new(
+            self.path,
+            self.start,
+            CharLocation::new(
+                self.end.line(),
+                self.end.column() + bytes,
+                self.end.offset() + bytes,
+            ),
+        )
+    }
+
+    /// Returns numbers of the first and the last line the span covers.
+    #[inline]
+    #[must_use]
+    pub const fn line_span(&self) -> (usize, usize) {
+        (self.start.line(), self.end.line())
+    }
+
+    /// Returns `true` if the span starts and ends on the same line.
+    #[inline]
+    #[must_use]
+    pub const fn is_single_line(&self) -> bool {
+        self.start.line() == self.end.line()
+    }
+
+    /// Converts the span into LSP range, see [`CharLocation::to_lsp_position`].
+    #[must_use]
+    pub fn to_lsp_range(&self, source: &str) -> LspRange {
+        LspRange::new(
+            self.start.to_lsp_position(source),
+            self.end.to_lsp_position(source),
+        )
+    }
+
+    /// Converts LSP range in the file at `path` back into span, see
+    /// [`CharLocation::from_lsp_position`].
+    #[must_use]
+    pub fn from_lsp_range(path: PathId, range: LspRange, source: &str) -> Option<Self> {
+        Some(Self::new(
+            path,
+            CharLocation::from_lsp_position(range.start(), source)?,
+            CharLocation::from_lsp_position(range.end(), source)?,
+        ))
+    }
+}
+
+/// Position as used by the Language Server Protocol: 0-based line and
+/// offset within the line in UTF-16 code units, e.g. `𝔸` is 2 units wide.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LspPosition {
+    line: usize,
+    character: usize,
+}
+
+impl LspPosition {
+    #[inline]
+    #[must_use]
+    pub const fn new(line: usize, character: usize) -> Self {
+        Self { line, character }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn character(&self) -> usize {
+        self.character
+    }
+}
+
+/// Range as used by the Language Server Protocol, see [`LspPosition`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LspRange {
+    start: LspPosition,
+    end: LspPosition,
+}
+
+impl LspRange {
+    #[inline]
+    #[must_use]
+    pub const fn new(start: LspPosition, end: LspPosition) -> Self {
+        Self { start, end }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn start(&self) -> LspPosition {
+        self.start
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn end(&self) -> LspPosition {
+        self.end
+    }
+}
+
+/// Displays start of the location as `path:line:column`.
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Option::<PathBuf>::from(self.path) {
+            Some(path) => write!(f, "{}:{}", path.display(), self.start),
+            None => write!(f, "<unknown>:{}", self.start),
+        }
+    }
 }
 
 pub trait HasLocation {
     #[must_use]
     fn location(&self) -> Location;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_character_is_displayed_at_column_1() {
+        let location = Location::of_first_byte(PathId::from("main.lzr"));
+
+        assert_eq!(location.start().column(), 0);
+        assert_eq!(location.start().display_column(), 1);
+        assert_eq!(location.start().to_string(), "1:1");
+        assert_eq!(location.to_string(), "main.lzr:1:1");
+    }
+
+    #[test]
+    fn extend() {
+        let path = PathId::from("main.lzr");
+        let start = CharLocation::new(2, 4, 10);
+        let location = Location::new(path, start, start).extend(3);
+
+        assert_eq!(location.start(), start);
+        assert_eq!(location.end(), CharLocation::new(2, 7, 13));
+        assert!(location.is_single_line());
+    }
+
+    #[test]
+    fn line_span() {
+        let path = PathId::from("main.lzr");
+        let start = CharLocation::new(1, 4, 4);
+        let end = CharLocation::new(2, 3, 10);
+
+        assert_eq!(Location::new(path, start, end).line_span(), (1, 2));
+        assert_eq!(Location::of_first_byte(path).line_span(), (1, 1));
+    }
+
+    #[test]
+    fn single_line() {
+        let path = PathId::from("main.lzr");
+
+        assert!(Location::new(
+            path,
+            CharLocation::new(3, 0, 20),
+            CharLocation::new(3, 5, 25)
+        )
+        .is_single_line());
+        assert!(!Location::new(
+            path,
+            CharLocation::new(3, 0, 20),
+            CharLocation::new(4, 1, 30)
+        )
+        .is_single_line());
+    }
+
+    #[test]
+    fn lsp_range() {
+        let path = PathId::from("main.lzr");
+        let source = "a;\nb = \"𝔸é\" + c;";
+        let tokens = crate::lexer::tokenize(path, source);
+        let c = tokens
+            .iter()
+            .find(|token| token.lexeme(source) == "c")
+            .unwrap()
+            .location();
+        let range = LspRange::new(LspPosition::new(1, 12), LspPosition::new(1, 13));
+
+        assert_eq!(c.to_lsp_range(source), range);
+        assert_eq!(Location::from_lsp_range(path, range, source), Some(c));
+
+        // `𝔸` is 2 UTF-16 code units wide.
+        assert_eq!(
+            CharLocation::from_lsp_position(LspPosition::new(1, 6), source),
+            None
+        );
+        assert_eq!(
+            CharLocation::from_lsp_position(LspPosition::new(0, 3), source),
+            None
+        );
+        assert_eq!(
+            CharLocation::from_lsp_position(LspPosition::new(2, 0), source),
+            None
+        );
+        assert_eq!(
+            CharLocation::from_lsp_position(LspPosition::new(0, 2), source),
+            Some(CharLocation::new(1, 2, 2))
+        );
+    }
+
+    #[test]
+    fn lsp_range_with_carriage_returns() {
+        let path = PathId::from("main.lzr");
+        let source = "a;\rbb;\r\nc;";
+        let tokens = crate::lexer::tokenize(path, source);
+        let location = |lexeme| {
+            tokens
+                .iter()
+                .find(|token| token.lexeme(source) == lexeme)
+                .unwrap()
+                .location()
+        };
+
+        for (lexeme, range) in [
+            (
+                "bb",
+                LspRange::new(LspPosition::new(1, 0), LspPosition::new(1, 2)),
+            ),
+            (
+                "c",
+                LspRange::new(LspPosition::new(2, 0), LspPosition::new(2, 1)),
+            ),
+        ] {
+            assert_eq!(location(lexeme).to_lsp_range(source), range, "{lexeme}");
+            assert_eq!(
+                Location::from_lsp_range(path, range, source),
+                Some(location(lexeme)),
+                "{lexeme}"
+            );
+        }
+
+        assert_eq!(
+            CharLocation::from_lsp_position(LspPosition::new(1, 3), source),
+            Some(CharLocation::new(2, 3, 6))
+        );
+        assert_eq!(
+            CharLocation::from_lsp_position(LspPosition::new(1, 4), source),
+            None
+        );
+        assert_eq!(
+            CharLocation::from_lsp_position(LspPosition::new(3, 0), source),
+            None
+        );
+    }
+}
@@ -1,12 +1,162 @@
-pub mod ast;
-pub mod interner;
-pub mod lexer;
-pub mod location;
-pub mod parser;
-pub mod stable_likely;
-pub mod token;
-pub mod value;
-
-fn main() {
-    println!("Hello, world!");
+use std::{
+    env, fs,
+    io::{self, BufRead, IsTerminal, Write},
+    process::ExitCode,
+};
+
+use proxima::{
+    diagnostic::Diagnostic,
+    interpreter::Interpreter,
+    parser::{check, parse_repl_input, ReplInput},
+    pretty_printer::format,
+    snippet::Snippet,
+};
+
+const USAGE: &str = "usage: proxima check <file> [--no-color]
+       proxima fmt <file> [--check] [--no-color]
+       proxima repl [--no-color]";
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let no_color = args.iter().any(|arg| arg == "--no-color") || env::var_os("NO_COLOR").is_some();
+    let args = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--no-color")
+        .collect::<Vec<_>>();
+
+    match args.as_slice() {
+        ["check", file] => run_check(file, no_color),
+        ["fmt", file] => run_fmt(file, false, no_color),
+        ["fmt", "--check", file] | ["fmt", file, "--check"] => run_fmt(file, true, no_color),
+        ["repl"] => run_repl(no_color),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Parses the file without running it and prints all diagnostics.
+/// Fails if there are any.
+fn run_check(file: &str, no_color: bool) -> ExitCode {
+    let source = match read_source(file) {
+        Ok(source) => source,
+        Err(code) => return code,
+    };
+
+    let diagnostics = check(file, &source);
+    print_diagnostics(&diagnostics, &source, no_color);
+
+    if diagnostics.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Reformats the file in place. With `--check` the file is left untouched
+/// and the command fails if it is not formatted already.
+fn run_fmt(file: &str, check_only: bool, no_color: bool) -> ExitCode {
+    let source = match read_source(file) {
+        Ok(source) => source,
+        Err(code) => return code,
+    };
+
+    let formatted = match format(file, &source) {
+        Ok(formatted) => formatted,
+        Err(errors) => {
+            let diagnostics = errors
+                .iter()
+                .map(|error| error.to_diagnostic(&source))
+                .collect::<Vec<_>>();
+            print_diagnostics(&diagnostics, &source, no_color);
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if formatted == source {
+        return ExitCode::SUCCESS;
+    }
+
+    if check_only {
+        eprintln!("`{file}` is not formatted");
+        return ExitCode::FAILURE;
+    }
+
+    if let Err(error) = fs::write(file, formatted) {
+        eprintln!("error: cannot write `{file}`: {error}");
+        return ExitCode::from(2);
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Evaluates lines read from the standard input one by one. Values of bare
+/// expressions are printed, statements are executed silently.
+fn run_repl(no_color: bool) -> ExitCode {
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut interpreter = Interpreter::new();
+
+    loop {
+        if interactive {
+            eprint!("> ");
+            let _ = io::stderr().flush();
+        }
+
+        let mut line = String::new();
+
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => return ExitCode::SUCCESS,
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("error: cannot read input: {error}");
+                return ExitCode::from(2);
+            }
+        }
+
+        let result = match parse_repl_input("<repl>", &line) {
+            Ok(ReplInput::Expression(expression)) => {
+                interpreter.eval_expression(&expression).map(Some)
+            }
+            Ok(ReplInput::Statements(statements)) => {
+                interpreter.eval_block(&statements).map(|_| None)
+            }
+            Err(errors) => {
+                let diagnostics = errors
+                    .iter()
+                    .map(|error| error.to_diagnostic(&line))
+                    .collect::<Vec<_>>();
+                print_diagnostics(&diagnostics, &line, no_color);
+
+                continue;
+            }
+        };
+
+        print!("{}", interpreter.take_output());
+
+        match result {
+            Ok(Some(value)) => println!("{value}"),
+            Ok(None) => {}
+            Err(error) => eprintln!("error: {error}"),
+        }
+    }
+}
+
+fn read_source(file: &str) -> Result<String, ExitCode> {
+    fs::read_to_string(file).map_err(|error| {
+        eprintln!("error: cannot read `{file}`: {error}");
+        ExitCode::from(2)
+    })
+}
+
+fn print_diagnostics(diagnostics: &[Diagnostic], source: &str, no_color: bool) {
+    for diagnostic in diagnostics {
+        eprintln!(
+            "{}\n",
+            Snippet::new(diagnostic, source).with_no_color(no_color)
+        );
+    }
 }
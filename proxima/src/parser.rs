@@ -1,70 +1,2600 @@
+use std::{
+    cmp::Reverse,
+    fmt::{self, Display},
+    num::NonZeroUsize,
+    sync::Arc,
+};
+
 use crate::{
-    interner::PathId,
-    lexer::Lexer,
-    location::{HasLocation, Location},
-    token::{LexError, RawToken, Token},
+    ast::{
+        ArrayExpression, AssignmentExpression, BinaryExpression, CallExpression, ClassDeclaration,
+        Comment, Expression, FunctionDeclaration, IdentifierExpression, IfExpression,
+        IndexExpression, Literal, MapExpression, MemberExpression, NewExpression, PrintStatement,
+        RawStatement, ReturnStatement, Statement, StatementsBlock, ThrowExpression, TypeAnnotation,
+        UnaryExpression, WhileExpression,
+    },
+    diagnostic::Diagnostic,
+    interner::{IdentifierId, PathId},
+    lexer::{check_source_length, Lexer, SourceTooLarge},
+    location::{CharLocation, HasLocation, Location},
+    reachability::check_unreachable_code,
+    token::{Keyword, LexError, Punctuator, RawToken, Token},
+    value::{DefaultNumberParser, NumberParser, Value},
 };
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Parses the whole program. Returns either the tree or all the errors
+/// found in the source, up to [`DEFAULT_MAX_ERRORS`] of them. The source
+/// length is not limited, see [`parse_with_max_source_length`].
+pub fn parse(path: impl Into<PathId>, source: &str) -> Result<StatementsBlock, Vec<Error>> {
+    parse_with_max_source_length(path, source, None)
+}
+
+/// Parses the whole program like [`parse`], but collects at most
+/// `max_errors` errors, see [`Parser::with_max_errors`].
+///
+/// # Errors
+/// Returns the errors found in the source, followed by
+/// [`Error::TooManyErrors`] if there are more of them than the limit.
+pub fn parse_with_max_errors(
+    path: impl Into<PathId>,
+    source: &str,
+    max_errors: NonZeroUsize,
+) -> Result<StatementsBlock, Vec<Error>> {
+    let (program, errors) = Parser::new(path.into(), source)
+        .with_max_errors(max_errors)
+        .parse_program();
+
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses the whole program like [`parse`], unless the source is longer
+/// than `max_source_length` bytes. `None` means no limit.
+///
+/// # Errors
+/// Returns [`Error::SourceTooLarge`] without scanning anything if the
+/// source exceeds the limit, and otherwise all the errors found in it.
+pub fn parse_with_max_source_length(
+    path: impl Into<PathId>,
+    source: &str,
+    max_source_length: Option<usize>,
+) -> Result<StatementsBlock, Vec<Error>> {
+    let path = path.into();
+    check_source_length_of(path, source, max_source_length).map_err(|error| vec![error])?;
+
+    let (program, errors) = Parser::new(path, source).parse_program();
+
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Fails with [`Error::SourceTooLarge`] if `source` is longer than
+/// `max_source_length` bytes.
+fn check_source_length_of(
+    path: PathId,
+    source: &str,
+    max_source_length: Option<usize>,
+) -> Result<(), Error> {
+    check_source_length(source, max_source_length).map_err(|error| Error::SourceTooLarge {
+        location: Location::of_first_byte(path),
+        error,
+    })
+}
+
+/// Parses the whole program only to report problems in it: lexer warnings,
+/// syntax errors and unreachable code, ordered by their position in the
+/// source.
+#[must_use]
+pub fn check(path: impl Into<PathId>, source: &str) -> Vec<Diagnostic> {
+    parse_with_diagnostics(path, source).diagnostics
+}
+
+/// Parses the whole program, recovering from errors, and collects all the
+/// diagnostics, ordered by their position in the source. Diagnostics at the
+/// same position are ordered by severity, so that notes follow the error
+/// they explain.
+#[must_use]
+pub fn parse_with_diagnostics(path: impl Into<PathId>, source: &str) -> ParseResult {
+    let path = path.into();
+    let mut lexer = Lexer::new(path, source);
+    let tokens = lexer.by_ref().collect::<Vec<_>>();
+    let mut parser = Parser::new_from_tokens(path, tokens).with_empty_body_warnings();
+    let (tree, errors) = parser.parse_program();
+
+    let mut diagnostics = lexer.diagnostics().to_vec();
+    diagnostics.extend_from_slice(parser.diagnostics());
+    diagnostics.extend(check_unreachable_code(&tree));
+    diagnostics.extend(errors.iter().map(|error| error.to_diagnostic(source)));
+    diagnostics.sort_by_key(|diagnostic| {
+        (
+            diagnostic.location().start().offset(),
+            Reverse(diagnostic.severity()),
+        )
+    });
+
+    ParseResult { tree, diagnostics }
+}
+
+/// Parses the whole program like [`parse_with_diagnostics`], unless the
+/// source is longer than `max_source_length` bytes. `None` means no limit.
+///
+/// # Errors
+/// Returns [`Error::SourceTooLarge`] without scanning anything if the
+/// source exceeds the limit.
+pub fn parse_with_diagnostics_and_max_source_length(
+    path: impl Into<PathId>,
+    source: &str,
+    max_source_length: Option<usize>,
+) -> Result<ParseResult, Error> {
+    let path = path.into();
+    check_source_length_of(path, source, max_source_length)?;
+
+    Ok(parse_with_diagnostics(path, source))
+}
+
+/// Line entered in the REPL, see [`parse_repl_input`].
+#[derive(Debug, Clone)]
+pub enum ReplInput {
+    /// Bare expression without a trailing semicolon, e.g. `1 + 1`, whose
+    /// value is printed.
+    Expression(Expression),
+    /// Statements, e.g. `x = 5;`, which are executed silently.
+    Statements(StatementsBlock),
+}
+
+/// Parses a line entered in the REPL. The whole input is tried as a single
+/// expression first, so that the semicolon can be omitted, and only then
+/// as a sequence of statements. Errors are the ones of the latter.
+///
+/// # Errors
+/// Returns all the errors found in the input, see [`parse`].
+pub fn parse_repl_input(path: impl Into<PathId>, source: &str) -> Result<ReplInput, Vec<Error>> {
+    parse_repl_input_with_max_source_length(path, source, None)
+}
+
+/// Parses a line entered in the REPL like [`parse_repl_input`], unless it
+/// is longer than `max_source_length` bytes. `None` means no limit.
+///
+/// # Errors
+/// Returns [`Error::SourceTooLarge`] without scanning anything if the
+/// input exceeds the limit, and otherwise all the errors found in it.
+pub fn parse_repl_input_with_max_source_length(
+    path: impl Into<PathId>,
+    source: &str,
+    max_source_length: Option<usize>,
+) -> Result<ReplInput, Vec<Error>> {
+    let path = path.into();
+    check_source_length_of(path, source, max_source_length).map_err(|error| vec![error])?;
+
+    let mut parser = Parser::new(path, source);
+
+    if let Ok(expression) = parser.parse_expression() {
+        if parser.is_at_end() {
+            return Ok(ReplInput::Expression(expression));
+        }
+    }
+
+    parse(path, source).map(ReplInput::Statements)
+}
+
+/// Tree of the program together with the warnings and errors reported while
+/// parsing it, see [`parse_with_diagnostics`]. The tree contains only
+/// statements parsed successfully.
+#[derive(Debug, Clone)]
+pub struct ParseResult {
+    tree: StatementsBlock,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseResult {
+    #[inline]
+    #[must_use]
+    pub const fn tree(&self) -> &StatementsBlock {
+        &self.tree
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns `true` if any of the diagnostics is an error, warnings
+    /// are ignored.
+    #[inline]
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    /// Returns the tree, unless there are errors.
+    #[inline]
+    #[must_use]
+    pub fn into_tree_if_ok(self) -> Option<StatementsBlock> {
+        if self.has_errors() {
+            None
+        } else {
+            Some(self.tree)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Error {
     Lex(LexError),
-    UnexpectedToken { expected: RawToken, found: Token },
+    UnexpectedToken {
+        expected: RawToken,
+        found: Token,
+    },
+    MissingSemicolon {
+        end: CharLocation,
+        found: Token,
+    },
+    ExpectedExpression {
+        found: Token,
+    },
+    ExpectedIdentifier {
+        found: Token,
+    },
+    ExpectedClassMember {
+        found: Token,
+    },
+    NestingTooDeep {
+        location: Location,
+        limit: usize,
+    },
+    InvalidAssignmentTarget {
+        location: Location,
+    },
+    TooManyErrors {
+        location: Location,
+        limit: usize,
+    },
+    /// See [`parse_with_max_source_length`].
+    SourceTooLarge {
+        location: Location,
+        error: SourceTooLarge,
+    },
+}
+
+impl Error {
+    /// Renders human readable message, quoting offending tokens from
+    /// `source`, e.g. ``expected `;`, found `foo` ``.
+    #[must_use]
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(|found| describe_found(found, source))
+    }
+
+    fn render_with(&self, describe_found: impl Fn(Token) -> String) -> String {
+        match self {
+            Self::Lex(error) => error.raw().to_string(),
+            Self::UnexpectedToken { expected, found } => format!(
+                "expected {}, found {}",
+                describe_expected(*expected),
+                describe_found(*found)
+            ),
+            Self::MissingSemicolon { found, .. } => {
+                format!("expected `;`, found {}", describe_found(*found))
+            }
+            Self::ExpectedExpression { found } => {
+                format!("expected expression, found {}", describe_found(*found))
+            }
+            Self::ExpectedIdentifier { found } => {
+                format!("expected identifier, found {}", describe_found(*found))
+            }
+            Self::ExpectedClassMember { found } => {
+                format!("expected method or field, found {}", describe_found(*found))
+            }
+            Self::NestingTooDeep { limit, .. } => {
+                format!("expression is nested too deeply (the limit is {limit})")
+            }
+            Self::InvalidAssignmentTarget { .. } => "invalid assignment target".to_owned(),
+            Self::TooManyErrors { limit, .. } => {
+                format!("too many errors (the limit is {limit}), stopping here")
+            }
+            Self::SourceTooLarge { error, .. } => error.to_string(),
+        }
+    }
+
+    /// Converts the error into an error [`Diagnostic`], see [`Error::render`].
+    #[must_use]
+    pub fn to_diagnostic(&self, source: &str) -> Diagnostic {
+        Diagnostic::error(self.render(source), self.location())
+    }
+}
+
+/// Displays the error with its location, e.g. ``main.lzr:1:3: expected `;`,
+/// found `foo` ``. Without the source tokens are described by their
+/// kind or value, see [`Error::render`] for quoting them verbatim.
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            self.location(),
+            self.render_with(describe_found_without_source)
+        )
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl HasLocation for Error {
+    fn location(&self) -> Location {
+        match self {
+            Self::Lex(error) => error.location(),
+            Self::UnexpectedToken { found, .. }
+            | Self::ExpectedExpression { found }
+            | Self::ExpectedIdentifier { found }
+            | Self::ExpectedClassMember { found } => found.location(),
+            Self::MissingSemicolon { end, found } => {
+                Location::new(found.location().path(), *end, *end)
+            }
+            Self::NestingTooDeep { location, .. }
+            | Self::InvalidAssignmentTarget { location }
+            | Self::TooManyErrors { location, .. }
+            | Self::SourceTooLarge { location, .. } => *location,
+        }
+    }
+}
+
+fn describe_expected(raw: RawToken) -> String {
+    match raw {
+        RawToken::Punctuator(punctuator) => format!("`{punctuator}`"),
+        RawToken::Keyword(keyword) => format!("`{keyword}`"),
+        RawToken::Identifier(_) => "identifier".to_owned(),
+        RawToken::Int(_) | RawToken::Float(_) | RawToken::Text(_) => "literal".to_owned(),
+        RawToken::Error(_) | RawToken::Comment => "token".to_owned(),
+        RawToken::EndOfFile => "end of file".to_owned(),
+    }
+}
+
+fn describe_found(token: Token, source: &str) -> String {
+    match token.raw() {
+        RawToken::EndOfFile => "end of file".to_owned(),
+        _ => format!("`{}`", token.lexeme(source)),
+    }
+}
+
+/// Returns name of the token used as an identifier. Contextual keywords
+/// (see [`Keyword::is_contextual`]) are identifiers outside of their
+/// construct, and since `switch` isn't parsed yet, that is everywhere.
+fn identifier_name(raw: RawToken) -> Option<IdentifierId> {
+    match raw {
+        RawToken::Identifier(name) => Some(name),
+        RawToken::Keyword(keyword) if keyword.is_contextual() => {
+            Some(IdentifierId::from(keyword.to_string()))
+        }
+        _ => None,
+    }
+}
+
+fn describe_found_without_source(token: Token) -> String {
+    match token.raw() {
+        RawToken::Identifier(name) => name
+            .with_resolved(|name| format!("`{name}`"))
+            .unwrap_or_else(|| "identifier".to_owned()),
+        RawToken::Punctuator(punctuator) => format!("`{punctuator}`"),
+        RawToken::Keyword(keyword) => format!("`{keyword}`"),
+        RawToken::Int(number) => format!("`{number}`"),
+        RawToken::Float(number) => format!("`{number:?}`"),
+        RawToken::Text(_) => "string literal".to_owned(),
+        RawToken::Error(_) | RawToken::Comment => "token".to_owned(),
+        RawToken::EndOfFile => "end of file".to_owned(),
+    }
 }
 
+/// State of the parser saved with [`Parser::checkpoint`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    token_idx: usize,
+    diagnostics_len: usize,
+}
+
+/// Default value of [`Parser::with_max_nesting_depth`]. Low enough that
+/// parsing and walking the tree fit in the 2 MiB stack threads get by
+/// default, even in unoptimized builds. Operators chained without
+/// parentheses count too, e.g. `a + b + c` is as deep as `(a + b) + c`.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 128;
+
+/// Default value of [`Parser::with_max_errors`].
+pub const DEFAULT_MAX_ERRORS: NonZeroUsize = NonZeroUsize::new(100).unwrap();
+
+#[derive(Clone, Debug)]
 pub struct Parser {
     path: PathId,
     tokens: Vec<Token>,
+    /// Trivia tokens, attached to statements as comments.
+    comments: Vec<Token>,
     current_token_idx: usize,
+    nesting_depth: usize,
+    max_nesting_depth: usize,
+    max_errors: NonZeroUsize,
+    number_parser: Arc<dyn NumberParser + Send + Sync>,
+    /// Source the tokens were scanned from, to pass the text of number
+    /// literals to the number parser. Unknown for parsers created
+    /// from tokens.
+    source: Option<Arc<str>>,
+    optional_trailing_semicolons: bool,
+    warn_empty_bodies: bool,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Parser {
+    /// Creates parser from already scanned tokens. Trivia tokens (comments)
+    /// are skipped, so tokens from [`crate::lexer::tokenize_with_trivia`]
+    /// can be parsed as well, in which case comments are attached to
+    /// the statements.
     #[inline]
     #[must_use]
-    pub const fn new_from_tokens(path: PathId, tokens: Vec<Token>) -> Self {
+    pub fn new_from_tokens(path: PathId, tokens: Vec<Token>) -> Self {
+        let (comments, tokens) = tokens
+            .into_iter()
+            .partition(|token: &Token| token.raw().is_trivia());
+
         Self {
             path,
             tokens,
+            comments,
             current_token_idx: 0,
+            nesting_depth: 0,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_errors: DEFAULT_MAX_ERRORS,
+            number_parser: Arc::new(DefaultNumberParser),
+            source: None,
+            optional_trailing_semicolons: false,
+            warn_empty_bodies: false,
+            diagnostics: Vec::new(),
         }
     }
 
+    /// Creates parser from any token stream, e.g. a filtered [`Lexer`].
+    /// Trivia tokens are skipped, see [`Parser::new_from_tokens`].
+    #[inline]
+    #[must_use]
+    pub fn from_tokens(path: PathId, tokens: impl IntoIterator<Item = Token>) -> Self {
+        Self::new_from_tokens(path, tokens.into_iter().collect())
+    }
+
+    /// Allows omitting the semicolon after the last statement of a block
+    /// or of the program, e.g. `{ a; b }`.
+    #[inline]
+    #[must_use]
+    pub const fn with_optional_trailing_semicolons(mut self) -> Self {
+        self.optional_trailing_semicolons = true;
+        self
+    }
+
+    /// Reports a warning for `while` and `if` with an empty body, e.g.
+    /// `while x {}`, which is often a mistake.
+    #[inline]
+    #[must_use]
+    pub const fn with_empty_body_warnings(mut self) -> Self {
+        self.warn_empty_bodies = true;
+        self
+    }
+
+    /// Sets how deeply expressions and blocks can be nested before the parser
+    /// gives up with [`Error::NestingTooDeep`] instead of overflowing the stack.
+    #[inline]
+    #[must_use]
+    pub const fn with_max_nesting_depth(mut self, limit: usize) -> Self {
+        self.max_nesting_depth = limit;
+        self
+    }
+
+    /// Sets how many errors [`Parser::parse_program`] collects before it
+    /// stops with [`Error::TooManyErrors`], so that a badly broken file
+    /// doesn't produce hundreds of cascading errors. The limit can't be 0,
+    /// as there is nothing to stop at before the first error.
+    #[inline]
+    #[must_use]
+    pub const fn with_max_errors(mut self, limit: NonZeroUsize) -> Self {
+        self.max_errors = limit;
+        self
+    }
+
+    /// Sets how number literals are converted into values. By default
+    /// [`DefaultNumberParser`] is used. The text of the literals is only
+    /// known to parsers created with [`Parser::new`], parsers created from
+    /// tokens keep the numbers scanned by the lexer.
+    #[inline]
+    #[must_use]
+    pub fn with_number_parser(
+        mut self,
+        number_parser: impl NumberParser + Send + Sync + 'static,
+    ) -> Self {
+        self.number_parser = Arc::new(number_parser);
+        self
+    }
+
     #[inline]
     #[must_use]
     pub fn new(path: PathId, source: &str) -> Self {
-        Self::new_from_tokens(path, Lexer::new(path, source).collect())
+        Self {
+            source: Some(Arc::from(source)),
+            ..Self::from_tokens(path, Lexer::new(path, source))
+        }
     }
 
-    fn consume(&mut self, raw: RawToken) -> Result<Token, Error> {
-        let current = self.current();
-        if raw != current.raw() {
-            return Err(Error::UnexpectedToken {
-                expected: raw,
-                found: current,
+    #[inline]
+    #[must_use]
+    pub const fn path(&self) -> PathId {
+        self.path
+    }
+
+    /// Returns warnings reported so far. Errors are returned by the
+    /// parsing methods themselves.
+    #[inline]
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Parses statements until the end of the input. On error, the parser
+    /// skips to the next statement and continues, so that all errors are
+    /// collected, up to the limit set by [`Parser::with_max_errors`].
+    pub fn parse_program(&mut self) -> (StatementsBlock, Vec<Error>) {
+        let start_location = self.current().location();
+
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+
+        while !self.is_at_end() {
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    errors.push(error);
+
+                    if errors.len() >= self.max_errors.get() {
+                        errors.push(Error::TooManyErrors {
+                            location: self.current().location(),
+                            limit: self.max_errors.get(),
+                        });
+                        break;
+                    }
+
+                    // The parser already stands at the start of the next
+                    // statement, as nothing was consumed after the statement
+                    // missing the semicolon.
+                    if !matches!(error, Error::MissingSemicolon { .. }) {
+                        self.synchronize();
+                    }
+                }
+            }
+        }
+
+        let location = start_location.to(self
+            .tokens
+            .last()
+            .map_or(start_location, |token| token.location()));
+
+        (StatementsBlock::new(statements, location), errors)
+    }
+
+    /// Skips tokens until the start of the next statement.
+    fn synchronize(&mut self) {
+        self.advance();
+
+        while !self.is_at_end() {
+            if self.get_previous().is_punctuator(Punctuator::Semicolon) {
+                return;
+            }
+
+            if matches!(
+                self.current().raw(),
+                RawToken::Keyword(Keyword::Func | Keyword::Class | Keyword::Return)
+            ) {
+                return;
+            }
+
+            self.advance();
+        }
+    }
+
+    /// Parses statement and attaches comments around it, if the parser
+    /// was given trivia tokens, see [`Statement::leading_comments`].
+    pub fn parse_statement(&mut self) -> Result<Statement, Error> {
+        let leading_comments = self.leading_comments();
+        let raw = self.parse_raw_statement()?;
+        let trailing_comments = self.trailing_comments();
+
+        Ok(Statement::new(raw).with_comments(leading_comments, trailing_comments))
+    }
+
+    fn parse_raw_statement(&mut self) -> Result<RawStatement, Error> {
+        match self.peek_keyword() {
+            Some(Keyword::Func) => Ok(RawStatement::Function(self.parse_function_declaration()?)),
+            Some(Keyword::Class) => Ok(RawStatement::Class(self.parse_class_declaration()?)),
+            Some(Keyword::Return) => self.parse_return_statement(),
+            Some(Keyword::Print | Keyword::Println) => self.parse_print_statement(),
+            _ if self.current().is_punctuator(Punctuator::OpenBrace) && !self.is_map_ahead() => {
+                Ok(RawStatement::Expression(Expression::Block(
+                    self.nested(Self::parse_statements_block)?,
+                )))
+            }
+            _ => {
+                let expression = self.parse_expression()?;
+
+                if !expression.is_block_like()
+                    || self.current().is_punctuator(Punctuator::Semicolon)
+                {
+                    self.expect_semicolon()?;
+                }
+
+                Ok(RawStatement::Expression(expression))
+            }
+        }
+    }
+
+    /// Returns comments between the previous token and the current one,
+    /// except those trailing the previous statement.
+    fn leading_comments(&self) -> Vec<Comment> {
+        let end = self.current().location().start().offset();
+        let previous = self
+            .current_token_idx
+            .checked_sub(1)
+            .map(|idx| self.tokens[idx].location());
+        let previous_is_open_brace =
+            self.current_token_idx > 0 && self.get_previous().is_punctuator(Punctuator::OpenBrace);
+
+        self.comments_between(previous.map_or(0, |location| location.end().offset()), end)
+            .filter(|comment| {
+                previous_is_open_brace
+                    || previous.is_none_or(|previous| {
+                        comment.location().start().line() != previous.end().line()
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns comments after the previous token, starting on the line
+    /// it ends on.
+    fn trailing_comments(&self) -> Vec<Comment> {
+        let previous = self.get_previous().location();
+        let end = if self.is_at_end() {
+            usize::MAX
+        } else {
+            self.current().location().start().offset()
+        };
+
+        self.comments_between(previous.end().offset(), end)
+            .filter(|comment| comment.location().start().line() == previous.end().line())
+            .collect()
+    }
+
+    /// Returns comments starting in `start..end` byte offset range.
+    fn comments_between(&self, start: usize, end: usize) -> impl Iterator<Item = Comment> + '_ {
+        let first = self
+            .comments
+            .partition_point(|comment| comment.location().start().offset() < start);
+
+        self.comments[first..]
+            .iter()
+            .take_while(move |comment| comment.location().start().offset() < end)
+            .map(|comment| Comment::new(comment.location()))
+    }
+
+    /// Parses statements wrapped in braces, e.g. `{ a; b; }`.
+    pub fn parse_statements_block(&mut self) -> Result<StatementsBlock, Error> {
+        let start_location = self.consume(Punctuator::OpenBrace.into())?.location();
+
+        let mut statements = Vec::new();
+
+        while !self.current().is_punctuator(Punctuator::CloseBrace) {
+            statements.push(self.nested(Self::parse_statement)?);
+        }
+
+        let end_location = self.consume(Punctuator::CloseBrace.into())?.location();
+
+        Ok(StatementsBlock::new(
+            statements,
+            start_location.to(end_location),
+        ))
+    }
+
+    /// Parses return statement, e.g. `return a;` or `return;`.
+    fn parse_return_statement(&mut self) -> Result<RawStatement, Error> {
+        let start_location = self.consume(Keyword::Return.into())?.location();
+        let expression = match self.current().raw() {
+            RawToken::Punctuator(Punctuator::Semicolon | Punctuator::CloseBrace)
+            | RawToken::EndOfFile => None,
+            _ => Some(self.parse_expression()?),
+        };
+        let end_location = self.expect_semicolon()?.map_or_else(
+            || {
+                expression
+                    .as_ref()
+                    .map_or(start_location, HasLocation::location)
+            },
+            |semicolon| semicolon.location(),
+        );
+
+        Ok(RawStatement::Return(ReturnStatement::new(
+            expression,
+            start_location.to(end_location),
+        )))
+    }
+
+    /// Parses output statement, e.g. `println a, b;`. Unlike in argument
+    /// lists, trailing comma is not allowed.
+    fn parse_print_statement(&mut self) -> Result<RawStatement, Error> {
+        let keyword = self.current();
+        self.advance();
+
+        let mut arguments = Vec::new();
+
+        if !self.current().is_punctuator(Punctuator::Semicolon) {
+            arguments.push(self.parse_expression()?);
+
+            while self.current().is_punctuator(Punctuator::Comma) {
+                self.advance();
+                arguments.push(self.parse_expression()?);
+            }
+        }
+
+        let end_location = self.expect_semicolon()?.map_or_else(
+            || self.get_previous().location(),
+            |semicolon| semicolon.location(),
+        );
+
+        Ok(RawStatement::Print(PrintStatement::new(
+            arguments,
+            keyword.raw() == Keyword::Println.into(),
+            keyword.location().to(end_location),
+        )))
+    }
+
+    /// Parses function declaration, e.g. `func add(a, b) { return a + b; }`.
+    fn parse_function_declaration(&mut self) -> Result<FunctionDeclaration, Error> {
+        let start_location = self.consume(Keyword::Func.into())?.location();
+        let (name, _) = self.consume_identifier()?;
+
+        self.consume(Punctuator::OpenParent.into())?;
+
+        let (parameters, _) = self.parse_comma_separated(Punctuator::CloseParent, |parser| {
+            let (parameter, _) = parser.consume_identifier()?;
+            let type_ = if parser.current().is_punctuator(Punctuator::Colon) {
+                parser.advance();
+                Some(parser.parse_type_annotation()?)
+            } else {
+                None
+            };
+
+            Ok((parameter, type_))
+        })?;
+        let (parameters, mut parameter_types): (Vec<_>, Vec<_>) = parameters.into_iter().unzip();
+
+        if parameter_types.iter().all(Option::is_none) {
+            parameter_types.clear();
+        }
+
+        let return_type = if self.current().is_punctuator(Punctuator::Arrow) {
+            self.advance();
+            Some(self.parse_type_annotation()?)
+        } else {
+            None
+        };
+
+        let body = self.parse_statements_block()?;
+        let location = start_location.to(body.location());
+
+        Ok(FunctionDeclaration::new(name, parameters, body, location)
+            .with_types(parameter_types, return_type))
+    }
+
+    /// Parses type, e.g. `int` in `a: int`.
+    fn parse_type_annotation(&mut self) -> Result<TypeAnnotation, Error> {
+        let (name, location) = self.consume_identifier()?;
+
+        Ok(TypeAnnotation::new(name, location))
+    }
+
+    /// Parses class declaration, e.g. `class Foo { func bar() {} x; }`.
+    fn parse_class_declaration(&mut self) -> Result<ClassDeclaration, Error> {
+        let start_location = self.consume(Keyword::Class.into())?.location();
+        let (name, _) = self.consume_identifier()?;
+
+        self.consume(Punctuator::OpenBrace.into())?;
+
+        let mut methods = Vec::new();
+        let mut fields = Vec::new();
+
+        loop {
+            let current = self.current();
+
+            match current.raw() {
+                RawToken::Punctuator(Punctuator::CloseBrace) => break,
+                RawToken::Keyword(Keyword::Func) => {
+                    methods.push(self.parse_function_declaration()?);
+                }
+                raw => {
+                    let Some(field) = identifier_name(raw) else {
+                        return Err(Error::ExpectedClassMember { found: current });
+                    };
+
+                    self.advance();
+                    self.consume(Punctuator::Semicolon.into())?;
+
+                    fields.push(field);
+                }
+            }
+        }
+
+        let end_location = self.consume(Punctuator::CloseBrace.into())?.location();
+
+        Ok(ClassDeclaration::new(
+            name,
+            methods,
+            fields,
+            start_location.to(end_location),
+        ))
+    }
+
+    /// Returns `true` if all tokens have been consumed.
+    #[inline]
+    #[must_use]
+    pub fn is_at_end(&self) -> bool {
+        self.current().raw() == RawToken::EndOfFile
+    }
+
+    /// Parses expression, which must span until the end of the input,
+    /// e.g. `1 + 2`, but not `1 + 2 3`.
+    pub fn parse_complete_expression(&mut self) -> Result<Expression, Error> {
+        let expression = self.parse_expression()?;
+        self.consume(RawToken::EndOfFile)?;
+
+        Ok(expression)
+    }
+
+    pub fn parse_expression(&mut self) -> Result<Expression, Error> {
+        self.nested(Self::parse_assignment_expression)
+    }
+
+    /// Parses assignment expression, e.g. `a = b = 1` or `a += 1`. The target
+    /// must be an lvalue, see [`Expression::is_lvalue`].
+    fn parse_assignment_expression(&mut self) -> Result<Expression, Error> {
+        let target = self.parse_binary_expression(0)?;
+
+        match self.peek_punctuator() {
+            Some(operator) if operator.is_assignment_operator() => {
+                self.parse_assignment_value(target, operator)
+            }
+            _ => Ok(target),
+        }
+    }
+
+    fn parse_assignment_value(
+        &mut self,
+        target: Expression,
+        operator: Punctuator,
+    ) -> Result<Expression, Error> {
+        if !target.is_lvalue() {
+            return Err(Error::InvalidAssignmentTarget {
+                location: target.location(),
             });
         }
 
-        self.current_token_idx += 1;
-        Ok(current)
+        self.advance();
+        let value = self.nested(Self::parse_assignment_expression)?;
+
+        Ok(Expression::Assignment(AssignmentExpression::new(
+            Box::new(target),
+            operator,
+            Box::new(value),
+        )))
     }
 
-    fn current(&self) -> Token {
-        self.get(0)
+    /// Parses binary expression using precedence climbing, where `min_precedence`
+    /// is the lowest binding power of an operator that is allowed to be consumed.
+    fn parse_binary_expression(&mut self, min_precedence: u8) -> Result<Expression, Error> {
+        let nesting_depth = self.nesting_depth;
+        let result = self.parse_binary_operands(min_precedence);
+        self.nesting_depth = nesting_depth;
+
+        result
+    }
+
+    /// Parses operands of [`Self::parse_binary_expression`]. Every operator
+    /// folded into the left operand nests it one level deeper, which is
+    /// counted against the nesting limit, so that long chains like
+    /// `1 + 1 + ...` don't overflow the stack of passes walking the tree.
+    fn parse_binary_operands(&mut self, min_precedence: u8) -> Result<Expression, Error> {
+        let mut left = self.parse_unary_expression()?;
+        let mut previous_relational = None;
+
+        while let RawToken::Punctuator(operator) = self.current().raw() {
+            let Some(precedence) = operator.binary_precedence() else {
+                break;
+            };
+
+            if precedence < min_precedence {
+                break;
+            }
+
+            self.advance();
+            self.enter_nesting_level()?;
+
+            let min_precedence = if operator.is_right_associative() {
+                precedence
+            } else {
+                precedence + 1
+            };
+            let right = self.nested(|parser| parser.parse_binary_expression(min_precedence))?;
+
+            left = Expression::Binary(BinaryExpression::new(
+                Box::new(left),
+                operator,
+                Box::new(right),
+            ));
+
+            if operator.is_relational() {
+                if let Some(previous) = previous_relational {
+                    self.warn_chained_comparison(previous, operator, left.location());
+                }
+
+                previous_relational = Some(operator);
+            }
+        }
+
+        Ok(left)
     }
 
-    fn next(&self) -> Token {
-        self.get(1)
+    /// Reports `a < b < c`, which is parsed as `(a < b) < c` and so
+    /// compares result of the first comparison.
+    fn warn_chained_comparison(
+        &mut self,
+        previous: Punctuator,
+        operator: Punctuator,
+        location: Location,
+    ) {
+        self.diagnostics.push(Diagnostic::warning(
+            format!(
+                "comparison operators cannot be chained, \
+                 use `a {previous} b && b {operator} c` instead of `a {previous} b {operator} c`"
+            ),
+            location,
+        ));
     }
 
-    fn get(&self, offset: usize) -> Token {
-        self.tokens
-            .get(self.current_token_idx + offset)
-            .copied()
-            .unwrap_or(Token::new(
-                RawToken::EndOfFile,
-                self.tokens
-                    .last()
-                    .map(|token| token.location())
-                    .unwrap_or(Location::of_first_byte()),
-            ))
+    /// Parses prefix unary expression, e.g. `-a`, `!a`, `~a` or `+a`. Note
+    /// that `-5` is parsed as unary expression too, not as a literal.
+    fn parse_unary_expression(&mut self) -> Result<Expression, Error> {
+        match self.peek_punctuator() {
+            Some(
+                operator @ (Punctuator::Minus
+                | Punctuator::Plus
+                | Punctuator::Bang
+                | Punctuator::Tilde),
+            ) => {
+                let start_location = self.current().location();
+                self.advance();
+                let operand = self.nested(Self::parse_unary_expression)?;
+                let location = start_location.to(operand.location());
+
+                Ok(Expression::Unary(UnaryExpression::new(
+                    operator,
+                    Box::new(operand),
+                    location,
+                )))
+            }
+            _ => self.parse_postfix_expression(),
+        }
+    }
+
+    fn parse_postfix_expression(&mut self) -> Result<Expression, Error> {
+        let mut expression = self.parse_primary_expression()?;
+
+        while let RawToken::Punctuator(punctuator) = self.current().raw() {
+            expression = match punctuator {
+                Punctuator::OpenParent => self.parse_call_expression(expression)?,
+                Punctuator::Dot => self.parse_member_expression(expression)?,
+                Punctuator::OpenBracket => self.parse_index_expression(expression)?,
+                _ => break,
+            };
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_call_expression(&mut self, callee: Expression) -> Result<Expression, Error> {
+        let (arguments, end_location) = self.parse_call_arguments()?;
+        let location = callee.location().to(end_location);
+
+        Ok(Expression::Call(CallExpression::new(
+            Box::new(callee),
+            arguments,
+            location,
+        )))
+    }
+
+    fn parse_member_expression(&mut self, object: Expression) -> Result<Expression, Error> {
+        self.advance();
+        let (member, end_location) = self.consume_identifier()?;
+        let location = object.location().to(end_location);
+
+        Ok(Expression::Member(MemberExpression::new(
+            Box::new(object),
+            member,
+            location,
+        )))
+    }
+
+    fn parse_index_expression(&mut self, object: Expression) -> Result<Expression, Error> {
+        self.advance();
+        let index = self.parse_expression()?;
+        let end_location = self
+            .consume(RawToken::Punctuator(Punctuator::CloseBracket))?
+            .location();
+        let location = object.location().to(end_location);
+
+        Ok(Expression::Index(IndexExpression::new(
+            Box::new(object),
+            Box::new(index),
+            location,
+        )))
+    }
+
+    fn parse_primary_expression(&mut self) -> Result<Expression, Error> {
+        let current = self.current();
+        let text = self.source.as_deref().map(|source| current.lexeme(source));
+        let value = match (current.raw(), text) {
+            (RawToken::Int(_), Some(text)) => self.number_parser.parse_int(text).ok_or(current),
+            (RawToken::Float(_), Some(text)) => self.number_parser.parse_float(text).ok_or(current),
+            _ => Err(current),
+        }
+        .or_else(Value::try_from);
+
+        if let Ok(value) = value {
+            self.advance();
+
+            return Ok(Expression::Literal(Literal::new(value, current.location())));
+        }
+
+        if let Some(name) = identifier_name(current.raw()) {
+            self.advance();
+
+            return Ok(Expression::Identifier(IdentifierExpression::new(
+                name,
+                current.location(),
+            )));
+        }
+
+        match current.raw() {
+            RawToken::Punctuator(Punctuator::OpenParent) => self.parse_parenthesized_expression(),
+            RawToken::Punctuator(Punctuator::OpenBrace) => self.nested(Self::parse_map_expression),
+            RawToken::Punctuator(Punctuator::OpenBracket) => {
+                self.nested(Self::parse_array_expression)
+            }
+            RawToken::Keyword(Keyword::New) => self.parse_new_expression(),
+            RawToken::Keyword(Keyword::While) => self.parse_while_expression(),
+            RawToken::Keyword(Keyword::If) => self.parse_if_expression(),
+            RawToken::Keyword(Keyword::Throw) => self.parse_throw_expression(),
+            RawToken::Error(raw) => Err(Error::Lex(LexError::new(raw, current.location()))),
+            _ => Err(Error::ExpectedExpression { found: current }),
+        }
+    }
+
+    /// Parses array literal, e.g. `[1, a + 2]`.
+    fn parse_array_expression(&mut self) -> Result<Expression, Error> {
+        let start_location = self.consume(Punctuator::OpenBracket.into())?.location();
+        let (elements, end_location) =
+            self.parse_comma_separated(Punctuator::CloseBracket, Self::parse_expression)?;
+
+        Ok(Expression::Array(ArrayExpression::new(
+            elements,
+            start_location.to(end_location),
+        )))
+    }
+
+    /// Parses map literal, e.g. `{"a": 1, b: 2}`.
+    fn parse_map_expression(&mut self) -> Result<Expression, Error> {
+        let start_location = self.consume(Punctuator::OpenBrace.into())?.location();
+        let (entries, end_location) =
+            self.parse_comma_separated(Punctuator::CloseBrace, |parser| {
+                let key = parser.parse_expression()?;
+                parser.consume(Punctuator::Colon.into())?;
+                let value = parser.parse_expression()?;
+
+                Ok((key, value))
+            })?;
+
+        Ok(Expression::Map(MapExpression::new(
+            entries,
+            start_location.to(end_location),
+        )))
+    }
+
+    /// Returns `true` if the brace at the current token starts a map
+    /// literal rather than a block, i.e. a `:` comes before a `;` outside
+    /// of nested brackets, e.g. `{a: 1}` but not `{ a; }`.
+    ///
+    /// This is only needed at the start of a statement: everywhere else a
+    /// brace starts a map, as blocks aren't expressions there. So the empty
+    /// `{}` is a block when it is a statement, unless it is followed by `;`
+    /// or a postfix operator, e.g. `{};` and `{}[a];`, and a map otherwise,
+    /// e.g. in `a = {};`.
+    fn is_map_ahead(&self) -> bool {
+        if self.get(1).is_punctuator(Punctuator::CloseBrace) {
+            return matches!(
+                self.get(2).raw(),
+                RawToken::Punctuator(
+                    Punctuator::Semicolon | Punctuator::OpenBracket | Punctuator::Dot
+                )
+            );
+        }
+
+        let mut depth = 0_usize;
+        let mut offset = 0;
+
+        loop {
+            offset += 1;
+
+            match self.get(offset).raw() {
+                RawToken::Punctuator(
+                    Punctuator::OpenBrace | Punctuator::OpenParent | Punctuator::OpenBracket,
+                ) => depth += 1,
+                RawToken::Punctuator(
+                    Punctuator::CloseBrace | Punctuator::CloseParent | Punctuator::CloseBracket,
+                ) => {
+                    if depth == 0 {
+                        return false;
+                    }
+
+                    depth -= 1;
+                }
+                RawToken::Punctuator(Punctuator::Colon) if depth == 0 => return true,
+                RawToken::Punctuator(Punctuator::Semicolon) if depth == 0 => return false,
+                RawToken::EndOfFile => return false,
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses expression in parentheses, e.g. `(a + b)`.
+    fn parse_parenthesized_expression(&mut self) -> Result<Expression, Error> {
+        self.advance();
+        let expression = self.parse_expression()?;
+        self.consume(Punctuator::CloseParent.into())?;
+
+        Ok(expression)
+    }
+
+    /// Parses loop, e.g. `while a { b; }`.
+    fn parse_while_expression(&mut self) -> Result<Expression, Error> {
+        let start_location = self.consume(Keyword::While.into())?.location();
+        let condition = self.parse_expression()?;
+        let body = self.parse_body("while")?;
+        let location = start_location.to(body.location());
+
+        Ok(Expression::While(WhileExpression::new(
+            Box::new(condition),
+            Box::new(body),
+            location,
+        )))
+    }
+
+    /// Parses conditional expression, e.g. `if a { b; } else if c { d; }`.
+    fn parse_if_expression(&mut self) -> Result<Expression, Error> {
+        let start_location = self.consume(Keyword::If.into())?.location();
+        let condition = self.parse_expression()?;
+        let then_branch = self.parse_body("if")?;
+
+        let else_branch = if self.peek_keyword() == Some(Keyword::Else) {
+            self.advance();
+
+            Some(Box::new(if self.peek_keyword() == Some(Keyword::If) {
+                self.nested(Self::parse_if_expression)?
+            } else {
+                self.parse_body("else")?
+            }))
+        } else {
+            None
+        };
+
+        let end_location = else_branch
+            .as_ref()
+            .map_or(then_branch.location(), |else_branch| else_branch.location());
+
+        Ok(Expression::If(IfExpression::new(
+            Box::new(condition),
+            Box::new(then_branch),
+            else_branch,
+            start_location.to(end_location),
+        )))
+    }
+
+    /// Parses body of `while` or `if`, warning about it being empty if
+    /// [`Parser::with_empty_body_warnings`] is enabled.
+    fn parse_body(&mut self, construct: &str) -> Result<Expression, Error> {
+        let body = self.nested(Self::parse_statements_block)?;
+
+        if self.warn_empty_bodies && body.is_empty() {
+            self.diagnostics.push(Diagnostic::warning(
+                format!("empty `{construct}` body"),
+                body.location(),
+            ));
+        }
+
+        Ok(Expression::Block(body))
+    }
+
+    /// Parses object construction expression, e.g. `new Foo(1, 2)`.
+    fn parse_new_expression(&mut self) -> Result<Expression, Error> {
+        let start_location = self.consume(Keyword::New.into())?.location();
+        let (class, _) = self.consume_identifier()?;
+        let (arguments, end_location) = self.parse_call_arguments()?;
+
+        Ok(Expression::New(NewExpression::new(
+            class,
+            arguments,
+            start_location.to(end_location),
+        )))
+    }
+
+    /// Parses exception raising expression, e.g. `throw error`.
+    fn parse_throw_expression(&mut self) -> Result<Expression, Error> {
+        let start_location = self.consume(Keyword::Throw.into())?.location();
+        let value = self.parse_expression()?;
+        let location = start_location.to(value.location());
+
+        Ok(Expression::Throw(ThrowExpression::new(
+            Box::new(value),
+            location,
+        )))
+    }
+
+    /// Parses parenthesized comma separated arguments, e.g. `(1, 2)`. Returns
+    /// arguments and location of the closing parenthesis.
+    fn parse_call_arguments(&mut self) -> Result<(Vec<Expression>, Location), Error> {
+        self.consume(Punctuator::OpenParent.into())?;
+        self.parse_comma_separated(Punctuator::CloseParent, Self::parse_expression)
+    }
+
+    /// Parses comma separated items up to and including `close`, e.g.
+    /// `1, 2)`. A single trailing comma is allowed, but leading and
+    /// repeated commas are reported, e.g. `,1)` or `1,,2)`. Returns items
+    /// and location of `close`.
+    fn parse_comma_separated<T>(
+        &mut self,
+        close: Punctuator,
+        mut parse_item: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<(Vec<T>, Location), Error> {
+        let mut items = Vec::new();
+
+        while !self.current().is_punctuator(close) {
+            let current = self.current();
+            if current.is_punctuator(Punctuator::Comma) {
+                return Err(Error::UnexpectedToken {
+                    expected: close.into(),
+                    found: current,
+                });
+            }
+
+            items.push(parse_item(self)?);
+
+            if !self.current().is_punctuator(close) {
+                self.consume(Punctuator::Comma.into())?;
+            }
+        }
+
+        let end_location = self.consume(close.into())?.location();
+
+        Ok((items, end_location))
+    }
+
+    /// Runs `f` one nesting level deeper, failing if the level exceeds
+    /// the configured maximum.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        self.enter_nesting_level()?;
+        let result = f(self);
+        self.nesting_depth -= 1;
+
+        result
+    }
+
+    /// Goes one nesting level deeper, failing if the level exceeds
+    /// the configured maximum. The caller restores the level.
+    fn enter_nesting_level(&mut self) -> Result<(), Error> {
+        if self.nesting_depth >= self.max_nesting_depth {
+            return Err(Error::NestingTooDeep {
+                location: self.current().location(),
+                limit: self.max_nesting_depth,
+            });
+        }
+
+        self.nesting_depth += 1;
+        Ok(())
+    }
+
+    fn consume(&mut self, raw: RawToken) -> Result<Token, Error> {
+        self.consume_any(&[raw])
+    }
+
+    /// Consumes the current token if it is one of `allowed`, e.g. any
+    /// assignment operator. Otherwise reports the first alternative as
+    /// the expected one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `allowed` is empty.
+    #[must_use = "consumed token is lost, use `Parser::checkpoint` to look ahead"]
+    pub fn consume_any(&mut self, allowed: &[RawToken]) -> Result<Token, Error> {
+        debug_assert!(!allowed.is_empty(), "no tokens are allowed");
+
+        let current = self.current();
+        if !allowed.contains(&current.raw()) {
+            return Err(Error::UnexpectedToken {
+                expected: allowed[0],
+                found: current,
+            });
+        }
+
+        self.current_token_idx += 1;
+        Ok(current)
+    }
+
+    /// Consumes semicolon terminating a statement. Returns `None` if the
+    /// semicolon is optional and omitted.
+    ///
+    /// A missing semicolon is reported right after the previous token,
+    /// where it should be inserted, and nothing is consumed, so that
+    /// parsing can continue with the next statement.
+    fn expect_semicolon(&mut self) -> Result<Option<Token>, Error> {
+        let current = self.current();
+
+        if current.is_punctuator(Punctuator::Semicolon) {
+            self.advance();
+            return Ok(Some(current));
+        }
+
+        if self.optional_trailing_semicolons
+            && (self.is_at_end() || current.is_punctuator(Punctuator::CloseBrace))
+        {
+            return Ok(None);
+        }
+
+        Err(Error::MissingSemicolon {
+            end: self.get_previous().location().end(),
+            found: current,
+        })
+    }
+
+    fn consume_identifier(&mut self) -> Result<(IdentifierId, Location), Error> {
+        let current = self.current();
+        let Some(name) = identifier_name(current.raw()) else {
+            return Err(Error::ExpectedIdentifier { found: current });
+        };
+
+        self.current_token_idx += 1;
+        Ok((name, current.location()))
+    }
+
+    fn advance(&mut self) {
+        self.current_token_idx += 1;
+    }
+
+    /// Returns the current token if it is a keyword, without consuming it.
+    #[inline]
+    #[must_use]
+    pub fn peek_keyword(&self) -> Option<Keyword> {
+        match self.current().raw() {
+            RawToken::Keyword(keyword) => Some(keyword),
+            _ => None,
+        }
+    }
+
+    /// Returns the current token if it is a punctuator, without consuming it.
+    #[inline]
+    #[must_use]
+    pub fn peek_punctuator(&self) -> Option<Punctuator> {
+        match self.current().raw() {
+            RawToken::Punctuator(punctuator) => Some(punctuator),
+            _ => None,
+        }
+    }
+
+    /// Returns current state of the parser, which can later be restored
+    /// with [`Parser::rewind`] if a speculative parse fails.
+    #[inline]
+    #[must_use]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            token_idx: self.current_token_idx,
+            diagnostics_len: self.diagnostics.len(),
+        }
+    }
+
+    /// Restores state saved with [`Parser::checkpoint`]: the position in
+    /// the token stream, and the diagnostics, dropping those reported by
+    /// the abandoned parse.
+    #[inline]
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.current_token_idx = checkpoint.token_idx;
+        self.diagnostics.truncate(checkpoint.diagnostics_len);
+    }
+
+    fn current(&self) -> Token {
+        self.get(0)
+    }
+
+    fn get_previous(&self) -> Token {
+        self.tokens[self.current_token_idx - 1]
+    }
+
+    fn get(&self, offset: usize) -> Token {
+        self.tokens
+            .get(self.current_token_idx + offset)
+            .copied()
+            .unwrap_or_else(|| {
+                Token::eof(
+                    self.tokens
+                        .last()
+                        .map_or(Location::of_first_byte(self.path), Token::location),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{
+        const_eval::fold_statements_block, diagnostic::Severity, interpreter::Interpreter,
+        lexer::tokenize_with_trivia, pretty_printer::format,
+    };
+
+    fn parse_expression(source: &str) -> Result<Expression, Error> {
+        Parser::new(PathId::from("test.lzr"), source).parse_expression()
+    }
+
+    fn parse_statement(source: &str) -> Result<RawStatement, Error> {
+        Parser::new(PathId::from("test.lzr"), source)
+            .parse_statement()
+            .map(Statement::into_raw)
+    }
+
+    #[test]
+    fn new_expression_without_arguments() {
+        let Ok(Expression::New(new)) = parse_expression("new Foo()") else {
+            panic!("expected new expression");
+        };
+
+        assert_eq!(new.class(), IdentifierId::from("Foo"));
+        assert!(new.arguments().is_empty());
+        assert_eq!(new.location().start().offset(), 0);
+        assert_eq!(new.location().end().offset(), 9);
+    }
+
+    #[test]
+    fn new_expression_with_argument() {
+        let Ok(Expression::New(new)) = parse_expression("new Foo(1)") else {
+            panic!("expected new expression");
+        };
+
+        assert_eq!(new.class(), IdentifierId::from("Foo"));
+        assert!(matches!(
+            new.arguments(),
+            [Expression::Literal(literal)] if *literal.value() == Value::Int(1)
+        ));
+    }
+
+    #[test]
+    fn consume_any() {
+        let allowed = [Punctuator::Plus.into(), Punctuator::Minus.into()];
+        let mut parser = Parser::new(PathId::from("test.lzr"), "- + *");
+
+        assert!(parser
+            .consume_any(&allowed)
+            .unwrap()
+            .is_punctuator(Punctuator::Minus));
+        assert!(parser
+            .consume_any(&allowed)
+            .unwrap()
+            .is_punctuator(Punctuator::Plus));
+        assert!(matches!(
+            parser.consume_any(&allowed),
+            Err(Error::UnexpectedToken {
+                expected: RawToken::Punctuator(Punctuator::Plus),
+                found,
+            }) if found.is_punctuator(Punctuator::Asterisk)
+        ));
+        assert_eq!(parser.checkpoint().token_idx, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn consume_any_nothing() {
+        let _ = Parser::new(PathId::from("test.lzr"), "+").consume_any(&[]);
+    }
+
+    #[test]
+    fn custom_number_parser() {
+        #[derive(Debug)]
+        struct Doubling;
+
+        impl NumberParser for Doubling {
+            fn parse_int(&self, text: &str) -> Option<Value> {
+                let value = text.replace('_', "").parse::<i64>().ok()?;
+
+                Some(Value::Int(value * 2))
+            }
+
+            fn parse_float(&self, text: &str) -> Option<Value> {
+                let value = text.replace('_', "").parse::<f64>().ok()?;
+
+                Some(Value::Float(value * 2.0))
+            }
+        }
+
+        let literals = |parser: Parser| {
+            let Ok(Expression::Call(call)) = parser.clone().parse_expression() else {
+                panic!("expected call");
+            };
+
+            call.arguments()
+                .iter()
+                .map(|argument| match argument {
+                    Expression::Literal(literal) => literal.value().clone(),
+                    _ => panic!("expected literal"),
+                })
+                .collect::<Vec<_>>()
+        };
+        let source = "f(2_1, 0.25, 0xFF, 18446744073709551616, \"a\")";
+        let parser = Parser::new(PathId::from("test.lzr"), source);
+
+        assert_eq!(
+            literals(parser.clone().with_number_parser(Doubling)),
+            [
+                Value::Int(42),
+                Value::Float(0.5),
+                Value::Int(255),
+                Value::Float(36_893_488_147_419_103_232.0),
+                Value::String("a".to_owned())
+            ]
+        );
+        assert_eq!(
+            literals(
+                Parser::from_tokens(
+                    PathId::from("test.lzr"),
+                    Lexer::new(PathId::from("test.lzr"), source)
+                )
+                .with_number_parser(Doubling)
+            ),
+            literals(parser.clone())
+        );
+        assert_eq!(
+            literals(parser),
+            [
+                Value::Int(21),
+                Value::Float(0.25),
+                Value::Int(255),
+                Value::Float(18_446_744_073_709_551_616.0),
+                Value::String("a".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "single-threaded"))]
+    fn parser_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Parser>();
+    }
+
+    #[test]
+    fn function_signature_types() {
+        let Ok(RawStatement::Function(function)) =
+            parse_statement("func f(a: int, b: string) -> bool {}")
+        else {
+            panic!("expected function declaration");
+        };
+        let type_name = |type_: Option<TypeAnnotation>| type_.map(|type_| type_.name());
+
+        assert_eq!(function.parameters().len(), 2);
+        assert_eq!(
+            type_name(function.parameter_type(0)),
+            Some(IdentifierId::from("int"))
+        );
+        assert_eq!(
+            type_name(function.parameter_type(1)),
+            Some(IdentifierId::from("string"))
+        );
+        assert_eq!(
+            type_name(function.return_type()),
+            Some(IdentifierId::from("bool"))
+        );
+        assert_eq!(
+            function.return_type().unwrap().location().start().offset(),
+            29
+        );
+
+        let Ok(RawStatement::Function(function)) = parse_statement("func f(a, b: int) {}") else {
+            panic!("expected function declaration");
+        };
+
+        assert!(function.parameter_type(0).is_none());
+        assert!(function.parameter_type(1).is_some());
+        assert!(function.return_type().is_none());
+
+        let Ok(RawStatement::Function(function)) = parse_statement("func f(a, b) {}") else {
+            panic!("expected function declaration");
+        };
+
+        assert!(function.parameter_types().is_empty());
+        assert!(function.return_type().is_none());
+
+        assert!(matches!(
+            parse_statement("func f(a:) {}"),
+            Err(Error::ExpectedIdentifier { .. })
+        ));
+        assert!(matches!(
+            parse_statement("func f() -> {}"),
+            Err(Error::ExpectedIdentifier { .. })
+        ));
+    }
+
+    #[test]
+    fn comma_separated_lists() {
+        let arguments = |source| match parse_expression(source) {
+            Ok(Expression::Call(call)) => call.arguments().len(),
+            result => panic!("expected call, got {result:?}"),
+        };
+
+        assert_eq!(arguments("f()"), 0);
+        assert_eq!(arguments("f(1)"), 1);
+        assert_eq!(arguments("f(1,)"), 1);
+        assert_eq!(arguments("f(1, 2,)"), 2);
+
+        let elements = |source| match parse_expression(source) {
+            Ok(Expression::Array(array)) => array.elements().len(),
+            result => panic!("expected array, got {result:?}"),
+        };
+
+        assert_eq!(elements("[]"), 0);
+        assert_eq!(elements("[1]"), 1);
+        assert_eq!(elements("[1,]"), 1);
+        assert_eq!(elements("[1, [2, 3], a + 4,]"), 3);
+
+        for source in ["[,]", "[,1]", "[1,,2]", "[1,,]"] {
+            assert!(
+                matches!(
+                    parse_expression(source),
+                    Err(Error::UnexpectedToken {
+                        expected: RawToken::Punctuator(Punctuator::CloseBracket),
+                        found,
+                    }) if found.is_punctuator(Punctuator::Comma)
+                ),
+                "{source}"
+            );
+        }
+
+        let Ok(RawStatement::Function(function)) = parse_statement("func f(a, b,) {}") else {
+            panic!("expected function declaration");
+        };
+        assert_eq!(function.parameters().len(), 2);
+
+        for source in ["f(,)", "f(,1)", "f(1,,2)", "f(1,,)", "new Foo(,)"] {
+            assert!(
+                matches!(
+                    parse_expression(source),
+                    Err(Error::UnexpectedToken {
+                        expected: RawToken::Punctuator(Punctuator::CloseParent),
+                        found,
+                    }) if found.is_punctuator(Punctuator::Comma)
+                ),
+                "{source}"
+            );
+        }
+
+        assert!(matches!(
+            parse_statement("func f(, a) {}"),
+            Err(Error::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            parse_expression("f(1 2)"),
+            Err(Error::UnexpectedToken {
+                expected: RawToken::Punctuator(Punctuator::Comma),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_expression("[1 2]"),
+            Err(Error::UnexpectedToken {
+                expected: RawToken::Punctuator(Punctuator::Comma),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn return_statement() {
+        let Ok(RawStatement::Return(return_)) = parse_statement("return a + 1;") else {
+            panic!("expected return statement");
+        };
+
+        assert!(matches!(return_.expression(), Some(Expression::Binary(_))));
+        assert_eq!(return_.location().end().offset(), 13);
+
+        let Ok(RawStatement::Return(return_)) = parse_statement("return;") else {
+            panic!("expected return statement");
+        };
+
+        assert!(return_.expression().is_none());
+        assert_eq!(return_.location().end().offset(), 7);
+
+        let block = Parser::new(PathId::from("test.lzr"), "{ return }")
+            .with_optional_trailing_semicolons()
+            .parse_statements_block()
+            .unwrap();
+        assert!(matches!(
+            block.statements()[0].raw(),
+            RawStatement::Return(return_) if return_.expression().is_none()
+        ));
+
+        assert!(matches!(
+            parse_statement("return"),
+            Err(Error::MissingSemicolon { .. })
+        ));
+    }
+
+    #[test]
+    fn print_statement() {
+        let Ok(RawStatement::Print(print)) = parse_statement("println 1, a + 2, f();") else {
+            panic!("expected print statement");
+        };
+
+        assert!(print.is_println());
+        assert_eq!(print.arguments().len(), 3);
+        assert_eq!(print.location().end().offset(), 22);
+
+        let Ok(RawStatement::Print(print)) = parse_statement("print;") else {
+            panic!("expected print statement");
+        };
+
+        assert!(!print.is_println());
+        assert!(print.arguments().is_empty());
+
+        assert!(matches!(
+            parse_statement("println 1, 2,;"),
+            Err(Error::ExpectedExpression { found }) if found.is_punctuator(Punctuator::Semicolon)
+        ));
+        assert!(matches!(
+            parse_statement("println , 1;"),
+            Err(Error::ExpectedExpression { .. })
+        ));
+    }
+
+    #[test]
+    fn new_expression_requires_class_name() {
+        assert!(matches!(
+            parse_expression("new (1)"),
+            Err(Error::ExpectedIdentifier { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_class() {
+        let Ok(RawStatement::Class(class)) = parse_statement("class Foo {}") else {
+            panic!("expected class declaration");
+        };
+
+        assert_eq!(class.name(), IdentifierId::from("Foo"));
+        assert!(class.methods().is_empty());
+        assert!(class.fields().is_empty());
+    }
+
+    #[test]
+    fn class_with_method_and_field() {
+        let Ok(RawStatement::Class(class)) = parse_statement("class Foo { func bar() {} x; }")
+        else {
+            panic!("expected class declaration");
+        };
+
+        assert_eq!(class.methods().len(), 1);
+        assert_eq!(class.methods()[0].name(), IdentifierId::from("bar"));
+        assert_eq!(class.fields(), &[IdentifierId::from("x")]);
+    }
+
+    #[test]
+    fn malformed_class_member() {
+        assert!(matches!(
+            parse_statement("class Foo { 1; }"),
+            Err(Error::ExpectedClassMember { .. })
+        ));
+        assert!(matches!(
+            parse_statement("class Foo { x }"),
+            Err(Error::UnexpectedToken { .. })
+        ));
+    }
+
+    #[test]
+    fn throw_expression() {
+        let Ok(Expression::Throw(throw)) = parse_expression("throw x") else {
+            panic!("expected throw expression");
+        };
+
+        assert!(matches!(
+            throw.value(),
+            Expression::Identifier(identifier) if identifier.name() == IdentifierId::from("x")
+        ));
+        assert_eq!(throw.location().start().offset(), 0);
+        assert_eq!(throw.location().end().offset(), 7);
+    }
+
+    #[test]
+    fn throw_requires_value() {
+        assert!(matches!(
+            parse_expression("throw"),
+            Err(Error::ExpectedExpression { .. })
+        ));
+    }
+
+    #[test]
+    fn deeply_nested_expression() {
+        let source = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+
+        assert!(matches!(
+            parse_expression(&source),
+            Err(Error::NestingTooDeep {
+                limit: DEFAULT_MAX_NESTING_DEPTH,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn nesting_limit_fits_thread_stack() {
+        let nested = |n: usize| {
+            [
+                format!("{}1{};", "(".repeat(n), ")".repeat(n)),
+                format!("{}1{};", "f(".repeat(n), ")".repeat(n)),
+                format!("x = {}1{};", "{1: ".repeat(n), "}".repeat(n)),
+                format!("{}{}", "{ ".repeat(n), "} ".repeat(n)),
+                format!("{}1;{}", "if a { ".repeat(n), "} ".repeat(n)),
+                format!("{}1;", "- ".repeat(n)),
+                format!("x = 1{};", " + 1".repeat(n)),
+                format!("x = 1{};", " ** 1".repeat(n)),
+            ]
+        };
+
+        thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || {
+                for source in nested(100_000) {
+                    let errors = parse("test.lzr", &source).unwrap_err();
+
+                    assert!(matches!(errors[0], Error::NestingTooDeep { .. }));
+                }
+
+                for source in nested(DEFAULT_MAX_NESTING_DEPTH / 3) {
+                    let program = parse("test.lzr", &source).unwrap();
+
+                    assert!(format("test.lzr", &source).is_ok());
+                    let _ = check("test.lzr", &source);
+                    let _ = fold_statements_block(&program);
+                    let _ = Interpreter::new().eval_block(&program);
+                }
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn long_operator_chain() {
+        let chain = |n: usize| format!("x = 1{};", " + 1".repeat(n - 1));
+
+        for source in [chain(20_000), chain(50_000)] {
+            assert!(matches!(
+                parse("test.lzr", &source).unwrap_err()[..],
+                [Error::NestingTooDeep { .. }]
+            ));
+            assert!(check("test.lzr", &source)
+                .iter()
+                .any(|diagnostic| diagnostic.message().contains("nested too deeply")));
+            assert!(format("test.lzr", &source).is_err());
+            assert!(matches!(
+                parse_repl_input("test.lzr", &source[4..source.len() - 1]),
+                Err(errors) if matches!(errors[..], [Error::NestingTooDeep { .. }])
+            ));
+        }
+
+        let source = chain(DEFAULT_MAX_NESTING_DEPTH / 2);
+        let program = parse("test.lzr", &source).unwrap();
+
+        assert!(check("test.lzr", &source).is_empty());
+        assert!(format("test.lzr", &source).is_ok());
+        assert_eq!(
+            Interpreter::new().eval_block(&program),
+            Ok(Value::Int(DEFAULT_MAX_NESTING_DEPTH as i64 / 2))
+        );
+        assert!(parse("test.lzr", &chain(DEFAULT_MAX_NESTING_DEPTH + 1)).is_err());
+        assert!(parse("test.lzr", &format!("{} a = 1;", chain(100_000)))
+            .is_err_and(|errors| matches!(errors[..], [Error::NestingTooDeep { .. }])));
+    }
+
+    #[test]
+    fn configurable_nesting_depth() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "((1))").with_max_nesting_depth(2);
+
+        let Err(Error::NestingTooDeep { location, limit }) = parser.parse_expression() else {
+            panic!("expected nesting error");
+        };
+
+        assert_eq!(limit, 2);
+        assert_eq!(location.start().offset(), 2);
+        assert!(Parser::new(PathId::from("test.lzr"), "((1))")
+            .with_max_nesting_depth(3)
+            .parse_expression()
+            .is_ok());
+    }
+
+    #[test]
+    fn parse_result() {
+        let result = parse_with_diagnostics("test.lzr", "a = 1;\nb;");
+
+        assert!(result.diagnostics().is_empty());
+        assert!(!result.has_errors());
+        assert_eq!(result.into_tree_if_ok().map(|tree| tree.len()), Some(2));
+
+        let result = parse_with_diagnostics("test.lzr", "a = ;\nb;\nwhile b {}");
+
+        assert!(result.has_errors());
+        assert_eq!(result.diagnostics().len(), 2);
+        assert_eq!(result.diagnostics()[1].severity(), Severity::Warning);
+        assert_eq!(result.tree().len(), 2);
+        assert!(result.into_tree_if_ok().is_none());
+    }
+
+    #[test]
+    fn source_too_large() {
+        let errors = parse_with_max_source_length("test.lzr", "a = 1;", Some(5)).unwrap_err();
+
+        assert!(matches!(
+            &errors[..],
+            [Error::SourceTooLarge { error, .. }] if error.length() == 6 && error.limit() == 5
+        ));
+        assert_eq!(
+            errors[0].to_string(),
+            "test.lzr:1:1: source is too large: 6 bytes (the limit is 5)"
+        );
+        assert!(parse_with_max_source_length("test.lzr", "a = 1;", Some(6)).is_ok());
+        assert!(parse_with_max_source_length("test.lzr", "a = 1;", None).is_ok());
+
+        assert!(matches!(
+            parse_with_diagnostics_and_max_source_length("test.lzr", "a = 1;", Some(5)),
+            Err(Error::SourceTooLarge { .. })
+        ));
+        assert!(matches!(
+            parse_with_diagnostics_and_max_source_length("test.lzr", "a = 1;", Some(6)),
+            Ok(result) if result.diagnostics().is_empty()
+        ));
+
+        assert!(matches!(
+            parse_repl_input_with_max_source_length("test.lzr", "1 + 1", Some(4)),
+            Err(errors) if matches!(errors[..], [Error::SourceTooLarge { .. }])
+        ));
+        assert!(matches!(
+            parse_repl_input_with_max_source_length("test.lzr", "1 + 1", Some(5)),
+            Ok(ReplInput::Expression(_))
+        ));
+    }
+
+    #[test]
+    fn too_many_errors() {
+        let source = "1 2;".repeat(10);
+        let limit = NonZeroUsize::new(3).unwrap();
+        let (_, errors) = Parser::new(PathId::from("test.lzr"), &source)
+            .with_max_errors(limit)
+            .parse_program();
+
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            errors[3],
+            Error::TooManyErrors { limit: 3, location } if location.start().offset() == 10
+        ));
+        assert_eq!(
+            parse_with_max_errors("test.lzr", &source, limit).unwrap_err(),
+            errors
+        );
+
+        let source = "1 2;".repeat(DEFAULT_MAX_ERRORS.get() * 2);
+        let (_, errors) = Parser::new(PathId::from("test.lzr"), &source).parse_program();
+
+        assert_eq!(errors.len(), DEFAULT_MAX_ERRORS.get() + 1);
+
+        let errors = parse_with_max_errors("test.lzr", "1 2; 3 4;", NonZeroUsize::MIN).unwrap_err();
+
+        assert!(matches!(
+            errors[..],
+            [_, Error::TooManyErrors { limit: 1, .. }]
+        ));
+        assert!(parse_with_max_errors("test.lzr", "1;", NonZeroUsize::MIN).is_ok());
+    }
+
+    #[test]
+    fn complete_expression() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "1 + 2");
+
+        assert!(!parser.is_at_end());
+        assert!(parser.parse_complete_expression().is_ok());
+        assert!(parser.is_at_end());
+    }
+
+    #[test]
+    fn trailing_tokens_after_complete_expression() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "1 + 2 3");
+
+        let Err(Error::UnexpectedToken { expected, found }) = parser.parse_complete_expression()
+        else {
+            panic!("expected unexpected token error");
+        };
+
+        assert_eq!(expected, RawToken::EndOfFile);
+        assert_eq!(found.raw(), RawToken::Int(3));
+        assert_eq!(found.location().start().offset(), 6);
+    }
+
+    #[test]
+    fn parse_program() {
+        let program = parse(
+            "test.lzr",
+            "func add(a, b) { return a + b; }\nclass Foo { x; }\nadd(1, 2);",
+        )
+        .unwrap();
+
+        assert_eq!(program.statements().len(), 3);
+        assert!(matches!(
+            program.statements()[0].raw(),
+            RawStatement::Function(_)
+        ));
+        assert!(matches!(
+            program.statements()[1].raw(),
+            RawStatement::Class(_)
+        ));
+        assert!(matches!(
+            program.statements()[2].raw(),
+            RawStatement::Expression(_)
+        ));
+    }
+
+    #[test]
+    fn parse_program_collects_all_errors() {
+        let errors = parse("test.lzr", "1 +; func f( {} g();").unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn trivia_is_skipped() {
+        let tokens = crate::lexer::tokenize_with_trivia("test.lzr", "/* a */ 1 + // b\n 2");
+        assert!(tokens.iter().any(|token| token.raw().is_trivia()));
+
+        let mut parser = Parser::new_from_tokens(PathId::from("test.lzr"), tokens);
+
+        assert!(matches!(
+            parser.parse_complete_expression(),
+            Ok(Expression::Binary(_))
+        ));
+    }
+
+    #[test]
+    fn comments_are_attached_to_statements() {
+        let source = "a; // a\n\n/// Adds numbers.\nfunc add(x, y) {\n    // b\n    b;\n}\n";
+        let tokens = tokenize_with_trivia("test.lzr", source);
+        let (program, errors) =
+            Parser::new_from_tokens(PathId::from("test.lzr"), tokens).parse_program();
+        assert!(errors.is_empty());
+
+        let [a, add] = program.statements() else {
+            panic!("expected two statements");
+        };
+        let texts = |comments: &[Comment]| {
+            comments
+                .iter()
+                .map(|comment| comment.text(source))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(a.leading_comments().is_empty());
+        assert_eq!(texts(a.trailing_comments()), ["// a"]);
+        assert_eq!(texts(add.leading_comments()), ["/// Adds numbers."]);
+        assert_eq!(add.leading_comments()[0].location().start().line(), 3);
+        assert!(add.trailing_comments().is_empty());
+
+        let RawStatement::Function(add) = add.raw() else {
+            panic!("expected function");
+        };
+        assert_eq!(
+            texts(add.body().statements()[0].leading_comments()),
+            ["// b"]
+        );
+    }
+
+    #[test]
+    fn comments_are_not_attached_without_trivia() {
+        let statement = Parser::new(PathId::from("test.lzr"), "// a\nb; // c")
+            .parse_statement()
+            .unwrap();
+
+        assert!(statement.leading_comments().is_empty());
+        assert!(statement.trailing_comments().is_empty());
+    }
+
+    #[test]
+    fn strict_trailing_semicolons() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "{ a; b }");
+
+        assert!(matches!(
+            parser.parse_statements_block(),
+            Err(Error::MissingSemicolon { found, .. })
+                if found.is_punctuator(Punctuator::CloseBrace)
+        ));
+    }
+
+    #[test]
+    fn optional_trailing_semicolons() {
+        let mut parser =
+            Parser::new(PathId::from("test.lzr"), "{ a; b }").with_optional_trailing_semicolons();
+
+        assert_eq!(
+            parser.parse_statements_block().unwrap().statements().len(),
+            2
+        );
+
+        let mut parser = Parser::new(PathId::from("test.lzr"), "a; return b")
+            .with_optional_trailing_semicolons();
+        let (program, errors) = parser.parse_program();
+
+        assert!(errors.is_empty());
+        assert_eq!(program.statements().len(), 2);
+
+        let mut parser =
+            Parser::new(PathId::from("test.lzr"), "{ a b }").with_optional_trailing_semicolons();
+
+        assert!(parser.parse_statements_block().is_err());
+    }
+
+    #[test]
+    fn peek_keyword() {
+        let parser = Parser::new(PathId::from("test.lzr"), "return 1;");
+
+        assert_eq!(parser.peek_keyword(), Some(Keyword::Return));
+        assert_eq!(parser.peek_punctuator(), None);
+        assert_eq!(parser.peek_keyword(), Some(Keyword::Return));
+    }
+
+    #[test]
+    fn peek_punctuator() {
+        let parser = Parser::new(PathId::from("test.lzr"), "+ 1");
+
+        assert_eq!(parser.peek_punctuator(), Some(Punctuator::Plus));
+        assert_eq!(parser.peek_keyword(), None);
+    }
+
+    #[test]
+    fn assignment() {
+        let Expression::Assignment(assignment) = parse_expression("a.b[0] = c = 1").unwrap() else {
+            panic!("expected assignment");
+        };
+
+        assert!(matches!(assignment.target(), Expression::Index(_)));
+        assert!(matches!(assignment.value(), Expression::Assignment(_)));
+        assert_eq!(assignment.operator(), Punctuator::Eq);
+        assert_eq!(assignment.location().start().offset(), 0);
+        assert_eq!(assignment.location().end().offset(), 14);
+    }
+
+    #[test]
+    fn invalid_assignment_target() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "1 = 2");
+
+        let Err(Error::InvalidAssignmentTarget { location }) = parser.parse_expression() else {
+            panic!("expected invalid assignment target error");
+        };
+
+        assert_eq!(location.start().offset(), 0);
+        assert_eq!(location.end().offset(), 1);
+
+        let mut parser = Parser::new(PathId::from("test.lzr"), "a + b = 2");
+
+        assert!(matches!(
+            parser.parse_expression(),
+            Err(Error::InvalidAssignmentTarget { .. })
+        ));
+    }
+
+    #[test]
+    fn disabled_keyword_as_identifier() {
+        let path = PathId::from("test.lzr");
+        let tokens = Lexer::new(path, "include = 1;")
+            .with_disabled_keywords([Keyword::Include])
+            .collect();
+        let statement = Parser::new_from_tokens(path, tokens)
+            .parse_statement()
+            .unwrap();
+
+        let RawStatement::Expression(statement) = statement.into_raw() else {
+            panic!("expected expression statement");
+        };
+
+        assert!(matches!(statement, Expression::Assignment(_)));
+    }
+
+    #[test]
+    fn clone_does_not_share_position() {
+        let parser = Parser::new(PathId::from("test.lzr"), "a + b");
+        let mut clone = parser.clone();
+
+        clone.advance();
+
+        assert_eq!(clone.peek_punctuator(), Some(Punctuator::Plus));
+        assert_eq!(parser.peek_punctuator(), None);
+        assert_eq!(
+            parser.current().raw(),
+            RawToken::Identifier(IdentifierId::from("a"))
+        );
+    }
+
+    #[test]
+    fn rewind_after_failed_speculative_parse() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "a + b c");
+        let checkpoint = parser.checkpoint();
+
+        assert!(parser.parse_complete_expression().is_err());
+        assert_ne!(parser.checkpoint(), checkpoint);
+
+        parser.rewind(checkpoint);
+
+        assert_eq!(parser.checkpoint(), checkpoint);
+        assert!(matches!(
+            parser.parse_expression(),
+            Ok(Expression::Binary(_))
+        ));
+        assert_eq!(
+            parser.current().raw(),
+            RawToken::Identifier(IdentifierId::from("c"))
+        );
+    }
+
+    #[test]
+    fn rewind_drops_diagnostics() {
+        let mut parser =
+            Parser::new(PathId::from("test.lzr"), "while a {} c").with_empty_body_warnings();
+        let checkpoint = parser.checkpoint();
+
+        assert!(parser.parse_complete_expression().is_err());
+        assert_eq!(parser.diagnostics().len(), 1);
+
+        parser.rewind(checkpoint);
+
+        assert!(parser.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn from_filtered_tokens() {
+        let path = PathId::from("test.lzr");
+        let tokens = tokenize_with_trivia(path, "a /* b */ + c d")
+            .into_iter()
+            .filter(|token| token.raw() != RawToken::Identifier(IdentifierId::from("d")));
+        let mut parser = Parser::from_tokens(path, tokens);
+
+        let Ok(Expression::Binary(binary)) = parser.parse_complete_expression() else {
+            panic!("expected binary expression");
+        };
+
+        assert_eq!(binary.operator(), Punctuator::Plus);
+    }
+
+    #[test]
+    fn render_unexpected_token() {
+        let source = "return 1 foo";
+        let Err(error) = parse_statement(source) else {
+            panic!("expected error");
+        };
+
+        assert_eq!(error.render(source), "expected `;`, found `foo`");
+        assert_eq!(error.location().start().offset(), 8);
+
+        let source = "(1";
+        let Err(error) = parse_expression(source) else {
+            panic!("expected error");
+        };
+
+        assert_eq!(error.render(source), "expected `)`, found end of file");
+    }
+
+    #[test]
+    fn missing_semicolon() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "a = 1 b = 2;");
+        let (program, errors) = parser.parse_program();
+
+        let [error @ Error::MissingSemicolon { found, .. }] = errors[..] else {
+            panic!("expected missing semicolon, got {errors:?}");
+        };
+        let location = error.location();
+
+        assert_eq!(location.start().offset(), 5);
+        assert_eq!(location.end().offset(), 5);
+        assert_eq!(found.location().start().offset(), 6);
+
+        // Parsing continues right at `b`, rather than skipping the statement.
+        let [statement] = program.statements() else {
+            panic!("expected single statement");
+        };
+        assert_eq!(statement.location().start().offset(), 6);
+    }
+
+    #[test]
+    fn check_reports_warnings_and_errors_in_order() {
+        let diagnostics = check("test.lzr", "return 1 foo;\n`a`;");
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].message(), "expected `;`, found `foo`");
+        assert!(!diagnostics[1].is_error());
+        assert!(check("test.lzr", "a = 1;").is_empty());
+    }
+
+    #[test]
+    fn negative_number_is_unary_expression() {
+        let Ok(Expression::Unary(unary)) = parse_expression("-5") else {
+            panic!("expected unary expression");
+        };
+
+        assert_eq!(unary.operator(), Punctuator::Minus);
+        assert!(matches!(unary.operand(), Expression::Literal(_)));
+    }
+
+    #[test]
+    fn subtraction_is_binary_expression() {
+        for source in ["a-5", "a - 5"] {
+            let Ok(Expression::Binary(binary)) = parse_expression(source) else {
+                panic!("expected binary expression");
+            };
+
+            assert_eq!(binary.operator(), Punctuator::Minus);
+            assert!(matches!(binary.right(), Expression::Literal(_)));
+        }
+
+        let Ok(Expression::Binary(binary)) = parse_expression("a - -5") else {
+            panic!("expected binary expression");
+        };
+
+        assert_eq!(binary.operator(), Punctuator::Minus);
+        assert!(matches!(binary.right(), Expression::Unary(_)));
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let Ok(Expression::Assignment(assignment)) = parse_expression("a.b *= 2 + 1") else {
+            panic!("expected assignment");
+        };
+
+        assert_eq!(assignment.operator(), Punctuator::AsteriskEq);
+        assert!(matches!(assignment.value(), Expression::Binary(_)));
+        assert!(matches!(
+            parse_expression("1 += 2"),
+            Err(Error::InvalidAssignmentTarget { .. })
+        ));
+    }
+
+    #[test]
+    fn while_and_if() {
+        let statement =
+            parse_statement("while a { if b { c; } else if d {} else { e; } }").unwrap();
+
+        let RawStatement::Expression(Expression::While(while_)) = statement else {
+            panic!("expected while");
+        };
+        let Expression::Block(body) = while_.body() else {
+            panic!("expected block");
+        };
+        let [statement] = body.statements() else {
+            panic!("expected single statement");
+        };
+        let RawStatement::Expression(Expression::If(if_)) = statement.raw() else {
+            panic!("expected if");
+        };
+
+        assert!(matches!(if_.else_branch(), Some(Expression::If(_))));
+    }
+
+    #[test]
+    fn empty_body_warning() {
+        let mut parser =
+            Parser::new(PathId::from("test.lzr"), "while x {}").with_empty_body_warnings();
+
+        assert!(parser.parse_statement().is_ok());
+        assert_eq!(parser.diagnostics().len(), 1);
+        assert_eq!(parser.diagnostics()[0].severity(), Severity::Warning);
+        assert_eq!(parser.diagnostics()[0].message(), "empty `while` body");
+        assert_eq!(parser.diagnostics()[0].location().start().offset(), 8);
+
+        let mut parser =
+            Parser::new(PathId::from("test.lzr"), "if x { y; }").with_empty_body_warnings();
+
+        assert!(parser.parse_statement().is_ok());
+        assert!(parser.diagnostics().is_empty());
+
+        let mut parser = Parser::new(PathId::from("test.lzr"), "while x {}");
+
+        assert!(parser.parse_statement().is_ok());
+        assert!(parser.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn chained_comparison_warning() {
+        let mut parser = Parser::new(PathId::from("test.lzr"), "a < b <= c");
+
+        assert!(matches!(
+            parser.parse_expression(),
+            Ok(Expression::Binary(_))
+        ));
+        assert_eq!(parser.diagnostics().len(), 1);
+        assert_eq!(parser.diagnostics()[0].severity(), Severity::Warning);
+        assert_eq!(
+            parser.diagnostics()[0].message(),
+            "comparison operators cannot be chained, \
+             use `a < b && b <= c` instead of `a < b <= c`"
+        );
+        assert_eq!(parser.diagnostics()[0].location().end().offset(), 10);
+
+        for source in ["(a < b) < c", "a < b && b < c", "a + b < c"] {
+            let mut parser = Parser::new(PathId::from("test.lzr"), source);
+
+            assert!(parser.parse_expression().is_ok());
+            assert!(parser.diagnostics().is_empty(), "{source}");
+        }
+    }
+
+    #[test]
+    fn contextual_keyword_as_identifier() {
+        let program = parse(
+            "test.lzr",
+            "case = 1; func f(case) { return case + 1; } class A { case; } a.case;",
+        )
+        .unwrap();
+
+        assert_eq!(program.statements().len(), 4);
+
+        let RawStatement::Expression(Expression::Assignment(assignment)) =
+            program.statements()[0].raw()
+        else {
+            panic!("expected assignment");
+        };
+        let Expression::Identifier(target) = assignment.target() else {
+            panic!("expected identifier");
+        };
+
+        assert_eq!(target.name(), IdentifierId::from("case"));
+
+        let RawStatement::Class(class) = program.statements()[2].raw() else {
+            panic!("expected class");
+        };
+
+        assert_eq!(class.fields(), [IdentifierId::from("case")]);
+        assert!(parse("test.lzr", "while = 1;").is_err());
+    }
+
+    #[test]
+    fn eof_past_the_end() {
+        let parser = Parser::new(PathId::from("test.lzr"), "a + b");
+        let last = parser.get(2);
+
+        assert_eq!(parser.get(3), Token::eof(last.location()));
+        assert_eq!(parser.get(100), Token::eof(last.location()));
+
+        let parser = Parser::new(PathId::from("test.lzr"), "");
+
+        assert_eq!(
+            parser.current(),
+            Token::eof(Location::of_first_byte(PathId::from("test.lzr")))
+        );
+    }
+
+    #[test]
+    fn error_is_std_error() {
+        let error = parse_expression("(1 foo").unwrap_err();
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+
+        assert_eq!(boxed.to_string(), "test.lzr:1:4: expected `)`, found `foo`");
+
+        let error = parse_expression("1 +").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "test.lzr:1:3: expected expression, found end of file"
+        );
+    }
+
+    #[test]
+    fn repl_input() {
+        assert!(matches!(
+            parse_repl_input("test.lzr", "1 + 1"),
+            Ok(ReplInput::Expression(Expression::Binary(_)))
+        ));
+        assert!(matches!(
+            parse_repl_input("test.lzr", "x = 5;"),
+            Ok(ReplInput::Statements(block)) if block.len() == 1
+        ));
+        assert!(matches!(
+            parse_repl_input("test.lzr", "x = 5; x"),
+            Err(errors) if matches!(errors[..], [Error::MissingSemicolon { .. }])
+        ));
+    }
+
+    #[test]
+    fn map_literals() {
+        let parse_map = |source| {
+            let Ok(Expression::Map(map)) =
+                Parser::new(PathId::from("test.lzr"), source).parse_expression()
+            else {
+                panic!("expected map: {source}");
+            };
+
+            map
+        };
+
+        assert!(parse_map("{}").entries().is_empty());
+
+        let map = parse_map("{\"a\": 1 + 2}");
+        let [(Expression::Literal(_), Expression::Binary(_))] = map.entries() else {
+            panic!("expected single entry");
+        };
+        assert_eq!(map.location().end().offset(), 12);
+
+        assert_eq!(parse_map("{a: {b: c}, 1: 2,}").entries().len(), 2);
+    }
+
+    #[test]
+    fn block_or_map_statement() {
+        assert!(matches!(
+            parse_statement("{ a; }").unwrap(),
+            RawStatement::Expression(Expression::Block(_))
+        ));
+        assert!(matches!(
+            parse_statement("{ f(a, b); { c: d }; }").unwrap(),
+            RawStatement::Expression(Expression::Block(_))
+        ));
+        assert!(matches!(
+            parse_statement("{}").unwrap(),
+            RawStatement::Expression(Expression::Block(_))
+        ));
+        assert!(matches!(
+            parse_statement("{a: 1};").unwrap(),
+            RawStatement::Expression(Expression::Map(_))
+        ));
+        assert!(matches!(
+            parse_statement("{f(a): g[0]}[b];").unwrap(),
+            RawStatement::Expression(Expression::Index(_))
+        ));
+        assert!(matches!(
+            parse_statement("{};").unwrap(),
+            RawStatement::Expression(Expression::Map(map)) if map.entries().is_empty()
+        ));
+        assert!(matches!(
+            parse_statement("{}[a];").unwrap(),
+            RawStatement::Expression(Expression::Index(_))
+        ));
+        assert!(matches!(
+            parse_statement("{}.a;").unwrap(),
+            RawStatement::Expression(Expression::Member(_))
+        ));
+        assert!(matches!(
+            parse_statement("x = {};").unwrap(),
+            RawStatement::Expression(Expression::Assignment(assignment))
+                if matches!(assignment.value(), Expression::Map(_))
+        ));
     }
 }
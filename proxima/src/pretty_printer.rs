@@ -0,0 +1,338 @@
+//! Pretty-printer reproducing the source from the syntax tree with
+//! consistent indentation and spacing, used by `proxima fmt`.
+//!
+//! Comments are not part of the tree, so they are scanned as trivia and
+//! printed on their own lines before the statement that follows them, or
+//! after the statement if they start on the line it ends on or inside of
+//! it, e.g. in the middle of an expression. Literals are
+//! copied from the source as is, e.g. `0xFF` stays `0xFF`.
+
+use std::{iter::Peekable, vec};
+
+use crate::{
+    ast::{ClassDeclaration, Literal, Statement, StatementsBlock},
+    interner::PathId,
+    lexer::{tokenize_with_trivia, BYTE_ORDER_MARK},
+    location::{HasLocation, Location},
+    parser::{Error, Parser},
+    to_source::SourceWriter,
+    token::Token,
+};
+
+const INDENTATION: &str = "    ";
+
+/// Formats the whole program. Returns parsing errors if the source is
+/// not a valid program, as it can't be formatted then. A byte order mark
+/// at the start of the source is kept.
+pub fn format(path: impl Into<PathId>, source: &str) -> Result<String, Vec<Error>> {
+    let path = path.into();
+    let tokens = tokenize_with_trivia(path, source);
+    let comments = tokens
+        .iter()
+        .copied()
+        .filter(|token| token.raw().is_trivia())
+        .collect::<Vec<_>>();
+
+    let (program, errors) = Parser::new_from_tokens(path, tokens).parse_program();
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut printer = PrettyPrinter {
+        source,
+        comments: comments.into_iter().peekable(),
+        output: String::new(),
+        indentation: 0,
+    };
+
+    if source.starts_with(BYTE_ORDER_MARK) {
+        printer.output.push(BYTE_ORDER_MARK);
+    }
+
+    printer.print_statements(program.statements(), source.len());
+
+    Ok(printer.output)
+}
+
+struct PrettyPrinter<'s> {
+    source: &'s str,
+    /// Comments that are not printed yet, in order of appearance.
+    comments: Peekable<vec::IntoIter<Token>>,
+    output: String,
+    indentation: usize,
+}
+
+impl PrettyPrinter<'_> {
+    /// Prints statements one per line, together with comments located
+    /// before `end_offset`. Single empty lines between statements are kept.
+    fn print_statements(&mut self, statements: &[Statement], end_offset: usize) {
+        let mut previous_end = None;
+
+        for statement in statements {
+            let location = statement.location();
+
+            previous_end = self.print_comments_before(location.start().offset(), previous_end);
+            self.print_empty_line_between(previous_end, location.start().offset());
+
+            self.print_indentation();
+            self.write_statement(statement);
+            self.print_trailing_comments(location);
+            self.output.push('\n');
+
+            previous_end = Some(location.end().offset());
+        }
+
+        self.print_comments_before(end_offset, previous_end);
+    }
+
+    /// Prints comments starting before `offset` on their own lines. Returns
+    /// end offset of the last printed comment, or `previous_end` if there
+    /// are no such comments.
+    fn print_comments_before(
+        &mut self,
+        offset: usize,
+        mut previous_end: Option<usize>,
+    ) -> Option<usize> {
+        while let Some(comment) = self
+            .comments
+            .next_if(|comment| comment.location().start().offset() < offset)
+        {
+            let location = comment.location();
+
+            self.print_empty_line_between(previous_end, location.start().offset());
+            self.print_indentation();
+            self.output.push_str(comment.lexeme(self.source).trim_end());
+            self.output.push('\n');
+
+            previous_end = Some(location.end().offset());
+        }
+
+        previous_end
+    }
+
+    /// Prints comments starting inside of the statement, e.g. `f(a /* a */);`,
+    /// or on the line the statement ends on, e.g. `a; // comment`. Comments
+    /// following a line comment are moved to their own lines.
+    fn print_trailing_comments(&mut self, statement: Location) {
+        let mut after_line_comment = false;
+
+        while let Some(comment) = self.comments.next_if(|comment| {
+            let start = comment.location().start();
+
+            start.offset() < statement.end().offset() || start.line() == statement.end().line()
+        }) {
+            let lexeme = comment.lexeme(self.source).trim_end();
+
+            if after_line_comment {
+                self.output.push('\n');
+                self.print_indentation();
+            } else {
+                self.output.push(' ');
+            }
+
+            self.output.push_str(lexeme);
+            after_line_comment |= lexeme.starts_with("//");
+        }
+    }
+
+    fn print_empty_line_between(&mut self, previous_end: Option<usize>, next_start: usize) {
+        let Some(between) =
+            previous_end.and_then(|previous_end| self.source.get(previous_end..next_start))
+        else {
+            return;
+        };
+
+        if between.matches('\n').count() > 1 {
+            self.output.push('\n');
+        }
+    }
+
+    fn print_indentation(&mut self) {
+        for _ in 0..self.indentation {
+            self.output.push_str(INDENTATION);
+        }
+    }
+}
+
+impl SourceWriter for PrettyPrinter<'_> {
+    fn output(&mut self) -> &mut String {
+        &mut self.output
+    }
+
+    /// Copies the literal from the source as is.
+    fn write_literal(&mut self, literal: &Literal) {
+        let location = literal.location();
+
+        self.output
+            .push_str(&self.source[location.start().offset()..location.end().offset()]);
+    }
+
+    /// Prints class with fields first, followed by methods separated
+    /// by empty lines.
+    fn write_class(&mut self, class: &ClassDeclaration) {
+        self.output.push_str("class ");
+        self.write_identifier(class.name());
+        self.output.push_str(" {\n");
+        self.indentation += 1;
+
+        for field in class.fields() {
+            self.print_indentation();
+            self.write_identifier(*field);
+            self.output.push_str(";\n");
+        }
+
+        for (idx, method) in class.methods().iter().enumerate() {
+            if idx > 0 || !class.fields().is_empty() {
+                self.output.push('\n');
+            }
+
+            self.print_comments_before(method.location().start().offset(), None);
+            self.print_indentation();
+            self.write_function(method);
+            self.print_trailing_comments(method.location());
+            self.output.push('\n');
+        }
+
+        self.print_comments_before(class.location().end().offset(), None);
+        self.indentation -= 1;
+        self.print_indentation();
+        self.output.push('}');
+    }
+
+    fn write_block(&mut self, block: &StatementsBlock) {
+        let end_offset = block.location().end().offset();
+        let has_comments = self
+            .comments
+            .peek()
+            .is_some_and(|comment| comment.location().start().offset() < end_offset);
+
+        if block.is_empty() && !has_comments {
+            self.output.push_str("{}");
+            return;
+        }
+
+        self.output.push_str("{\n");
+        self.indentation += 1;
+        self.print_statements(block.statements(), end_offset);
+        self.indentation -= 1;
+        self.print_indentation();
+        self.output.push('}');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn format(source: &str) -> String {
+        super::format("test.lzr", source).unwrap()
+    }
+
+    #[test]
+    fn indentation_and_spacing() {
+        assert_eq!(
+            format("func  add(a,b){return a+b;}\nclass A{func b(){ if a {b;}else{c(1,2);} } x;}"),
+            "func add(a, b) {\n    return a + b;\n}\n\
+             class A {\n    x;\n\n    func b() {\n        if a {\n            b;\n        } \
+             else {\n            c(1, 2);\n        }\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn empty_lines_are_kept() {
+        assert_eq!(format("a;\n\n\n\nb;\nc;"), "a;\n\nb;\nc;\n");
+        assert_eq!(format("while a {}\n"), "while a {}\n");
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            format("// leading\na;   // trailing\nwhile a { b; /* inner */ }\n// last"),
+            "// leading\na; // trailing\nwhile a {\n    b; /* inner */\n}\n// last\n"
+        );
+    }
+
+    #[test]
+    fn comments_inside_statements() {
+        assert_eq!(format("f(a /* c */);"), "f(a); /* c */\n");
+        assert_eq!(format("x = 1 + // one\n 2;"), "x = 1 + 2; // one\n");
+        assert_eq!(format("f(a, /* c */ b);\nc;"), "f(a, b); /* c */\nc;\n");
+        assert_eq!(
+            format("x = 1 // one\n + /* two */ 2; // three"),
+            "x = 1 + 2; // one\n/* two */\n// three\n"
+        );
+
+        for source in [
+            "f(a); /* c */\n",
+            "x = 1 + 2; // one\n",
+            "x = 1 + 2; // one\n/* two */\n// three\n",
+        ] {
+            assert_eq!(format(source), source);
+        }
+    }
+
+    #[test]
+    fn literals_and_identifiers() {
+        assert_eq!(
+            format("a = f(0xFF, 1_000, \"\\n\") + `b`.`if`;"),
+            "a = f(0xFF, 1_000, \"\\n\") + b.`if`;\n"
+        );
+    }
+
+    #[test]
+    fn type_annotations() {
+        assert_eq!(
+            format("func f(a:int,b)->bool{}"),
+            "func f(a: int, b) -> bool {}\n"
+        );
+    }
+
+    #[test]
+    fn print_statements() {
+        assert_eq!(format("println  1 ,2;print;"), "println 1, 2;\nprint;\n");
+    }
+
+    #[test]
+    fn parentheses_preserve_meaning() {
+        assert_eq!(format("(a + b) * c;"), "(a + b) * c;\n");
+        assert_eq!(format("a * (b + c);"), "a * (b + c);\n");
+        assert_eq!(format("a - (b - c);"), "a - (b - c);\n");
+        assert_eq!(format("1 + (2 + 3);"), "1 + (2 + 3);\n");
+        assert_eq!(format("(2 ** 3) ** 2;"), "(2 ** 3) ** 2;\n");
+        assert_eq!(format("(a ?? b) ?? c;"), "(a ?? b) ?? c;\n");
+        assert_eq!(format("-(a + b);"), "-(a + b);\n");
+        assert_eq!(format("-(-a);"), "-(-a);\n");
+        assert_eq!(format("(a + b).c;"), "(a + b).c;\n");
+        assert_eq!(format("(a < b) < c;"), "(a < b) < c;\n");
+    }
+
+    #[test]
+    fn redundant_parentheses_are_removed() {
+        assert_eq!(format("((a));"), "a;\n");
+        assert_eq!(format("(1 + 2) + 3;"), "1 + 2 + 3;\n");
+        assert_eq!(format("(a * b) + (c / d);"), "a * b + c / d;\n");
+        assert_eq!(format("2 ** (3 ** 2);"), "2 ** 3 ** 2;\n");
+        assert_eq!(format("(-a) ** 2;"), "-a ** 2;\n");
+        assert_eq!(format("(a == b) && (c < d);"), "a == b && c < d;\n");
+        assert_eq!(format("a = (b = c + (d));"), "a = b = c + d;\n");
+    }
+
+    #[test]
+    fn idempotent() {
+        let source = "func f(a) {\n    // comment\n    return a.b[1](2);\n}\n\nf(1);\n";
+
+        assert_eq!(format(source), source);
+        assert_eq!(format(&format(source)), source);
+    }
+
+    #[test]
+    fn byte_order_mark_is_kept() {
+        assert_eq!(format("\u{FEFF}a  =1;"), "\u{FEFF}a = 1;\n");
+        assert_eq!(format("\u{FEFF}a = 1;\n"), "\u{FEFF}a = 1;\n");
+        assert_eq!(format("a = 1;\n"), "a = 1;\n");
+    }
+
+    #[test]
+    fn invalid_program() {
+        assert!(super::format("test.lzr", "a +").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Reports code that can never run, because it follows a statement that
+//! always leaves the block, e.g. `b();` in `{ return a; b(); }`.
+
+use crate::{
+    ast::{Expression, FunctionDeclaration, RawStatement, Statement, StatementsBlock},
+    diagnostic::Diagnostic,
+    location::HasLocation,
+};
+
+/// Returns a warning for every block in the program with statements after
+/// a terminator (see [`crate::ast::Statement::is_terminator`]). The warning
+/// spans all the unreachable statements of the block.
+#[must_use]
+pub fn check_unreachable_code(program: &StatementsBlock) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_block(program, &mut diagnostics);
+    diagnostics
+}
+
+fn check_block(block: &StatementsBlock, diagnostics: &mut Vec<Diagnostic>) {
+    let statements = block.statements();
+
+    if let Some(terminator_idx) = statements.iter().position(Statement::is_terminator) {
+        let unreachable = &statements[terminator_idx + 1..];
+
+        if let (Some(first), Some(last)) = (unreachable.first(), unreachable.last()) {
+            diagnostics.push(Diagnostic::warning(
+                "unreachable code",
+                first.location().to(last.location()),
+            ));
+        }
+    }
+
+    for statement in statements {
+        match statement.raw() {
+            RawStatement::Expression(expression) => check_expression(expression, diagnostics),
+            RawStatement::Return(return_) => {
+                if let Some(expression) = return_.expression() {
+                    check_expression(expression, diagnostics);
+                }
+            }
+            RawStatement::Print(print) => {
+                for argument in print.arguments() {
+                    check_expression(argument, diagnostics);
+                }
+            }
+            RawStatement::Function(function) => check_function(function, diagnostics),
+            RawStatement::Class(class) => {
+                for method in class.methods() {
+                    check_function(method, diagnostics);
+                }
+            }
+        }
+    }
+}
+
+fn check_function(function: &FunctionDeclaration, diagnostics: &mut Vec<Diagnostic>) {
+    check_block(function.body(), diagnostics);
+}
+
+fn check_expression(expression: &Expression, diagnostics: &mut Vec<Diagnostic>) {
+    match expression {
+        Expression::Literal(_) | Expression::Identifier(_) | Expression::Break(_) => {}
+        Expression::Unary(unary) => check_expression(unary.operand(), diagnostics),
+        Expression::Binary(binary) => {
+            check_expression(binary.left(), diagnostics);
+            check_expression(binary.right(), diagnostics);
+        }
+        Expression::Call(call) => {
+            check_expression(call.callee(), diagnostics);
+
+            for argument in call.arguments() {
+                check_expression(argument, diagnostics);
+            }
+        }
+        Expression::New(new) => {
+            for argument in new.arguments() {
+                check_expression(argument, diagnostics);
+            }
+        }
+        Expression::Member(member) => check_expression(member.object(), diagnostics),
+        Expression::Index(index) => {
+            check_expression(index.object(), diagnostics);
+            check_expression(index.index(), diagnostics);
+        }
+        Expression::Assignment(assignment) => {
+            check_expression(assignment.target(), diagnostics);
+            check_expression(assignment.value(), diagnostics);
+        }
+        Expression::Throw(throw) => check_expression(throw.value(), diagnostics),
+        Expression::Block(block) => check_block(block, diagnostics),
+        Expression::While(while_) => {
+            check_expression(while_.condition(), diagnostics);
+            check_expression(while_.body(), diagnostics);
+        }
+        Expression::If(if_) => {
+            check_expression(if_.condition(), diagnostics);
+            check_expression(if_.then_branch(), diagnostics);
+
+            if let Some(else_branch) = if_.else_branch() {
+                check_expression(else_branch, diagnostics);
+            }
+        }
+        Expression::Map(map) => {
+            for (key, value) in map.entries() {
+                check_expression(key, diagnostics);
+                check_expression(value, diagnostics);
+            }
+        }
+        Expression::Array(array) => {
+            for element in array {
+                check_expression(element, diagnostics);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diagnostic::Severity, parser::parse};
+
+    fn check(source: &str) -> Vec<Diagnostic> {
+        check_unreachable_code(&parse("test.lzr", source).unwrap())
+    }
+
+    #[test]
+    fn code_after_return() {
+        let diagnostics = check("func f() { return; a(); b(); }");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity(), Severity::Warning);
+        assert_eq!(diagnostics[0].message(), "unreachable code");
+        assert_eq!(diagnostics[0].location().start().offset(), 19);
+        assert_eq!(diagnostics[0].location().end().offset(), 27);
+
+        assert_eq!(check("func f() { return; b; }").len(), 1);
+        assert_eq!(check("func f() { return 1; b; }").len(), 1);
+        assert_eq!(check("while a { throw b; c; }").len(), 1);
+        assert_eq!(check("class A { func f() { return 1; 2; } }").len(), 1);
+        assert_eq!(
+            crate::parser::check("test.lzr", "return 1; 2;")[0].message(),
+            "unreachable code"
+        );
+    }
+
+    #[test]
+    fn code_in_sibling_block_is_reachable() {
+        assert!(check("if a { return; } b();").is_empty());
+        assert!(check("func f() { { return; } b; }").is_empty());
+        assert!(check("func f() { if a { return 1; } else { return 2; } }").is_empty());
+        assert!(check("func f() { a(); return 1; }").is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+//! Renders [`Diagnostic`]s together with the source line they point at:
+//!
+//! ```text
+//! error: expected `)`, found `foo`
+//!  --> main.lzr:1:11
+//!   |
+//! 1 | return (1 foo)
+//!   |           ^^^
+//! ```
+
+use std::fmt::{self, Display};
+
+use crate::{
+    diagnostic::{Diagnostic, Severity},
+    lexer::BYTE_ORDER_MARK,
+    location::HasLocation,
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+const BOLD_GREEN: &str = "\x1b[1;32m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const BOLD_YELLOW: &str = "\x1b[1;33m";
+
+/// Diagnostic paired with the source text it was reported for.
+#[derive(Copy, Clone, Debug)]
+pub struct Snippet<'a> {
+    diagnostic: &'a Diagnostic,
+    source: &'a str,
+    no_color: bool,
+}
+
+impl<'a> Snippet<'a> {
+    #[inline]
+    #[must_use]
+    pub const fn new(diagnostic: &'a Diagnostic, source: &'a str) -> Self {
+        Self {
+            diagnostic,
+            source,
+            no_color: false,
+        }
+    }
+
+    /// Disables ANSI color codes. The crate never probes the terminal itself,
+    /// so callers should set this when the output is not a terminal or the
+    /// user asked for plain output.
+    #[inline]
+    #[must_use]
+    pub const fn with_no_color(mut self, no_color: bool) -> Self {
+        self.no_color = no_color;
+        self
+    }
+
+    /// Returns `code` unless colors are disabled.
+    const fn style(&self, code: &'static str) -> &'static str {
+        if self.no_color {
+            ""
+        } else {
+            code
+        }
+    }
+
+    /// Clamps `offset` to the source and moves it back to a character
+    /// boundary, so that a location that doesn't match the source, e.g.
+    /// a stale one, can't make slicing panic. Offsets inside of the byte
+    /// order mark are moved after it.
+    fn clamp_offset(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        offset.max(self.text_start())
+    }
+
+    /// Returns offset of the text after the byte order mark, which is
+    /// skipped by the lexer and isn't displayed, or 0 if there is none.
+    fn text_start(&self) -> usize {
+        if self.source.starts_with(BYTE_ORDER_MARK) {
+            BYTE_ORDER_MARK.len_utf8()
+        } else {
+            0
+        }
+    }
+
+    /// Returns the number of characters between the offsets, or 0 if they
+    /// don't form a valid range.
+    fn chars_between(&self, start: usize, end: usize) -> usize {
+        self.source
+            .get(start..end)
+            .map_or(0, |text| text.chars().count())
+    }
+
+    /// Returns the line containing `offset`, which must be clamped, without
+    /// the line break, and the offset the line starts at.
+    fn line_at(&self, offset: usize) -> (&'a str, usize) {
+        let line_start = self.source[..offset]
+            .rfind(['\n', '\r'])
+            .map_or(self.text_start(), |idx| idx + 1);
+        let line_end = self.source[line_start..]
+            .find(['\n', '\r'])
+            .map_or(self.source.len(), |idx| line_start + idx);
+
+        (&self.source[line_start..line_end], line_start)
+    }
+}
+
+impl Display for Snippet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = self.diagnostic.location();
+        let start = location.start();
+        let start_offset = self.clamp_offset(start.offset());
+        let (line, line_start) = self.line_at(start_offset);
+
+        let severity_color = match self.diagnostic.severity() {
+            Severity::Error => BOLD_RED,
+            Severity::Warning => BOLD_YELLOW,
+            Severity::Note => BOLD_GREEN,
+        };
+        let (severity, gutter, bold, reset) = (
+            self.style(severity_color),
+            self.style(BOLD_BLUE),
+            self.style(BOLD),
+            self.style(RESET),
+        );
+
+        let line_number = start.line().to_string();
+        let padding = " ".repeat(line_number.len());
+
+        // Carets cover the span on its first line, at least one character.
+        let caret_start = self.chars_between(line_start, start_offset);
+        let span_end = self
+            .clamp_offset(location.end().offset())
+            .clamp(start_offset, line_start + line.len());
+        let caret_count = self.chars_between(start_offset, span_end).max(1);
+
+        writeln!(
+            f,
+            "{severity}{}{reset}{bold}: {}{reset}",
+            self.diagnostic.severity(),
+            self.diagnostic.message()
+        )?;
+        writeln!(f, "{padding}{gutter}-->{reset} {location}")?;
+        writeln!(f, "{padding} {gutter}|{reset}")?;
+        writeln!(f, "{gutter}{line_number} |{reset} {line}")?;
+        write!(
+            f,
+            "{padding} {gutter}|{reset} {}{severity}{}{reset}",
+            " ".repeat(caret_start),
+            "^".repeat(caret_count)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interner::PathId,
+        location::{CharLocation, Location},
+        parser::Parser,
+    };
+
+    fn unexpected_token(source: &str) -> Diagnostic {
+        let error = Parser::new(PathId::from("main.lzr"), source)
+            .parse_statement()
+            .unwrap_err();
+
+        Diagnostic::error(error.render(source), error.location())
+    }
+
+    #[test]
+    fn plain() {
+        let diagnostic = unexpected_token("return (1 foo)");
+        let snippet = Snippet::new(&diagnostic, "return (1 foo)").with_no_color(true);
+
+        assert_eq!(
+            snippet.to_string(),
+            "error: expected `)`, found `foo`\n \
+             --> main.lzr:1:11\n  \
+             |\n\
+             1 | return (1 foo)\n  \
+             |           ^^^"
+        );
+        assert!(!snippet.to_string().contains('\x1b'));
+    }
+
+    #[test]
+    fn line_in_the_middle() {
+        let source = "a;\nreturn (1 foo)\nb;";
+        let error = Parser::new(PathId::from("main.lzr"), source)
+            .parse_program()
+            .1[0];
+        let diagnostic = Diagnostic::error(error.render(source), error.location());
+
+        assert!(Snippet::new(&diagnostic, source)
+            .with_no_color(true)
+            .to_string()
+            .ends_with("2 | return (1 foo)\n  |           ^^^"));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let source = "\u{FEFF}return (1 foo)\n";
+        let (_, errors) = Parser::new(PathId::from("main.lzr"), source).parse_program();
+        let diagnostic = errors[0].to_diagnostic(source);
+
+        assert!(Snippet::new(&diagnostic, source)
+            .with_no_color(true)
+            .to_string()
+            .ends_with("1 | return (1 foo)\n  |           ^^^"));
+    }
+
+    #[test]
+    fn location_not_matching_source() {
+        let path = PathId::from("main.lzr");
+        let diagnostic = |start, end| {
+            Diagnostic::error(
+                "stale",
+                Location::new(
+                    path,
+                    CharLocation::new(1, start, start),
+                    CharLocation::new(1, end, end),
+                ),
+            )
+        };
+        let render = |diagnostic: &Diagnostic, source| {
+            Snippet::new(diagnostic, source)
+                .with_no_color(true)
+                .to_string()
+        };
+
+        assert!(render(&diagnostic(10, 20), "a;").ends_with("1 | a;\n  |   ^"));
+        assert!(render(&diagnostic(1, 2), "\u{e9}b").ends_with("1 | \u{e9}b\n  | ^"));
+        assert!(render(&diagnostic(3, 1), "\u{e9}b").ends_with("1 | \u{e9}b\n  |   ^"));
+    }
+
+    #[test]
+    fn colored() {
+        let diagnostic = unexpected_token("return (1 foo)");
+        let snippet = Snippet::new(&diagnostic, "return (1 foo)").to_string();
+
+        assert!(snippet.contains("\x1b[1;31m^^^\x1b[0m"));
+        assert!(snippet.contains("\x1b[1;34m1 |\x1b[0m"));
+    }
+}
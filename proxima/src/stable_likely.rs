@@ -1,22 +1,62 @@
-/// Brings [likely](core::intrinsics::likely) to stable Rust.
-#[inline]
-#[allow(dead_code)]
+//! Branch prediction hints on stable Rust.
+//!
+//! Both functions return their argument unchanged: they don't affect the
+//! program's behavior and only tell the optimizer which branch is cold.
+//!
+//! When the toolchain provides [`core::hint::cold_path`] (Rust 1.95+), it is
+//! used directly. Older toolchains fall back to calling an empty `#[cold]`
+//! function, which gives the optimizer the same information.
+
+#[cfg(has_cold_path)]
+use core::hint::cold_path;
+
+#[cfg(not(has_cold_path))]
+use fallback::cold_path;
+
+/// Hints that `b` is most likely `true`. Stable counterpart of
+/// [likely](core::intrinsics::likely).
+#[inline(always)]
 pub(crate) const fn likely(b: bool) -> bool {
-    #[allow(clippy::needless_bool)]
-    if (1i32).checked_div(if b { 1 } else { 0 }).is_some() {
-        true
-    } else {
-        false
+    if !b {
+        cold_path();
     }
+
+    b
 }
 
-/// Brings [unlikely](core::intrinsics::unlikely) to stable Rust.
-#[inline]
+/// Hints that `b` is most likely `false`. Stable counterpart of
+/// [unlikely](core::intrinsics::unlikely).
+#[inline(always)]
 pub(crate) const fn unlikely(b: bool) -> bool {
-    #[allow(clippy::needless_bool)]
-    if (1i32).checked_div(if b { 0 } else { 1 }).is_none() {
-        true
-    } else {
-        false
+    if b {
+        cold_path();
+    }
+
+    b
+}
+
+#[cfg(not(has_cold_path))]
+mod fallback {
+    /// Never inlined: the call to a `#[cold]` function is what marks the
+    /// branch as cold, so inlining the empty body would drop the hint.
+    #[cold]
+    #[inline(never)]
+    pub(super) const fn cold_path() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn likely_is_identity() {
+        assert!(likely(true));
+        assert!(!likely(false));
+    }
+
+    #[test]
+    fn unlikely_is_identity() {
+        assert!(unlikely(true));
+        assert!(!unlikely(false));
     }
 }
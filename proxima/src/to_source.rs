@@ -0,0 +1,548 @@
+//! Compact single-line source of syntax tree nodes, e.g. for quoting
+//! expressions in diagnostics: ``did you mean `a + b`?``.
+//!
+//! Unlike [`crate::pretty_printer`], the source text of the program isn't
+//! needed: literals are written from their values and comments are dropped.
+//! Literals without syntax of their own, e.g. booleans produced by constant
+//! folding, are written in their [`Display`](std::fmt::Display) form.
+
+use crate::{
+    ast::{
+        ClassDeclaration, Expression, FunctionDeclaration, Literal, RawStatement, Statement,
+        StatementsBlock,
+    },
+    interner::IdentifierId,
+    token::{Keyword, Punctuator},
+    value::Value,
+};
+
+impl Expression {
+    /// Returns source of the expression with single spaces between tokens
+    /// and only the parentheses needed, which parses back into the same
+    /// tree, e.g. `a + b * c`.
+    #[must_use]
+    pub fn to_source(&self) -> String {
+        let mut writer = CompactWriter::default();
+        writer.write_expression(self);
+        writer.output
+    }
+}
+
+impl Statement {
+    /// Returns source of the statement on a single line, without comments,
+    /// e.g. `func f(a) { return a; }`. See [`Expression::to_source`].
+    #[must_use]
+    pub fn to_source(&self) -> String {
+        let mut writer = CompactWriter::default();
+        writer.write_statement(self);
+        writer.output
+    }
+}
+
+/// Writes source of syntax tree nodes, adding only the parentheses needed
+/// to parse it back into the same tree. Shared by [`Expression::to_source`]
+/// and [`crate::pretty_printer`], which differ in how literals, blocks and
+/// classes are laid out.
+pub(crate) trait SourceWriter {
+    fn output(&mut self) -> &mut String;
+
+    fn write_literal(&mut self, literal: &Literal);
+
+    fn write_block(&mut self, block: &StatementsBlock);
+
+    fn write_class(&mut self, class: &ClassDeclaration);
+
+    fn write_statement(&mut self, statement: &Statement) {
+        match statement.raw() {
+            RawStatement::Expression(expression) => {
+                self.write_expression(expression);
+
+                if !expression.is_block_like() {
+                    self.output().push(';');
+                }
+            }
+            RawStatement::Return(return_) => {
+                self.output().push_str("return");
+
+                if let Some(expression) = return_.expression() {
+                    self.output().push(' ');
+                    self.write_expression(expression);
+                }
+
+                self.output().push(';');
+            }
+            RawStatement::Print(print) => {
+                self.output().push_str(if print.is_println() {
+                    "println"
+                } else {
+                    "print"
+                });
+
+                for (idx, argument) in print.arguments().iter().enumerate() {
+                    self.output().push_str(if idx > 0 { ", " } else { " " });
+                    self.write_expression(argument);
+                }
+
+                self.output().push(';');
+            }
+            RawStatement::Function(function) => self.write_function(function),
+            RawStatement::Class(class) => self.write_class(class),
+        }
+    }
+
+    fn write_function(&mut self, function: &FunctionDeclaration) {
+        self.output().push_str("func ");
+        self.write_identifier(function.name());
+        self.output().push('(');
+
+        for (idx, parameter) in function.parameters().iter().enumerate() {
+            if idx > 0 {
+                self.output().push_str(", ");
+            }
+
+            self.write_identifier(*parameter);
+
+            if let Some(type_) = function.parameter_type(idx) {
+                self.output().push_str(": ");
+                self.write_identifier(type_.name());
+            }
+        }
+
+        self.output().push_str(") ");
+
+        if let Some(type_) = function.return_type() {
+            self.output().push_str("-> ");
+            self.write_identifier(type_.name());
+            self.output().push(' ');
+        }
+
+        self.write_block(function.body());
+    }
+
+    fn write_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Literal(literal) => self.write_literal(literal),
+            Expression::Identifier(identifier) => self.write_identifier(identifier.name()),
+            Expression::Unary(unary) => {
+                self.output().push_str(&unary.operator().to_string());
+                // `-(-a)` must not become `--a`, which is a decrement.
+                self.write_operand(
+                    unary.operand(),
+                    matches!(unary.operand(), Expression::Unary(_))
+                        || is_negative_literal(unary.operand())
+                        || needs_parentheses(unary.operand()),
+                );
+            }
+            Expression::Binary(binary) => {
+                let operator = binary.operator();
+
+                self.write_operand(
+                    binary.left(),
+                    binary_operand_needs_parentheses(binary.left(), operator, false),
+                );
+                self.output().push_str(&format!(" {operator} "));
+                self.write_operand(
+                    binary.right(),
+                    binary_operand_needs_parentheses(binary.right(), operator, true),
+                );
+            }
+            Expression::Call(call) => {
+                self.write_postfix_object(call.callee());
+                self.write_arguments(call.arguments());
+            }
+            Expression::New(new) => {
+                self.output().push_str("new ");
+                self.write_identifier(new.class());
+                self.write_arguments(new.arguments());
+            }
+            Expression::Member(member) => {
+                self.write_postfix_object(member.object());
+                self.output().push('.');
+                self.write_identifier(member.member());
+            }
+            Expression::Index(index) => {
+                self.write_postfix_object(index.object());
+                self.output().push('[');
+                self.write_expression(index.index());
+                self.output().push(']');
+            }
+            Expression::Assignment(assignment) => {
+                self.write_expression(assignment.target());
+                self.output()
+                    .push_str(&format!(" {} ", assignment.operator()));
+                self.write_expression(assignment.value());
+            }
+            Expression::Throw(throw) => {
+                self.output().push_str("throw ");
+                self.write_expression(throw.value());
+            }
+            Expression::Break(_) => self.output().push_str("break"),
+            Expression::Block(block) => self.write_block(block),
+            Expression::While(while_) => {
+                self.output().push_str("while ");
+                self.write_expression(while_.condition());
+                self.output().push(' ');
+                self.write_expression(while_.body());
+            }
+            Expression::If(if_) => {
+                self.output().push_str("if ");
+                self.write_expression(if_.condition());
+                self.output().push(' ');
+                self.write_expression(if_.then_branch());
+
+                if let Some(else_branch) = if_.else_branch() {
+                    self.output().push_str(" else ");
+                    self.write_expression(else_branch);
+                }
+            }
+            Expression::Map(map) => {
+                self.output().push('{');
+
+                for (idx, (key, value)) in map.entries().iter().enumerate() {
+                    if idx > 0 {
+                        self.output().push_str(", ");
+                    }
+
+                    self.write_expression(key);
+                    self.output().push_str(": ");
+                    self.write_expression(value);
+                }
+
+                self.output().push('}');
+            }
+            Expression::Array(array) => {
+                self.output().push('[');
+
+                for (idx, element) in array.iter().enumerate() {
+                    if idx > 0 {
+                        self.output().push_str(", ");
+                    }
+
+                    self.write_expression(element);
+                }
+
+                self.output().push(']');
+            }
+        }
+    }
+
+    fn write_operand(&mut self, operand: &Expression, parenthesized: bool) {
+        if parenthesized {
+            self.output().push('(');
+            self.write_expression(operand);
+            self.output().push(')');
+        } else {
+            self.write_expression(operand);
+        }
+    }
+
+    /// Writes callee of a call or object of member access or index
+    /// expression, e.g. `a` in `a.b`.
+    fn write_postfix_object(&mut self, object: &Expression) {
+        let parenthesized = is_negative_literal(object)
+            || !matches!(
+                object,
+                Expression::Literal(_)
+                    | Expression::Identifier(_)
+                    | Expression::Call(_)
+                    | Expression::New(_)
+                    | Expression::Member(_)
+                    | Expression::Index(_)
+            );
+
+        self.write_operand(object, parenthesized);
+    }
+
+    fn write_arguments(&mut self, arguments: &[Expression]) {
+        self.output().push('(');
+
+        for (idx, argument) in arguments.iter().enumerate() {
+            if idx > 0 {
+                self.output().push_str(", ");
+            }
+
+            self.write_expression(argument);
+        }
+
+        self.output().push(')');
+    }
+
+    /// Writes identifier, wrapping it in backticks if it is a keyword.
+    fn write_identifier(&mut self, identifier: IdentifierId) {
+        let output = self.output();
+
+        identifier.with_resolved(|name| {
+            if Keyword::from(name).is_some() {
+                output.push('`');
+                output.push_str(name);
+                output.push('`');
+            } else {
+                output.push_str(name);
+            }
+        });
+    }
+}
+
+/// Writes everything on a single line, see [`Statement::to_source`].
+#[derive(Default)]
+struct CompactWriter {
+    output: String,
+}
+
+impl SourceWriter for CompactWriter {
+    fn output(&mut self) -> &mut String {
+        &mut self.output
+    }
+
+    fn write_literal(&mut self, literal: &Literal) {
+        write_value(&mut self.output, literal.value());
+    }
+
+    fn write_block(&mut self, block: &StatementsBlock) {
+        if block.is_empty() {
+            self.output.push_str("{}");
+            return;
+        }
+
+        self.output.push('{');
+
+        for statement in block {
+            self.output.push(' ');
+            self.write_statement(statement);
+        }
+
+        self.output.push_str(" }");
+    }
+
+    fn write_class(&mut self, class: &ClassDeclaration) {
+        self.output.push_str("class ");
+        self.write_identifier(class.name());
+        self.output.push_str(" {");
+
+        for field in class.fields() {
+            self.output.push(' ');
+            self.write_identifier(*field);
+            self.output.push(';');
+        }
+
+        for method in class.methods() {
+            self.output.push(' ');
+            self.write_function(method);
+        }
+
+        self.output.push_str(" }");
+    }
+}
+
+/// Writes value as a literal. Floats always get a fractional part or an
+/// exponent, so that they aren't scanned back as integers. Numbers without
+/// a literal of their own are written as expressions evaluating to them,
+/// e.g. infinity as `1e999`, which is too large to be finite.
+pub(crate) fn write_value(output: &mut String, value: &Value) {
+    match value {
+        // `-9223372036854775808` would be negation of a float, as the
+        // integer literal doesn't fit into `i64`.
+        Value::Int(i64::MIN) => output.push_str("(-9223372036854775807 - 1)"),
+        Value::Float(number) if number.is_nan() => output.push_str("(0.0 / 0.0)"),
+        Value::Float(number) if number.is_infinite() => {
+            output.push_str(if *number > 0.0 { "1e999" } else { "-1e999" });
+        }
+        Value::Float(number) => output.push_str(&format!("{number:?}")),
+        Value::String(string) => {
+            output.push('"');
+
+            for c in string.chars() {
+                match c {
+                    '"' => output.push_str("\\\""),
+                    '\\' => output.push_str("\\\\"),
+                    '\n' => output.push_str("\\n"),
+                    '\r' => output.push_str("\\r"),
+                    '\t' => output.push_str("\\t"),
+                    '\0' => output.push_str("\\0"),
+                    c if c.is_control() => output.push_str(&format!("\\u{{{:X}}}", c as u32)),
+                    c => output.push(c),
+                }
+            }
+
+            output.push('"');
+        }
+        _ => output.push_str(&value.to_string()),
+    }
+}
+
+/// Returns `true` for literals folded from negated numbers, e.g. `-5`,
+/// which must be parenthesized where a unary expression would be. Numbers
+/// written parenthesized by [`write_value`] are not included.
+fn is_negative_literal(expression: &Expression) -> bool {
+    let Expression::Literal(literal) = expression else {
+        return false;
+    };
+
+    match literal.value() {
+        Value::Int(number) => *number < 0 && *number != i64::MIN,
+        Value::Float(number) => number.is_sign_negative() && !number.is_nan(),
+        _ => false,
+    }
+}
+
+/// Returns `true` if the operand of binary operator must be wrapped in
+/// parentheses to be parsed back into the same tree, e.g. `a + b` in
+/// `(a + b) * c`, or `b - c` in `a - (b - c)`. Comparisons nested in
+/// comparisons are always parenthesized, as chaining them is reported.
+fn binary_operand_needs_parentheses(
+    operand: &Expression,
+    operator: Punctuator,
+    is_right: bool,
+) -> bool {
+    let Expression::Binary(operand) = operand else {
+        return needs_parentheses(operand);
+    };
+
+    let precedence = operator.binary_precedence();
+    let operand_precedence = operand.operator().binary_precedence();
+
+    operand_precedence < precedence
+        || (operand_precedence == precedence && is_right != operator.is_right_associative())
+        || (operator.is_relational() && operand.operator().is_relational())
+}
+
+/// Returns `true` if the expression must be wrapped in parentheses to be
+/// an operand of unary or binary operator.
+const fn needs_parentheses(operand: &Expression) -> bool {
+    matches!(
+        operand,
+        Expression::Binary(_)
+            | Expression::Assignment(_)
+            | Expression::Throw(_)
+            | Expression::Block(_)
+            | Expression::While(_)
+            | Expression::If(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Expression, Literal, UnaryExpression},
+        const_eval::fold_expression,
+        interner::PathId,
+        interpreter::Interpreter,
+        location::Location,
+        parser::Parser,
+        token::Punctuator,
+        value::Value,
+    };
+
+    fn parse_statement(source: &str) -> crate::ast::Statement {
+        Parser::new(PathId::from("test.lzr"), source)
+            .parse_statement()
+            .unwrap()
+    }
+
+    fn eval(source: &str) -> Value {
+        let expression = Parser::new(PathId::from("test.lzr"), source)
+            .parse_expression()
+            .unwrap();
+
+        Interpreter::new().eval_expression(&expression).unwrap()
+    }
+
+    #[test]
+    fn compact_source() {
+        assert_eq!(
+            parse_statement("a  =b+ c*d ;").to_source(),
+            "a = b + c * d;"
+        );
+        assert_eq!(
+            parse_statement("func f( a ,b ) {\n  return a;\n}").to_source(),
+            "func f(a, b) { return a; }"
+        );
+        assert_eq!(
+            parse_statement("class A {\n x;\n func f() {}\n}").to_source(),
+            "class A { x; func f() {} }"
+        );
+        assert_eq!(
+            parse_statement("if a { b; } else { `while`(\"x\\ny\", 1.0, 0x10); }").to_source(),
+            "if a { b; } else { `while`(\"x\\ny\", 1.0, 16); }"
+        );
+    }
+
+    #[test]
+    fn folded_literals() {
+        let expression = Parser::new(PathId::from("test.lzr"), "-(1 + 4) * 0.5 + -(-2)")
+            .parse_expression()
+            .unwrap();
+
+        assert_eq!(fold_expression(&expression).to_source(), "-0.5");
+
+        let expression = Parser::new(PathId::from("test.lzr"), "-(2 - 7) - -a.b")
+            .parse_expression()
+            .unwrap();
+
+        assert_eq!(fold_expression(&expression).to_source(), "5 - -a.b");
+    }
+
+    #[test]
+    fn numbers_without_literals() {
+        let location = Location::of_first_byte(PathId::from("test.lzr"));
+        let literal = |value| Expression::Literal(Literal::new(value, location));
+
+        for value in [
+            Value::Int(i64::MIN),
+            Value::Int(i64::MAX),
+            Value::Float(f64::INFINITY),
+            Value::Float(f64::NEG_INFINITY),
+            Value::Float(-0.0),
+        ] {
+            let source = literal(value.clone()).to_source();
+            let operand = Expression::Unary(UnaryExpression::new(
+                Punctuator::Minus,
+                Box::new(literal(value.clone())),
+                location,
+            ))
+            .to_source();
+
+            assert_eq!(eval(&source), value, "{source}");
+
+            if let Some(negated) = value.unary_operation(Punctuator::Minus) {
+                assert_eq!(eval(&operand), negated, "{operand}");
+            }
+        }
+
+        let Value::Float(nan) = eval(&literal(Value::Float(f64::NAN)).to_source()) else {
+            panic!("expected float");
+        };
+
+        assert!(nan.is_nan());
+        assert_eq!(
+            literal(Value::Int(i64::MIN)).to_source(),
+            "(-9223372036854775807 - 1)"
+        );
+        assert_eq!(literal(Value::Float(f64::INFINITY)).to_source(), "1e999");
+    }
+
+    #[test]
+    fn round_trip() {
+        for source in [
+            "(a + b) * c - (d - e);",
+            "a = b += -(-c) ** 2 ** (1 + 1);",
+            "(a < b) < c;",
+            "f(a)(b).c[d + 1](new E(), \"\\\"\\\\\\t\");",
+            "while a { if b { throw c; } else if d { e; } }",
+            "println 1, 2.5, -a;",
+            "func f(`if`) { return `if`; }",
+            "func f() { if a { return; } }",
+            "func f(a: int, b) -> bool { return a; }",
+            "{\"a\": {}, b + 1: {c: d}};",
+            "[1, [a], [], {b: [c]}][0];",
+        ] {
+            let statement = parse_statement(source);
+            let text = statement.to_source();
+            let reparsed = parse_statement(&text);
+
+            assert_eq!(reparsed.to_source(), text, "{source}");
+            assert_eq!(reparsed.node_count(), statement.node_count(), "{source}");
+            assert_eq!(reparsed.depth(), statement.depth(), "{source}");
+        }
+    }
+}
@@ -1,12 +1,15 @@
-use crate::location::{HasLocation, Location};
+use crate::{
+    interner::{IdentifierId, StringId},
+    location::{HasLocation, Location},
+};
 use derive_more::Display;
 use paste::paste;
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display};
 
 macro_rules! keywords {
     ($($kw:ident),*) => {
         paste! {
-            #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+            #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
             pub enum Keyword {
                 $([<$kw:camel>]),*
             }
@@ -37,72 +40,185 @@ keywords! {
     using, switch, case, include, class, new
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Punctuator {
-    Arrow,              // ->
-    Eq,                 // =
-    DoubleEq,           // ==
-    Bang,               // !
-    BangEq,             // !=
-    LessEq,             // <=
-    Less,               // <
-    Greater,            // >
-    GreaterEq,          // >=
-    PlusEq,             // +=
-    MinusEq,            // -=
-    AsteriskEq,         // *=
-    SlashEq,            // /=
-    PercentEq,          // %=
-    AtEq,               // @=
-    AmpersandEq,        // &=
-    CaretEq,            // ^=
-    BarEq,              // |=
-    DoubleColonEq,      // ::=
-    LeftShiftEq,        // <<=
-    RightShiftEq,       // >>=
-    TripleRightShiftEq, // >>>=
-    DoublePlus,         // ++
-    DoubleMinus,        // --
-    LeftShift,          // <<
-    RightShift,         // >>
-    TripleGreater,      // >>>
-    DoubleDot,          // ..
-    DoubleAsterisk,     // **
-    QuestionColon,      // ?:
-    DoubleQuestion,     // ??
-    Tilde,              // ~
-    Caret,              // ^
-    DoubleCaret,        // ^^
-    Bar,                // |
-    DoubleBar,          // ||
-    Ampersand,          // &
-    DoubleAmpersand,    // &&
-    Question,           // ?
-    Semicolon,          // ;
-    Colon,              // :
-    DoubleColon,        // ::
-    OpenParent,         // (
-    CloseParent,        // )
-    OpenBracket,        // [
-    CloseBracket,       // ]
-    OpenBrace,          // {
-    CloseBrace,         // }
-    Comma,              // ,
-    Dot,                // .
-    Number,
-    HexNumber,
-    New,
-    Plus,     // +
-    Minus,    // -
-    Asterisk, // *
-    Slash,    // /
-    Percent,  // %
-    At,       // @
-    Eof,
+impl Keyword {
+    /// Returns `true` for keywords that are only meaningful inside a certain
+    /// construct, e.g. `case` inside `switch`. The lexer doesn't know the
+    /// context, so it always produces keywords for them, and the parser
+    /// accepts them as identifiers everywhere else, e.g. `case = 1;`.
+    #[inline]
+    #[must_use]
+    pub const fn is_contextual(self) -> bool {
+        matches!(self, Self::Case)
+    }
+}
+
+macro_rules! punctuators {
+    ($($name:ident => $spelling:literal),* $(,)?) => {
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum Punctuator {
+            $($name),*
+        }
+
+        impl Punctuator {
+            /// All punctuators, in declaration order.
+            pub const ALL: &'static [Punctuator] = &[$(Punctuator::$name),*];
+        }
+
+        impl Display for Punctuator {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Punctuator::$name => f.write_str($spelling)),*
+                }
+            }
+        }
+
+        impl TryFrom<&str> for Punctuator {
+            type Error = ();
+
+            /// Converts spelling of the punctuator, e.g. `->`, back into it.
+            fn try_from(s: &str) -> Result<Self, Self::Error> {
+                match s {
+                    $($spelling => Ok(Punctuator::$name),)*
+                    _ => Err(()),
+                }
+            }
+        }
+    };
+}
+
+punctuators! {
+    Arrow => "->",
+    Eq => "=",
+    DoubleEq => "==",
+    Bang => "!",
+    BangEq => "!=",
+    LessEq => "<=",
+    Less => "<",
+    Greater => ">",
+    GreaterEq => ">=",
+    PlusEq => "+=",
+    MinusEq => "-=",
+    AsteriskEq => "*=",
+    SlashEq => "/=",
+    PercentEq => "%=",
+    AtEq => "@=",
+    AmpersandEq => "&=",
+    CaretEq => "^=",
+    BarEq => "|=",
+    DoubleColonEq => "::=",
+    LeftShiftEq => "<<=",
+    RightShiftEq => ">>=",
+    TripleRightShiftEq => ">>>=",
+    DoublePlus => "++",
+    DoubleMinus => "--",
+    LeftShift => "<<",
+    RightShift => ">>",
+    TripleGreater => ">>>",
+    DoubleDot => "..",
+    DoubleAsterisk => "**",
+    QuestionColon => "?:",
+    DoubleQuestion => "??",
+    Tilde => "~",
+    Caret => "^",
+    DoubleCaret => "^^",
+    Bar => "|",
+    DoubleBar => "||",
+    Ampersand => "&",
+    DoubleAmpersand => "&&",
+    Question => "?",
+    Semicolon => ";",
+    Colon => ":",
+    DoubleColon => "::",
+    OpenParent => "(",
+    CloseParent => ")",
+    OpenBracket => "[",
+    CloseBracket => "]",
+    OpenBrace => "{",
+    CloseBrace => "}",
+    Comma => ",",
+    Dot => ".",
+    Plus => "+",
+    Minus => "-",
+    Asterisk => "*",
+    Slash => "/",
+    Percent => "%",
+    At => "@",
+}
+
+impl Punctuator {
+    /// Returns binding power of the punctuator used as a binary operator,
+    /// or `None` if it cannot be used as one. Higher binds tighter.
+    #[must_use]
+    pub const fn binary_precedence(&self) -> Option<u8> {
+        match self {
+            Self::DoubleQuestion => Some(1),
+            Self::DoubleBar => Some(2),
+            Self::DoubleAmpersand => Some(3),
+            Self::Bar => Some(4),
+            Self::Caret => Some(5),
+            Self::Ampersand => Some(6),
+            Self::DoubleEq | Self::BangEq => Some(7),
+            Self::Less | Self::LessEq | Self::Greater | Self::GreaterEq => Some(8),
+            Self::LeftShift | Self::RightShift | Self::TripleGreater => Some(9),
+            Self::Plus | Self::Minus => Some(10),
+            Self::Asterisk | Self::Slash | Self::Percent | Self::At => Some(11),
+            Self::DoubleAsterisk => Some(12),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the punctuator is a right-associative binary
+    /// operator, e.g. `2 ** 3 ** 2` is `2 ** (3 ** 2)`.
+    #[inline]
+    #[must_use]
+    pub const fn is_right_associative(&self) -> bool {
+        matches!(self, Self::DoubleAsterisk | Self::DoubleQuestion)
+    }
+
+    /// Returns `true` for `<`, `<=`, `>` and `>=`.
+    #[inline]
+    #[must_use]
+    pub const fn is_relational(&self) -> bool {
+        matches!(
+            self,
+            Self::Less | Self::LessEq | Self::Greater | Self::GreaterEq
+        )
+    }
+
+    /// Returns `true` for `=` and compound assignment operators, e.g. `+=`.
+    #[inline]
+    #[must_use]
+    pub const fn is_assignment_operator(&self) -> bool {
+        matches!(self, Self::Eq | Self::DoubleColonEq) || self.compound_base().is_some()
+    }
+
+    /// Returns binary operator the compound assignment operator applies,
+    /// e.g. `+` for `+=`. Returns `None` for other punctuators.
+    #[must_use]
+    pub const fn compound_base(&self) -> Option<Punctuator> {
+        match self {
+            Self::PlusEq => Some(Self::Plus),
+            Self::MinusEq => Some(Self::Minus),
+            Self::AsteriskEq => Some(Self::Asterisk),
+            Self::SlashEq => Some(Self::Slash),
+            Self::PercentEq => Some(Self::Percent),
+            Self::AmpersandEq => Some(Self::Ampersand),
+            Self::BarEq => Some(Self::Bar),
+            Self::CaretEq => Some(Self::Caret),
+            Self::LeftShiftEq => Some(Self::LeftShift),
+            Self::RightShiftEq => Some(Self::RightShift),
+            Self::TripleRightShiftEq => Some(Self::TripleGreater),
+            Self::AtEq => Some(Self::At),
+            _ => None,
+        }
+    }
 }
 
 /// Represents error that scanning process can fail with.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Display)]
+///
+/// Variants are kept in alphabetical order, so that sorting errors with
+/// the derived [`Ord`] is deterministic and reads naturally.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Display)]
 pub enum RawLexError {
     #[display(fmt = "digit doesn't correspond to base")]
     DigitDoesNotCorrespondToBase,
@@ -128,8 +244,6 @@ pub enum RawLexError {
     ExponentHasNoDigits,
     #[display(fmt = "exponent requires decimal mantissa")]
     ExponentRequiresDecimalMantissa,
-    #[display(fmt = "number contains no digits")]
-    NumberContainsNoDigits,
     #[display(fmt = "invalid byte escape sequence")]
     InvalidByteEscapeSequence,
     #[display(fmt = "invalid digit")]
@@ -140,14 +254,24 @@ pub enum RawLexError {
     InvalidUnicodeEscapeSequence,
     #[display(fmt = "more than one character in character literal")]
     MoreThanOneCharInCharLiteral,
+    #[display(fmt = "number contains no digits")]
+    NumberContainsNoDigits,
     #[display(fmt = "number cannot be parsed")]
     NumberParseError,
+    #[display(fmt = "token is too long")]
+    TokenTooLong,
+    #[display(fmt = "unclosed delimiter")]
+    UnclosedDelimiter,
     #[display(fmt = "underscore must separate successive digits")]
     UnderscoreMustSeparateSuccessiveDigits,
     #[display(fmt = "unexpected character")]
     UnexpectedChar,
+    #[display(fmt = "unexpected closing delimiter")]
+    UnexpectedClosingDelimiter,
     #[display(fmt = "unknown escape sequence")]
     UnknownEscapeSequence,
+    #[display(fmt = "unterminated block comment")]
+    UnterminatedBlockComment,
     #[display(fmt = "untermined character literal")]
     UnterminatedCharLiteral,
     #[display(fmt = "unterminated string literal")]
@@ -188,17 +312,141 @@ impl From<LexError> for Token {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Scanned token without its location.
+///
+/// Float literals are compared with `==` on floats, so `0.0 == -0.0` and
+/// `NaN` doesn't equal itself, which is why tokens have no [`Ord`]. Use
+/// [`RawToken::total_cmp`] to sort them.
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum RawToken {
     Punctuator(Punctuator),
     Keyword(Keyword),
     Error(RawLexError),
+    Identifier(IdentifierId),
+    /// Number literal without a radix point and an exponent, e.g. `42`
+    /// or `0xFF`.
+    Int(i64),
+    /// Number literal with a radix point or an exponent, e.g. `1.5`
+    /// or `1e3`.
+    Float(f64),
+    Text(StringId),
+    Comment,
+    /// End of the token stream. The lexer never produces it, see
+    /// [`Token::eof`].
+    EndOfFile,
+}
+
+impl RawToken {
+    const fn kind_index(&self) -> u8 {
+        match self {
+            Self::Punctuator(_) => 0,
+            Self::Keyword(_) => 1,
+            Self::Error(_) => 2,
+            Self::Identifier(_) => 3,
+            Self::Int(_) => 4,
+            Self::Float(_) => 5,
+            Self::Text(_) => 6,
+            Self::Comment => 7,
+            Self::EndOfFile => 8,
+        }
+    }
+}
+
+impl RawToken {
+    /// Orders tokens by kind in declaration order first, and then by their
+    /// payload, so that sets of tokens can be sorted deterministically.
+    /// Identifiers and strings are ordered by their text, and floats by
+    /// [`f64::total_cmp`], e.g. `-0.0` comes before `0.0`.
+    #[must_use]
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        self.kind_index()
+            .cmp(&other.kind_index())
+            .then_with(|| match (self, other) {
+                (Self::Punctuator(lhs), Self::Punctuator(rhs)) => lhs.cmp(rhs),
+                (Self::Keyword(lhs), Self::Keyword(rhs)) => lhs.cmp(rhs),
+                (Self::Error(lhs), Self::Error(rhs)) => lhs.cmp(rhs),
+                (Self::Identifier(lhs), Self::Identifier(rhs)) => {
+                    compare_text(*lhs, *rhs).then_with(|| lhs.cmp(rhs))
+                }
+                (Self::Int(lhs), Self::Int(rhs)) => lhs.cmp(rhs),
+                (Self::Float(lhs), Self::Float(rhs)) => lhs.total_cmp(rhs),
+                (Self::Text(lhs), Self::Text(rhs)) => {
+                    compare_text(*lhs, *rhs).then_with(|| lhs.cmp(rhs))
+                }
+                // Kinds without payload, or different kinds, which are
+                // ordered already. Variants are listed rather than matched
+                // with `_`, so that new ones get their payload compared.
+                (
+                    Self::Punctuator(_)
+                    | Self::Keyword(_)
+                    | Self::Error(_)
+                    | Self::Identifier(_)
+                    | Self::Int(_)
+                    | Self::Float(_)
+                    | Self::Text(_)
+                    | Self::Comment
+                    | Self::EndOfFile,
+                    _,
+                ) => Ordering::Equal,
+            })
+    }
+}
+
+/// Compares text of interned ids, with ids that were never interned first.
+fn compare_text<Id: Into<Option<String>>>(lhs: Id, rhs: Id) -> Ordering {
+    lhs.into().cmp(&rhs.into())
+}
+
+/// Coarse kind of a token, e.g. for syntax highlighting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TokenCategory {
+    Keyword,
+    /// Operator, e.g. `+` or `&&=`.
+    Operator,
+    /// Delimiter or separator, e.g. `(` or `;`.
+    Punctuation,
+    Literal,
     Identifier,
-    Number,
-    Text,
+    Comment,
+    Error,
     EndOfFile,
 }
 
+impl RawToken {
+    /// Returns category of the token used by syntax highlighters.
+    #[must_use]
+    pub const fn category(&self) -> TokenCategory {
+        match self {
+            Self::Keyword(_) => TokenCategory::Keyword,
+            Self::Punctuator(
+                Punctuator::OpenParent
+                | Punctuator::CloseParent
+                | Punctuator::OpenBracket
+                | Punctuator::CloseBracket
+                | Punctuator::OpenBrace
+                | Punctuator::CloseBrace
+                | Punctuator::Comma
+                | Punctuator::Semicolon
+                | Punctuator::Colon,
+            ) => TokenCategory::Punctuation,
+            Self::Punctuator(_) => TokenCategory::Operator,
+            Self::Int(_) | Self::Float(_) | Self::Text(_) => TokenCategory::Literal,
+            Self::Identifier(_) => TokenCategory::Identifier,
+            Self::Comment => TokenCategory::Comment,
+            Self::Error(_) => TokenCategory::Error,
+            Self::EndOfFile => TokenCategory::EndOfFile,
+        }
+    }
+
+    /// Returns `true` for tokens that don't affect the meaning of
+    /// the program, i.e. comments.
+    #[inline]
+    #[must_use]
+    pub const fn is_trivia(&self) -> bool {
+        matches!(self, RawToken::Comment)
+    }
+}
+
 impl From<Keyword> for RawToken {
     fn from(kw: Keyword) -> Self {
         RawToken::Keyword(kw)
@@ -217,7 +465,7 @@ impl From<RawLexError> for RawToken {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Token {
     raw: RawToken,
     location: Location,
@@ -234,12 +482,287 @@ impl Token {
     #[inline]
     #[must_use]
     pub fn new(raw: RawToken, location: Location) -> Self {
+        debug_assert!(
+            location.start().offset() <= location.end().offset(),
+            "token span is inverted: {location:?}"
+        );
+        debug_assert!(
+            location.start().line() <= location.end().line(),
+            "token span ends on an earlier line than it starts: {location:?}"
+        );
+
         Self { raw, location }
     }
 
+    /// Creates end of file token, which the parser sees past the last
+    /// token. `location` is usually location of the last token.
+    #[inline]
+    #[must_use]
+    pub fn eof(location: Location) -> Self {
+        Self::new(RawToken::EndOfFile, location)
+    }
+
     #[inline]
     #[must_use]
     pub const fn raw(&self) -> RawToken {
         self.raw
     }
+
+    /// Returns length of the token's lexeme in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.location.end().offset() - self.location.start().offset()
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns text of the token in the source it was scanned from.
+    /// Empty string is returned if the token doesn't belong to `source`.
+    #[inline]
+    #[must_use]
+    pub fn lexeme<'s>(&self, source: &'s str) -> &'s str {
+        source
+            .get(self.location.start().offset()..self.location.end().offset())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_keyword(&self, kw: Keyword) -> bool {
+        self.raw == RawToken::Keyword(kw)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_punctuator(&self, p: Punctuator) -> bool {
+        self.raw == RawToken::Punctuator(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interner::PathId,
+        lexer::{tokenize_with_trivia, Lexer},
+        location::CharLocation,
+    };
+
+    #[test]
+    fn keyword_token() {
+        let token = Token::new(
+            Keyword::If.into(),
+            Location::of_first_byte(PathId::from("test.lzr")),
+        );
+
+        assert!(token.is_keyword(Keyword::If));
+        assert!(!token.is_keyword(Keyword::While));
+        assert!(!token.is_punctuator(Punctuator::Eq));
+    }
+
+    #[test]
+    fn punctuator_token() {
+        let token = Token::new(
+            Punctuator::Eq.into(),
+            Location::of_first_byte(PathId::from("test.lzr")),
+        );
+
+        assert!(token.is_punctuator(Punctuator::Eq));
+        assert!(!token.is_punctuator(Punctuator::DoubleEq));
+        assert!(!token.is_keyword(Keyword::If));
+    }
+
+    #[test]
+    fn identifier_token() {
+        let token = Token::new(
+            RawToken::Identifier(IdentifierId::from("if")),
+            Location::of_first_byte(PathId::from("test.lzr")),
+        );
+
+        assert!(!token.is_keyword(Keyword::If));
+        assert!(!token.is_punctuator(Punctuator::Eq));
+    }
+
+    #[test]
+    fn token_length() {
+        let tokens = Lexer::new(PathId::from("test.lzr"), "<= имя").collect::<Vec<_>>();
+
+        assert_eq!(tokens[0].len(), 2);
+        assert_eq!(tokens[1].len(), "имя".len());
+        assert!(!tokens[1].is_empty());
+    }
+
+    #[test]
+    fn punctuator_spelling_round_trip() {
+        for punctuator in [
+            Punctuator::Plus,
+            Punctuator::Arrow,
+            Punctuator::DoubleColonEq,
+            Punctuator::TripleRightShiftEq,
+            Punctuator::OpenBrace,
+            Punctuator::QuestionColon,
+        ] {
+            assert_eq!(
+                Punctuator::try_from(punctuator.to_string().as_str()),
+                Ok(punctuator)
+            );
+        }
+
+        assert_eq!(Punctuator::try_from("+"), Ok(Punctuator::Plus));
+        assert_eq!(Punctuator::try_from("->"), Ok(Punctuator::Arrow));
+        assert_eq!(Punctuator::try_from("<>"), Err(()));
+        assert_eq!(Punctuator::try_from("Number"), Err(()));
+    }
+
+    #[test]
+    fn assignment_operators() {
+        assert!(Punctuator::Eq.is_assignment_operator());
+        assert!(Punctuator::DoubleColonEq.is_assignment_operator());
+        assert!(Punctuator::PlusEq.is_assignment_operator());
+        assert!(!Punctuator::DoubleEq.is_assignment_operator());
+        assert!(!Punctuator::Plus.is_assignment_operator());
+
+        assert_eq!(Punctuator::PlusEq.compound_base(), Some(Punctuator::Plus));
+        assert_eq!(
+            Punctuator::TripleRightShiftEq.compound_base(),
+            Some(Punctuator::TripleGreater)
+        );
+        assert_eq!(Punctuator::Eq.compound_base(), None);
+        assert_eq!(Punctuator::DoubleColonEq.compound_base(), None);
+    }
+
+    #[test]
+    fn lex_errors_sort_deterministically() {
+        let mut errors = Lexer::new(PathId::from("test.lzr"), "1__0 0x # \"a")
+            .filter_map(|token| match token.raw() {
+                RawToken::Error(error) => Some(error),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let mut reversed = errors.iter().rev().copied().collect::<Vec<_>>();
+
+        errors.sort();
+        reversed.sort();
+
+        assert_eq!(errors, reversed);
+        assert_eq!(
+            errors,
+            [
+                RawLexError::NumberContainsNoDigits,
+                RawLexError::UnderscoreMustSeparateSuccessiveDigits,
+                RawLexError::UnexpectedChar,
+                RawLexError::UnterminatedStringLiteral,
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "token span is inverted")]
+    fn inverted_span() {
+        let _ = Token::new(
+            RawToken::EndOfFile,
+            Location::new(
+                PathId::from("test.lzr"),
+                CharLocation::new(1, 5, 5),
+                CharLocation::new(1, 2, 2),
+            ),
+        );
+    }
+
+    #[test]
+    fn categories() {
+        let categories =
+            tokenize_with_trivia("test.lzr", "func f(a) { return a + \"b\" ** 1; } // c\n$")
+                .iter()
+                .map(|token| token.raw().category())
+                .collect::<Vec<_>>();
+
+        assert_eq!(
+            categories,
+            vec![
+                TokenCategory::Keyword,
+                TokenCategory::Identifier,
+                TokenCategory::Punctuation,
+                TokenCategory::Identifier,
+                TokenCategory::Punctuation,
+                TokenCategory::Punctuation,
+                TokenCategory::Keyword,
+                TokenCategory::Identifier,
+                TokenCategory::Operator,
+                TokenCategory::Literal,
+                TokenCategory::Operator,
+                TokenCategory::Literal,
+                TokenCategory::Punctuation,
+                TokenCategory::Punctuation,
+                TokenCategory::Comment,
+                TokenCategory::Error,
+            ]
+        );
+        assert_eq!(RawToken::EndOfFile.category(), TokenCategory::EndOfFile);
+    }
+
+    #[test]
+    fn sort_raw_tokens() {
+        // Interned in reverse order, to check that they are sorted by text.
+        let b = IdentifierId::from("b");
+        let a = IdentifierId::from("a");
+        let mut tokens = vec![
+            RawToken::EndOfFile,
+            RawToken::Float(2.5),
+            RawToken::Identifier(b),
+            RawToken::Int(2),
+            RawToken::Keyword(Keyword::While),
+            RawToken::Float(-1.0),
+            RawToken::Punctuator(Punctuator::Plus),
+            RawToken::Identifier(a),
+            RawToken::Int(-3),
+            RawToken::Punctuator(Punctuator::Arrow),
+            RawToken::Comment,
+            RawToken::Keyword(Keyword::If),
+            RawToken::Error(RawLexError::InvalidDigit),
+            RawToken::Text(StringId::from("y")),
+            RawToken::Text(StringId::from("x")),
+        ];
+        tokens.sort_by(RawToken::total_cmp);
+
+        assert_eq!(
+            tokens,
+            vec![
+                RawToken::Punctuator(Punctuator::Arrow),
+                RawToken::Punctuator(Punctuator::Plus),
+                RawToken::Keyword(Keyword::If),
+                RawToken::Keyword(Keyword::While),
+                RawToken::Error(RawLexError::InvalidDigit),
+                RawToken::Identifier(a),
+                RawToken::Identifier(b),
+                RawToken::Int(-3),
+                RawToken::Int(2),
+                RawToken::Float(-1.0),
+                RawToken::Float(2.5),
+                RawToken::Text(StringId::from("x")),
+                RawToken::Text(StringId::from("y")),
+                RawToken::Comment,
+                RawToken::EndOfFile,
+            ]
+        );
+    }
+
+    #[test]
+    fn float_tokens_equality() {
+        let nan = RawToken::Float(f64::NAN);
+
+        assert_eq!(RawToken::Float(0.0), RawToken::Float(-0.0));
+        assert_ne!(nan, nan);
+        assert_eq!(nan.total_cmp(&nan), Ordering::Equal);
+        assert_eq!(
+            RawToken::Float(-0.0).total_cmp(&RawToken::Float(0.0)),
+            Ordering::Less
+        );
+    }
 }
@@ -0,0 +1,245 @@
+//! Groups tokens enclosed in `()`, `[]` and `{}` into trees, so that
+//! tools can skip whole groups and the parser gets balanced input.
+
+use crate::{
+    location::{HasLocation, Location},
+    token::{LexError, Punctuator, RawLexError, RawToken, Token},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Delimiter {
+    /// `(` and `)`.
+    Parenthesis,
+    /// `[` and `]`.
+    Bracket,
+    /// `{` and `}`.
+    Brace,
+}
+
+impl Delimiter {
+    #[inline]
+    #[must_use]
+    pub const fn open(&self) -> Punctuator {
+        match self {
+            Self::Parenthesis => Punctuator::OpenParent,
+            Self::Bracket => Punctuator::OpenBracket,
+            Self::Brace => Punctuator::OpenBrace,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn close(&self) -> Punctuator {
+        match self {
+            Self::Parenthesis => Punctuator::CloseParent,
+            Self::Bracket => Punctuator::CloseBracket,
+            Self::Brace => Punctuator::CloseBrace,
+        }
+    }
+
+    /// Returns delimiter the punctuator opens, e.g. [`Delimiter::Brace`]
+    /// for `{`.
+    #[must_use]
+    pub const fn opened_by(punctuator: Punctuator) -> Option<Self> {
+        match punctuator {
+            Punctuator::OpenParent => Some(Self::Parenthesis),
+            Punctuator::OpenBracket => Some(Self::Bracket),
+            Punctuator::OpenBrace => Some(Self::Brace),
+            _ => None,
+        }
+    }
+
+    /// Returns delimiter the punctuator closes, e.g. [`Delimiter::Brace`]
+    /// for `}`.
+    #[must_use]
+    pub const fn closed_by(punctuator: Punctuator) -> Option<Self> {
+        match punctuator {
+            Punctuator::CloseParent => Some(Self::Parenthesis),
+            Punctuator::CloseBracket => Some(Self::Bracket),
+            Punctuator::CloseBrace => Some(Self::Brace),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenTree {
+    /// Any token except delimiters.
+    Leaf(Token),
+    /// Tokens between matching delimiters, e.g. `(a, b)`. The location
+    /// covers the delimiters as well.
+    Group {
+        delimiter: Delimiter,
+        tokens: Vec<TokenTree>,
+        location: Location,
+    },
+}
+
+impl HasLocation for TokenTree {
+    fn location(&self) -> Location {
+        match self {
+            Self::Leaf(token) => token.location(),
+            Self::Group { location, .. } => *location,
+        }
+    }
+}
+
+/// Group that is not closed yet.
+struct OpenGroup {
+    delimiter: Delimiter,
+    open: Token,
+    tokens: Vec<TokenTree>,
+}
+
+impl OpenGroup {
+    fn close(self, end: Location) -> TokenTree {
+        TokenTree::Group {
+            delimiter: self.delimiter,
+            tokens: self.tokens,
+            location: self.open.location().to(end),
+        }
+    }
+}
+
+/// Groups tokens into trees. Closing delimiters that don't match the last
+/// opened one are reported as [`RawLexError::UnexpectedClosingDelimiter`]
+/// and skipped, groups that are never closed are reported as
+/// [`RawLexError::UnclosedDelimiter`] and end at their last token.
+#[must_use]
+pub fn group_tokens(tokens: impl IntoIterator<Item = Token>) -> (Vec<TokenTree>, Vec<LexError>) {
+    let mut trees = Vec::new();
+    let mut open_groups: Vec<OpenGroup> = Vec::new();
+    let mut errors = Vec::new();
+
+    for token in tokens {
+        let RawToken::Punctuator(punctuator) = token.raw() else {
+            push(&mut open_groups, &mut trees, TokenTree::Leaf(token));
+            continue;
+        };
+
+        if let Some(delimiter) = Delimiter::opened_by(punctuator) {
+            open_groups.push(OpenGroup {
+                delimiter,
+                open: token,
+                tokens: Vec::new(),
+            });
+        } else if let Some(delimiter) = Delimiter::closed_by(punctuator) {
+            match open_groups.pop() {
+                Some(group) if group.delimiter == delimiter => {
+                    let tree = group.close(token.location());
+                    push(&mut open_groups, &mut trees, tree);
+                }
+                group => {
+                    open_groups.extend(group);
+                    errors.push(LexError::new(
+                        RawLexError::UnexpectedClosingDelimiter,
+                        token.location(),
+                    ));
+                }
+            }
+        } else {
+            push(&mut open_groups, &mut trees, TokenTree::Leaf(token));
+        }
+    }
+
+    while let Some(group) = open_groups.pop() {
+        errors.push(LexError::new(
+            RawLexError::UnclosedDelimiter,
+            group.open.location(),
+        ));
+
+        let end = group
+            .tokens
+            .last()
+            .map_or(group.open.location(), HasLocation::location);
+        let tree = group.close(end);
+        push(&mut open_groups, &mut trees, tree);
+    }
+
+    (trees, errors)
+}
+
+/// Adds the tree to the innermost open group, or to the top level.
+fn push(open_groups: &mut [OpenGroup], trees: &mut Vec<TokenTree>, tree: TokenTree) {
+    match open_groups.last_mut() {
+        Some(group) => group.tokens.push(tree),
+        None => trees.push(tree),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn group(source: &str) -> (Vec<TokenTree>, Vec<LexError>) {
+        group_tokens(tokenize("test.lzr", source))
+    }
+
+    #[test]
+    fn balanced() {
+        let (trees, errors) = group("{ (a) b }");
+
+        assert!(errors.is_empty());
+
+        let [TokenTree::Group {
+            delimiter: Delimiter::Brace,
+            tokens,
+            location,
+        }] = trees.as_slice()
+        else {
+            panic!("expected brace group");
+        };
+
+        assert_eq!(location.start().offset(), 0);
+        assert_eq!(location.end().offset(), 9);
+        assert!(matches!(
+            tokens.as_slice(),
+            [
+                TokenTree::Group {
+                    delimiter: Delimiter::Parenthesis,
+                    tokens: inner,
+                    ..
+                },
+                TokenTree::Leaf(_)
+            ] if inner.len() == 1
+        ));
+    }
+
+    #[test]
+    fn unbalanced() {
+        let (trees, errors) = group("a ] { ( b");
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| (error.raw(), error.location().start().offset()))
+                .collect::<Vec<_>>(),
+            [
+                (RawLexError::UnexpectedClosingDelimiter, 2),
+                (RawLexError::UnclosedDelimiter, 6),
+                (RawLexError::UnclosedDelimiter, 4),
+            ]
+        );
+
+        let [TokenTree::Leaf(_), TokenTree::Group {
+            tokens, location, ..
+        }] = trees.as_slice()
+        else {
+            panic!("expected leaf and group");
+        };
+
+        assert_eq!(location.end().offset(), 9);
+        assert!(matches!(tokens.as_slice(), [TokenTree::Group { .. }]));
+    }
+
+    #[test]
+    fn mismatched() {
+        let (trees, errors) = group("(a]");
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].raw(), RawLexError::UnexpectedClosingDelimiter);
+        assert_eq!(errors[1].raw(), RawLexError::UnclosedDelimiter);
+        assert_eq!(trees.len(), 1);
+    }
+}
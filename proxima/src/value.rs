@@ -1,4 +1,956 @@
-#[derive(Debug, Clone)]
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    mem,
+    rc::Rc,
+};
+
+use crate::{
+    ast::FunctionDeclaration,
+    interner::IdentifierId,
+    interpreter::RuntimeError,
+    to_source::write_value,
+    token::{Punctuator, RawToken, Token},
+};
+
+/// Runtime value. Arrays are compared element-wise, functions by identity.
+///
+/// Numbers are either integers or floats. Arithmetic on two integers gives
+/// an integer, unless it is a division or a negative power, and any float
+/// operand makes the result a float. Integer overflow is an error rather
+/// than a float, which would lose precision. Bitwise operators require
+/// integers.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
+    Int(i64),
+    Float(f64),
     String(String),
+    Array(Vec<Value>),
+    Bool(bool),
+    Null,
+    Function(Rc<Function>),
+    Map(HashMap<MapKey, Value>),
+}
+
+/// Function created by evaluating its declaration, see [`Value::Function`].
+/// Variables of the function it was declared in are captured by value,
+/// so later assignments to them are not observed.
+#[derive(Debug)]
+pub struct Function {
+    declaration: FunctionDeclaration,
+    captured: Option<HashMap<IdentifierId, Value>>,
+}
+
+impl Function {
+    /// Creates function declared inside of another function, whose
+    /// variables are `captured`, or at the top level if `captured`
+    /// is `None`.
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        declaration: FunctionDeclaration,
+        captured: Option<HashMap<IdentifierId, Value>>,
+    ) -> Self {
+        Self {
+            declaration,
+            captured,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn declaration(&self) -> &FunctionDeclaration {
+        &self.declaration
+    }
+
+    /// Variables captured where the function was declared, `None` for
+    /// top-level functions, which see global variables directly.
+    #[inline]
+    #[must_use]
+    pub const fn captured(&self) -> Option<&HashMap<IdentifierId, Value>> {
+        self.captured.as_ref()
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Options for [`Value::format_with`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Character inserted between groups of three digits in the integer
+    /// part of numbers, e.g. `_` gives `1_000_000`.
+    pub digit_separator: Option<char>,
+}
+
+impl Value {
+    /// Returns name of the value's type used in error messages,
+    /// e.g. `"integer"`.
+    #[inline]
+    #[must_use]
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "integer",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Bool(_) => "bool",
+            Value::Null => "null",
+            Value::Function(_) => "function",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Applies arithmetic prefix operator (`-` or `+`) to the value. Returns
+    /// `None` if the operator isn't supported for the value type, or if
+    /// the result overflows, like `-` of the smallest integer.
+    #[must_use]
+    pub fn unary_operation(&self, operator: Punctuator) -> Option<Value> {
+        match (operator, self) {
+            (Punctuator::Minus, Value::Int(number)) => number.checked_neg().map(Value::Int),
+            (Punctuator::Minus, Value::Float(number)) => Some(Value::Float(-number)),
+            (Punctuator::Plus, Value::Int(_) | Value::Float(_)) => Some(self.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns `false` for `false` and `null`, and `true` for all other
+    /// values, including `0` and empty strings.
+    #[inline]
+    #[must_use]
+    pub const fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Bool(false) | Value::Null)
+    }
+
+    /// Compares values as `==` does. Unlike [`PartialEq`], integers and
+    /// floats are compared by value, e.g. `1 == 1.0`, also inside of arrays
+    /// and maps. Two integers are compared exactly, and only an integer and
+    /// a float are compared as floats.
+    #[must_use]
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int(lhs), Value::Int(rhs)) => lhs == rhs,
+            (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+                self.as_f64() == other.as_f64()
+            }
+            (Value::Array(lhs), Value::Array(rhs)) => {
+                lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| lhs.equals(rhs))
+            }
+            (Value::Map(lhs), Value::Map(rhs)) => {
+                lhs.len() == rhs.len()
+                    && lhs
+                        .iter()
+                        .all(|(key, lhs)| rhs.get(key).is_some_and(|rhs| lhs.equals(rhs)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Compares numbers as `<` and other relational operators do. Two
+    /// integers are compared exactly, and only an integer and a float are
+    /// compared as floats. Returns `None` if any of the values is not
+    /// a number or is `NaN`.
+    #[must_use]
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(lhs), Value::Int(rhs)) => Some(lhs.cmp(rhs)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+
+    /// Returns the number as a float, or `None` if the value is not
+    /// a number.
+    #[inline]
+    #[must_use]
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(number) => Some(*number as f64),
+            Value::Float(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// Applies arithmetic or bitwise binary operator to the values. Returns
+    /// `None` if the operator isn't supported for the operand types, or if
+    /// the result is undefined, like integer remainder of division by zero,
+    /// or overflows.
+    #[must_use]
+    pub fn binary_operation(&self, operator: Punctuator, rhs: &Value) -> Option<Value> {
+        if is_bitwise(operator) {
+            return self.bitwise_operation(operator, rhs).ok();
+        }
+
+        match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => match operator {
+                Punctuator::Slash => float_operation(*lhs as f64, operator, *rhs as f64),
+                Punctuator::DoubleAsterisk if *rhs < 0 => {
+                    float_operation(*lhs as f64, operator, *rhs as f64)
+                }
+                _ => integer_operation(*lhs, operator, *rhs),
+            },
+            (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+                float_operation(self.as_f64()?, operator, rhs.as_f64()?)
+            }
+            (Value::String(lhs), Value::String(rhs)) if operator == Punctuator::Plus => {
+                Some(Value::String(format!("{lhs}{rhs}")))
+            }
+            _ => None,
+        }
+    }
+
+    /// Applies bitwise or shift operator (`&`, `|`, `^`, `<<`, `>>` or `>>>`)
+    /// to the values. `>>` is an arithmetic shift, which keeps the sign,
+    /// while `>>>` is a logical one.
+    ///
+    /// # Errors
+    /// Returns [`RuntimeError::TypeMismatch`] if any of the operands is not
+    /// an integer, and [`RuntimeError::InvalidShift`] if the shift amount
+    /// is negative or not less than 64.
+    ///
+    /// # Panics
+    /// Panics if `operator` is not bitwise.
+    pub fn bitwise_operation(
+        &self,
+        operator: Punctuator,
+        rhs: &Value,
+    ) -> Result<Value, RuntimeError> {
+        let (lhs, rhs) = (self.as_integer()?, rhs.as_integer()?);
+
+        let shift = || {
+            u32::try_from(rhs)
+                .ok()
+                .filter(|amount| *amount < i64::BITS)
+                .ok_or(RuntimeError::InvalidShift { amount: rhs })
+        };
+
+        Ok(Value::Int(match operator {
+            Punctuator::Ampersand => lhs & rhs,
+            Punctuator::Bar => lhs | rhs,
+            Punctuator::Caret => lhs ^ rhs,
+            Punctuator::LeftShift => lhs << shift()?,
+            Punctuator::RightShift => lhs >> shift()?,
+            Punctuator::TripleGreater => ((lhs as u64) >> shift()?) as i64,
+            _ => panic!("`{operator}` is not a bitwise operator"),
+        }))
+    }
+
+    /// Returns the integer, or [`RuntimeError::TypeMismatch`] if the value
+    /// is not an integer.
+    fn as_integer(&self) -> Result<i64, RuntimeError> {
+        match self {
+            Value::Int(number) => Ok(*number),
+            _ => Err(RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: self.type_name(),
+            }),
+        }
+    }
+
+    /// Returns element of the array at the given index. Returns `None` if
+    /// the index is out of bounds or the value is not an array.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        match self {
+            Value::Array(elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    /// Returns element of the array, or character of the string as a
+    /// string, at the given index, as done by index expressions, e.g.
+    /// `array[0]` or `"hello"[1]`. Strings are indexed by characters,
+    /// not bytes.
+    ///
+    /// # Errors
+    /// Returns [`RuntimeError::TypeMismatch`] if the value is not an array,
+    /// a string or a map, or the index is not an integer,
+    /// [`RuntimeError::IndexOutOfBounds`] if there is no such element, and
+    /// [`RuntimeError::KeyNotFound`] if the map has no such key.
+    pub fn index(&self, index: &Value) -> Result<Value, RuntimeError> {
+        if let Value::Map(entries) = self {
+            let key = MapKey::try_from(index.clone())?;
+
+            return entries
+                .get(&key)
+                .cloned()
+                .ok_or_else(|| RuntimeError::KeyNotFound {
+                    key: match index {
+                        // `1.0` is displayed as `1`, hiding that it is a float.
+                        Value::Float(number) => format!("{number:?}"),
+                        _ => index.to_string(),
+                    },
+                });
+        }
+
+        let index = index.as_integer()?;
+        let element = usize::try_from(index).ok().and_then(|idx| match self {
+            Value::Array(elements) => elements.get(idx).cloned(),
+            Value::String(string) => string
+                .chars()
+                .nth(idx)
+                .map(|c| Value::String(c.to_string())),
+            _ => None,
+        });
+
+        element.ok_or_else(|| match self {
+            Value::Array(elements) => RuntimeError::IndexOutOfBounds {
+                index,
+                length: elements.len(),
+            },
+            Value::String(string) => RuntimeError::IndexOutOfBounds {
+                index,
+                length: string.chars().count(),
+            },
+            _ => RuntimeError::TypeMismatch {
+                expected: "array, string or map",
+                found: self.type_name(),
+            },
+        })
+    }
+
+    /// Formats the value according to the given options. With default options
+    /// the result is the same as of [`Display`].
+    #[must_use]
+    pub fn format_with(&self, options: FormatOptions) -> String {
+        match (self, options.digit_separator) {
+            (Value::Int(_) | Value::Float(_), Some(separator)) => {
+                group_digits(&self.to_string(), separator)
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+const fn is_bitwise(operator: Punctuator) -> bool {
+    matches!(
+        operator,
+        Punctuator::Ampersand
+            | Punctuator::Bar
+            | Punctuator::Caret
+            | Punctuator::LeftShift
+            | Punctuator::RightShift
+            | Punctuator::TripleGreater
+    )
+}
+
+/// Applies arithmetic operator to integers. Returns `None` if the result
+/// overflows. Division always gives a float, e.g. `7 / 2` is `3.5`, so it
+/// is left to [`float_operation`], as well as negative powers.
+fn integer_operation(lhs: i64, operator: Punctuator, rhs: i64) -> Option<Value> {
+    match operator {
+        Punctuator::Plus => lhs.checked_add(rhs),
+        Punctuator::Minus => lhs.checked_sub(rhs),
+        Punctuator::Asterisk => lhs.checked_mul(rhs),
+        Punctuator::Percent if rhs == 0 => return None,
+        Punctuator::Percent => lhs.checked_rem(rhs),
+        // Exponents beyond `u32` overflow for any base but 0, 1 and -1,
+        // for which only the parity of the exponent matters.
+        Punctuator::DoubleAsterisk => {
+            lhs.checked_pow(u32::try_from(rhs).unwrap_or(u32::MAX - u32::from(rhs % 2 == 0)))
+        }
+        _ => None,
+    }
+    .map(Value::Int)
+}
+
+fn float_operation(lhs: f64, operator: Punctuator, rhs: f64) -> Option<Value> {
+    Some(Value::Float(match operator {
+        Punctuator::Plus => lhs + rhs,
+        Punctuator::Minus => lhs - rhs,
+        Punctuator::Asterisk => lhs * rhs,
+        Punctuator::Slash => lhs / rhs,
+        Punctuator::Percent if rhs == 0.0 => return None,
+        Punctuator::Percent => lhs % rhs,
+        Punctuator::DoubleAsterisk => lhs.powf(rhs),
+        _ => return None,
+    }))
+}
+
+/// Inserts `separator` between groups of three digits in the integer part
+/// of a formatted number, e.g. `-1234.5` becomes `-1_234.5`.
+fn group_digits(number: &str, separator: char) -> String {
+    let (sign, unsigned) = number.split_at(usize::from(number.starts_with('-')));
+    let integer_part_len = unsigned.find('.').unwrap_or(unsigned.len());
+
+    let mut result = String::from(sign);
+
+    for (idx, c) in unsigned.char_indices() {
+        if idx > 0 && idx < integer_part_len && (integer_part_len - idx) % 3 == 0 {
+            result.push(separator);
+        }
+
+        result.push(c);
+    }
+
+    result
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(number) => write!(f, "{number}"),
+            Value::Float(number) => write!(f, "{number}"),
+            Value::String(string) => write!(f, "{string}"),
+            Value::Array(elements) => {
+                f.write_str("[")?;
+
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{element}")?;
+                }
+
+                f.write_str("]")
+            }
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Null => f.write_str("null"),
+            Value::Function(function) => write!(f, "<func {}>", function.declaration().name()),
+            Value::Map(entries) => {
+                // Iteration order of a hash map is arbitrary, so entries are
+                // sorted to keep the output stable. String keys are quoted,
+                // so that `"1"` and `1` are told apart.
+                let mut entries = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = match key.value() {
+                            Value::String(_) => {
+                                let mut quoted = String::new();
+                                write_value(&mut quoted, key.value());
+                                quoted
+                            }
+                            key => key.to_string(),
+                        };
+
+                        (key, value)
+                    })
+                    .collect::<Vec<_>>();
+                entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+                f.write_str("{")?;
+
+                for (idx, (key, value)) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    write!(f, "{key}: {value}")?;
+                }
+
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Value usable as a key of a hash map. Unlike [`Value`], it implements
+/// [`Eq`], as values containing `NaN` are rejected.
+///
+/// Keys equal by `==` are the same key, e.g. `m[1.0]` gets the entry with
+/// key `1`, so integral floats are converted into integers, also inside of
+/// arrays and maps, e.g. `{1.0: a}` is `{1: a}`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapKey(Value);
+
+impl MapKey {
+    #[inline]
+    #[must_use]
+    pub const fn value(&self) -> &Value {
+        &self.0
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_value(self) -> Value {
+        self.0
+    }
+}
+
+impl Eq for MapKey {}
+
+impl TryFrom<Value> for MapKey {
+    type Error = RuntimeError;
+
+    /// # Errors
+    /// Returns [`RuntimeError::NanMapKey`] if the value is `NaN`, or an
+    /// array or a map containing it.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        fn contains_nan(value: &Value) -> bool {
+            match value {
+                Value::Float(number) => number.is_nan(),
+                Value::Array(elements) => elements.iter().any(contains_nan),
+                Value::Map(entries) => entries.values().any(contains_nan),
+                _ => false,
+            }
+        }
+
+        fn normalize(value: Value) -> Value {
+            match value {
+                Value::Float(number)
+                    if number.fract() == 0.0
+                        && (i64::MIN as f64..i64::MAX as f64).contains(&number) =>
+                {
+                    Value::Int(number as i64)
+                }
+                Value::Array(elements) => {
+                    Value::Array(elements.into_iter().map(normalize).collect())
+                }
+                Value::Map(entries) => Value::Map(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (key, normalize(value)))
+                        .collect(),
+                ),
+                _ => value,
+            }
+        }
+
+        if contains_nan(&value) {
+            return Err(RuntimeError::NanMapKey);
+        }
+
+        Ok(Self(normalize(value)))
+    }
+}
+
+/// Hashes the variant and the content, consistently with [`PartialEq`]:
+/// functions are hashed by identity and maps only by their length, as
+/// their iteration order is arbitrary. Floats are never integral, and so
+/// never `-0.0`, which would have to hash like `0.0`.
+impl Hash for MapKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+            mem::discriminant(value).hash(state);
+
+            match value {
+                Value::Int(number) => number.hash(state),
+                Value::Float(number) => number.to_bits().hash(state),
+                Value::String(string) => string.hash(state),
+                Value::Array(elements) => {
+                    elements.len().hash(state);
+
+                    for element in elements {
+                        hash_value(element, state);
+                    }
+                }
+                Value::Bool(value) => value.hash(state),
+                Value::Null => {}
+                Value::Function(function) => Rc::as_ptr(function).hash(state),
+                Value::Map(entries) => entries.len().hash(state),
+            }
+        }
+
+        hash_value(&self.0, state);
+    }
+}
+
+/// Converts number literals into values, e.g. to back numbers with
+/// arbitrary-precision math. The methods receive the text of the literal as
+/// written, e.g. `1_000`, and return `None` to keep the number scanned by
+/// the lexer. See [`crate::parser::Parser::with_number_parser`].
+pub trait NumberParser: Debug {
+    /// Converts integer literal, e.g. `42` or `0xFF`.
+    #[must_use]
+    fn parse_int(&self, _text: &str) -> Option<Value> {
+        None
+    }
+
+    /// Converts float literal, e.g. `1.5` or `1e3`, including integer ones
+    /// too large for [`Value::Int`].
+    #[must_use]
+    fn parse_float(&self, _text: &str) -> Option<Value> {
+        None
+    }
+}
+
+/// Number parser used by default, producing [`Value::Int`]
+/// and [`Value::Float`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DefaultNumberParser;
+
+impl NumberParser for DefaultNumberParser {}
+
+/// Implements `From<$type>` for [`Value`] wrapping it into `$variant`.
+macro_rules! value_from {
+    ($($type:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$type> for Value {
+                #[inline]
+                fn from(value: $type) -> Self {
+                    Value::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+value_from! {
+    i64 => Int,
+    f64 => Float,
+    bool => Bool,
+    String => String,
+    Vec<Value> => Array,
+}
+
+/// Converts literal token into a value. Returns the token back,
+/// if it isn't a number or a string literal.
+impl TryFrom<Token> for Value {
+    type Error = Token;
+
+    fn try_from(token: Token) -> Result<Self, Self::Error> {
+        match token.raw() {
+            RawToken::Int(number) => Ok(Value::Int(number)),
+            RawToken::Float(number) => Ok(Value::Float(number)),
+            RawToken::Text(text) => Ok(Value::String(text.into())),
+            _ => Err(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interner::{PathId, StringId},
+        location::Location,
+        token::{Punctuator, RawToken},
+    };
+
+    #[test]
+    fn from_primitives() {
+        assert_eq!(Value::from(3), Value::Int(3));
+        assert_eq!(Value::from(3.0), Value::Float(3.0));
+        assert_eq!(Value::from(true), Value::Bool(true));
+        assert_eq!(Value::from("a".to_owned()), Value::String("a".to_owned()));
+        assert_eq!(
+            Value::from(vec![Value::from(1), Value::from(false)]),
+            Value::Array(vec![Value::Int(1), Value::Bool(false)])
+        );
+    }
+
+    #[test]
+    fn number_token_to_value() {
+        let token = Token::new(
+            RawToken::Float(3.5),
+            Location::of_first_byte(PathId::from("test.lzr")),
+        );
+
+        assert!(matches!(Value::try_from(token), Ok(Value::Float(n)) if n == 3.5));
+    }
+
+    #[test]
+    fn text_token_to_value() {
+        let token = Token::new(
+            RawToken::Text(StringId::from("hello")),
+            Location::of_first_byte(PathId::from("test.lzr")),
+        );
+
+        assert!(matches!(Value::try_from(token), Ok(Value::String(s)) if s == "hello"));
+    }
+
+    #[test]
+    fn punctuator_token_is_not_a_value() {
+        let token = Token::new(
+            Punctuator::Plus.into(),
+            Location::of_first_byte(PathId::from("test.lzr")),
+        );
+
+        assert_eq!(Value::try_from(token).unwrap_err(), token);
+    }
+
+    #[test]
+    fn display_is_ungrouped() {
+        assert_eq!(Value::Int(1000000).to_string(), "1000000");
+        assert_eq!(
+            Value::Int(1000000).format_with(FormatOptions::default()),
+            "1000000"
+        );
+    }
+
+    #[test]
+    fn digit_grouping() {
+        let options = FormatOptions {
+            digit_separator: Some('_'),
+        };
+
+        assert_eq!(Value::Int(1000000).format_with(options), "1_000_000");
+        assert_eq!(Value::Float(-1234.5).format_with(options), "-1_234.5");
+        assert_eq!(Value::Int(123).format_with(options), "123");
+        assert_eq!(
+            Value::String("1000".to_owned()).format_with(options),
+            "1000"
+        );
+    }
+
+    fn array() -> Value {
+        Value::Array(vec![
+            Value::Int(1),
+            Value::String("a".to_owned()),
+            Value::Array(vec![Value::Int(2)]),
+        ])
+    }
+
+    #[test]
+    fn unary_operations() {
+        assert_eq!(
+            Value::Int(5).unary_operation(Punctuator::Minus),
+            Some(Value::Int(-5))
+        );
+        assert_eq!(
+            Value::Int(5).unary_operation(Punctuator::Plus),
+            Some(Value::Int(5))
+        );
+        assert_eq!(
+            Value::String("a".to_owned()).unary_operation(Punctuator::Minus),
+            None
+        );
+        assert_eq!(
+            Value::Int(i64::MIN).unary_operation(Punctuator::Minus),
+            None
+        );
+    }
+
+    #[test]
+    fn integer_arithmetic() {
+        let int = |lhs: i64, operator, rhs: i64| {
+            Value::Int(lhs).binary_operation(operator, &Value::Int(rhs))
+        };
+
+        assert_eq!(int(2, Punctuator::Plus, 3), Some(Value::Int(5)));
+        assert_eq!(int(7, Punctuator::Percent, 4), Some(Value::Int(3)));
+        assert_eq!(
+            int(2, Punctuator::DoubleAsterisk, 10),
+            Some(Value::Int(1024))
+        );
+        assert_eq!(int(7, Punctuator::Slash, 2), Some(Value::Float(3.5)));
+        assert_eq!(
+            int(2, Punctuator::DoubleAsterisk, -1),
+            Some(Value::Float(0.5))
+        );
+        assert_eq!(int(i64::MAX, Punctuator::Plus, 1), None);
+        assert_eq!(int(i64::MIN, Punctuator::Asterisk, -1), None);
+        assert_eq!(int(2, Punctuator::DoubleAsterisk, 64), None);
+        assert_eq!(
+            int(-1, Punctuator::DoubleAsterisk, 1 << 40),
+            Some(Value::Int(1))
+        );
+        assert_eq!(
+            int(-1, Punctuator::DoubleAsterisk, (1 << 40) + 1),
+            Some(Value::Int(-1))
+        );
+        assert_eq!(int(1, Punctuator::Percent, 0), None);
+        assert_eq!(
+            Value::Int(1).binary_operation(Punctuator::Plus, &Value::Float(0.5)),
+            Some(Value::Float(1.5))
+        );
+    }
+
+    #[test]
+    fn bitwise_operations_require_integers() {
+        assert_eq!(
+            Value::Int(3).binary_operation(Punctuator::Ampersand, &Value::Int(1)),
+            Some(Value::Int(1))
+        );
+        assert_eq!(
+            Value::Float(3.5).binary_operation(Punctuator::Ampersand, &Value::Int(1)),
+            None
+        );
+        assert_eq!(
+            Value::Float(3.5).bitwise_operation(Punctuator::Ampersand, &Value::Int(1)),
+            Err(RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: "float"
+            })
+        );
+        assert_eq!(
+            Value::Int(1).bitwise_operation(Punctuator::LeftShift, &Value::Int(64)),
+            Err(RuntimeError::InvalidShift { amount: 64 })
+        );
+    }
+
+    #[test]
+    fn truthiness_and_equality() {
+        assert!(Value::Int(0).is_truthy());
+        assert!(Value::String(String::new()).is_truthy());
+        assert!(!Value::Bool(false).is_truthy());
+        assert!(!Value::Null.is_truthy());
+
+        assert!(Value::Int(1).equals(&Value::Float(1.0)));
+        assert!(!Value::Int(1).equals(&Value::String("1".to_owned())));
+        assert!(array().equals(&array()));
+        assert!(!Value::Int(9_007_199_254_740_993).equals(&Value::Int(9_007_199_254_740_992)));
+        assert!(Value::Array(vec![Value::Int(1)]).equals(&Value::Array(vec![Value::Float(1.0)])));
+        assert!(!Value::Array(vec![Value::Int(1)]).equals(&Value::Array(vec![])));
+    }
+
+    #[test]
+    fn comparison() {
+        assert_eq!(
+            Value::Int(9_007_199_254_740_993).compare(&Value::Int(9_007_199_254_740_992)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            Value::Int(1).compare(&Value::Float(1.5)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(Value::Float(f64::NAN).compare(&Value::Int(1)), None);
+        assert_eq!(Value::Null.compare(&Value::Int(1)), None);
+    }
+
+    #[test]
+    fn array_indexing() {
+        let array = array();
+
+        assert_eq!(array.get(0), Some(&Value::Int(1)));
+        assert_eq!(
+            array.index(&Value::Int(1)),
+            Ok(Value::String("a".to_owned()))
+        );
+        assert_eq!(
+            array.index(&Value::Int(2)),
+            Ok(Value::Array(vec![Value::Int(2)]))
+        );
+        assert_eq!(array.get(3), None);
+        assert_eq!(
+            array.index(&Value::Int(3)),
+            Err(RuntimeError::IndexOutOfBounds {
+                index: 3,
+                length: 3
+            })
+        );
+        assert_eq!(
+            array.index(&Value::Int(-1)),
+            Err(RuntimeError::IndexOutOfBounds {
+                index: -1,
+                length: 3
+            })
+        );
+    }
+
+    #[test]
+    fn string_indexing() {
+        let string = Value::from("héllo".to_owned());
+
+        assert_eq!(
+            string.index(&Value::Int(1)),
+            Ok(Value::from("é".to_owned()))
+        );
+        assert_eq!(
+            string.index(&Value::Int(4)),
+            Ok(Value::from("o".to_owned()))
+        );
+        assert_eq!(
+            string.index(&Value::Int(5)),
+            Err(RuntimeError::IndexOutOfBounds {
+                index: 5,
+                length: 5
+            })
+        );
+        assert_eq!(
+            string.index(&Value::Float(0.0)),
+            Err(RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: "float"
+            })
+        );
+        assert_eq!(
+            Value::Int(1).index(&Value::Int(0)),
+            Err(RuntimeError::TypeMismatch {
+                expected: "array, string or map",
+                found: "integer"
+            })
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(Value::Int(1).type_name(), "integer");
+        assert_eq!(Value::Float(1.0).type_name(), "float");
+        assert_eq!(Value::String(String::new()).type_name(), "string");
+        assert_eq!(Value::Array(Vec::new()).type_name(), "array");
+        assert_eq!(Value::Bool(true).type_name(), "bool");
+        assert_eq!(Value::Null.type_name(), "null");
+        assert_eq!(
+            RuntimeError::TypeMismatch {
+                expected: "number",
+                found: Value::Null.type_name()
+            }
+            .to_string(),
+            "expected number, found null"
+        );
+    }
+
+    #[test]
+    fn array_equality() {
+        assert_eq!(array(), array());
+        assert_ne!(array(), Value::Array(vec![Value::Int(1)]));
+        assert_eq!(array().to_string(), "[1, a, [2]]");
+    }
+
+    #[test]
+    fn map_display() {
+        let map = Value::Map(HashMap::from([
+            (
+                MapKey::try_from(Value::Int(1)).unwrap(),
+                Value::from("a".to_owned()),
+            ),
+            (
+                MapKey::try_from(Value::from("1".to_owned())).unwrap(),
+                Value::from("b".to_owned()),
+            ),
+            (
+                MapKey::try_from(Value::from("\"".to_owned())).unwrap(),
+                Value::Null,
+            ),
+        ]));
+
+        assert_eq!(map.to_string(), r#"{"1": b, "\"": null, 1: a}"#);
+    }
+
+    #[test]
+    fn hash_map_keys() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let hasher = RandomState::new();
+        let hash = |value: Value| hasher.hash_one(MapKey::try_from(value).unwrap());
+
+        assert_eq!(
+            hash(Value::from("key".to_owned())),
+            hash(Value::from("key".to_owned()))
+        );
+        assert_eq!(hash(Value::Float(0.0)), hash(Value::Float(-0.0)));
+        assert_eq!(hash(Value::Float(1.0)), hash(Value::Int(1)));
+        assert_eq!(
+            MapKey::try_from(Value::Float(1.0)),
+            MapKey::try_from(Value::Int(1))
+        );
+        assert_eq!(
+            MapKey::try_from(Value::Array(vec![Value::Float(-0.0)])),
+            MapKey::try_from(Value::Array(vec![Value::Int(0)]))
+        );
+        assert_ne!(
+            MapKey::try_from(Value::Float(1.5)),
+            MapKey::try_from(Value::Int(1))
+        );
+        assert_eq!(
+            MapKey::try_from(Value::Float(1e300)).map(MapKey::into_value),
+            Ok(Value::Float(1e300))
+        );
+
+        assert_eq!(
+            MapKey::try_from(Value::Float(f64::NAN)),
+            Err(RuntimeError::NanMapKey)
+        );
+        assert_eq!(
+            MapKey::try_from(Value::Array(vec![Value::Float(f64::NAN)])),
+            Err(RuntimeError::NanMapKey)
+        );
+    }
 }
@@ -0,0 +1,112 @@
+use std::{
+    env, fs,
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn proxima(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_proxima"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn check_clean_file() {
+    let output = proxima(&["check", "tests/fixtures/clean.lzr"]);
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn check_file_with_syntax_error() {
+    let output = proxima(&["check", "--no-color", "tests/fixtures/syntax_error.lzr"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr.starts_with("error: expected `;`, found `}`"));
+    assert!(stderr.contains("--> tests/fixtures/syntax_error.lzr:3:1"));
+}
+
+#[test]
+fn check_missing_file() {
+    assert_eq!(proxima(&["check", "missing.lzr"]).status.code(), Some(2));
+}
+
+#[test]
+fn fmt_check_formatted_file() {
+    let output = proxima(&["fmt", "--check", "tests/fixtures/formatted.lzr"]);
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn fmt_check_unformatted_file() {
+    let output = proxima(&["fmt", "tests/fixtures/unformatted.lzr", "--check"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "`tests/fixtures/unformatted.lzr` is not formatted\n"
+    );
+}
+
+#[test]
+fn fmt_is_idempotent() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let formatted =
+        fs::read_to_string(format!("{manifest_dir}/tests/fixtures/formatted.lzr")).unwrap();
+    let file = env::temp_dir().join(format!("proxima-fmt-{}.lzr", std::process::id()));
+
+    fs::copy(
+        format!("{manifest_dir}/tests/fixtures/unformatted.lzr"),
+        &file,
+    )
+    .unwrap();
+    let path = file.to_str().unwrap();
+
+    assert!(proxima(&["fmt", path]).status.success());
+    assert_eq!(fs::read_to_string(&file).unwrap(), formatted);
+
+    assert!(proxima(&["fmt", path]).status.success());
+    assert_eq!(fs::read_to_string(&file).unwrap(), formatted);
+
+    fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn fmt_file_with_syntax_error() {
+    let output = proxima(&["fmt", "--no-color", "tests/fixtures/syntax_error.lzr"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("error: expected `;`, found `}`"));
+}
+
+#[test]
+fn repl_prints_values_of_bare_expressions() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_proxima"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"1 + 1\nx = 5;\nprintln x * 2;\nx\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2\n10\n5\n");
+    assert!(output.stderr.is_empty());
+}
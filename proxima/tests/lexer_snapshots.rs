@@ -0,0 +1,60 @@
+use proxima::{
+    interner::PathId,
+    lexer::{tokenize_with_trivia, Lexer},
+    location::HasLocation,
+    token::{RawToken, Token},
+};
+
+/// Renders token with resolved identifiers and strings, because interned ids
+/// depend on the order in which tests run.
+fn render(token: Token) -> String {
+    let raw = match token.raw() {
+        RawToken::Identifier(id) => format!("Identifier({:?})", String::from(id)),
+        RawToken::Text(id) => format!("Text({:?})", String::from(id)),
+        raw => format!("{raw:?}"),
+    };
+    let start = token.location().start();
+    let end = token.location().end();
+
+    format!(
+        "{raw} @ {}:{}..{}:{}",
+        start.line(),
+        start.column(),
+        end.line(),
+        end.column()
+    )
+}
+
+fn lex(fixture: &str, source: &str) -> String {
+    Lexer::new(PathId::from(fixture), source)
+        .map(render)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn operators() {
+    insta::assert_snapshot!(lex("operators.lzr", include_str!("fixtures/operators.lzr")));
+}
+
+#[test]
+fn numbers() {
+    insta::assert_snapshot!(lex("numbers.lzr", include_str!("fixtures/numbers.lzr")));
+}
+
+#[test]
+fn strings() {
+    insta::assert_snapshot!(lex("strings.lzr", include_str!("fixtures/strings.lzr")));
+}
+
+#[test]
+fn comments() {
+    insta::assert_snapshot!(tokenize_with_trivia(
+        "comments.lzr",
+        include_str!("fixtures/comments.lzr")
+    )
+    .into_iter()
+    .map(render)
+    .collect::<Vec<_>>()
+    .join("\n"));
+}